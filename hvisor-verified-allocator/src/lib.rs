@@ -1,3 +1,8 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub mod original;
 pub mod verified_impl;
 // pub mod verified_proof; // If you want to compare the performance, please comment out this line first.
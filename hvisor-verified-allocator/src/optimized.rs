@@ -139,7 +139,7 @@ impl<T: BitAllocView + Copy> Clone for BitAllocCascade16<T> {
     }
 }
 
-impl<T: BitAllocView + std::marker::Copy> BitAllocView for BitAllocCascade16<T> {
+impl<T: BitAllocView + core::marker::Copy> BitAllocView for BitAllocCascade16<T> {
     const CAP: usize = T::CAP * 16;
 
     /// Creates a new `BitAllocCascade16` with all bits set to 0 (all free).
@@ -196,7 +196,7 @@ impl<T: BitAllocView + std::marker::Copy> BitAllocView for BitAllocCascade16<T>
     }
 }
 
-impl<T: BitAlloc + std::marker::Copy> BitAlloc for BitAllocCascade16<T> {
+impl<T: BitAlloc + core::marker::Copy> BitAlloc for BitAllocCascade16<T> {
     fn alloc(&mut self) -> Option<usize> {
         if !self.any() {
             return None;
@@ -1,4 +1,6 @@
-use std::ops::Range;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::ops::Range;
 
 /// A generic trait which provides methods for extracting and setting specific bits or ranges of
 /// bits.
@@ -6,7 +8,7 @@ pub trait BitField {
     /// Returns the length, eg number of bits, in this bit field.
     ///
     /// ```rust
-    /// use bit_field::BitField;
+    /// use hvisor_verified_allocator::original::BitField;
     ///
     /// assert_eq!(u32::bit_length(), 32);
     /// assert_eq!(u64::bit_length(), 64);
@@ -17,7 +19,7 @@ pub trait BitField {
     /// index `length() - 1` is the most significant bit.
     ///
     /// ```rust
-    /// use bit_field::BitField;
+    /// use hvisor_verified_allocator::original::BitField;
     ///
     /// let value: u32 = 0b110101;
     ///
@@ -34,12 +36,16 @@ pub trait BitField {
     /// bit, while index `length() - 1` is the most significant bit.
     ///
     /// ```rust
-    /// use bit_field::BitField;
+    /// use hvisor_verified_allocator::original::BitField;
     ///
     /// let value: u32 = 0b110101;
     ///
     /// assert_eq!(value.get_bits(0..3), 0b101);
     /// assert_eq!(value.get_bits(2..6), 0b1101);
+    ///
+    /// assert_eq!(0xa5u8.get_bits(0..8), 0xa5);
+    /// assert_eq!(u64::MAX.get_bits(0..64), u64::MAX);
+    /// assert_eq!(u64::MAX.get_bits(63..64), 1);
     /// ```
     ///
     /// ## Panics
@@ -53,7 +59,7 @@ pub trait BitField {
     /// `length() - 1` is the most significant bit.
     ///
     /// ```rust
-    /// use bit_field::BitField;
+    /// use hvisor_verified_allocator::original::BitField;
     ///
     /// let mut value = 0u32;
     ///
@@ -77,7 +83,7 @@ pub trait BitField {
     /// the other bits in `value` are set to 1, this function will panic.
     ///
     /// ```rust
-    /// use bit_field::BitField;
+    /// use hvisor_verified_allocator::original::BitField;
     ///
     /// let mut value = 0u32;
     ///
@@ -86,6 +92,14 @@ pub trait BitField {
     ///
     /// value.set_bits(0..4, 0b1010);
     /// assert_eq!(value, 0b1010);
+    ///
+    /// let mut byte = 0u8;
+    /// byte.set_bits(0..8, 0xff);
+    /// assert_eq!(byte, 0xff);
+    ///
+    /// let mut word = 0u64;
+    /// word.set_bits(32..64, 0xffff_ffff);
+    /// assert_eq!(word, 0xffff_ffff_0000_0000);
     /// ```
     ///
     /// ## Panics
@@ -100,7 +114,7 @@ macro_rules! bitfield_numeric_impl {
     ($($t:ty)*) => ($(
         impl BitField for $t {
             fn bit_length() -> usize {
-                ::std::mem::size_of::<Self>() as usize * 8
+                ::core::mem::size_of::<Self>() as usize * 8
             }
 
             fn get_bit(&self, bit: usize) -> bool {
@@ -154,7 +168,22 @@ macro_rules! bitfield_numeric_impl {
     )*)
 }
 
-bitfield_numeric_impl! { u16 }
+bitfield_numeric_impl! { u8 u16 u32 u64 u128 usize }
+
+/// Error of the bounds-checked `try_*` operations of `BitAlloc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfRange {
+    /// The key, or the size of the block, that exceeds the capacity.
+    pub value: usize,
+    /// Capacity of the allocator.
+    pub cap: usize,
+}
+
+impl core::fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} out of range 0..{}", self.value, self.cap)
+    }
+}
 
 /// Allocator of a bitmap, able to allocate / free bits.
 pub trait BitAlloc: Default {
@@ -165,20 +194,55 @@ pub trait BitAlloc: Default {
     #[allow(clippy::declare_interior_mutable_const)]
     const DEFAULT: Self;
 
+    #[cfg(feature = "alloc")]
     fn verieasy_get(&self) -> Vec<u16>;
 
+    /// Write the bitmap as 16-bit words into `out`, lowest bits first, and return the number of
+    /// words written. Panics if `out` is shorter than `CAP / 16`. Unlike `verieasy_get`, this does
+    /// not allocate.
+    fn write_bitmap(&self, out: &mut [u16]) -> usize;
+
     /// Allocate a free bit.
     fn alloc(&mut self) -> Option<usize>;
 
     /// Allocate a free block with a given size, and return the first bit position.
     fn alloc_contiguous(&mut self, size: usize, align_log2: usize) -> Option<usize>;
 
+    /// Allocate a single free bit aligned to `1 << align_log2`. Panics if `align_log2` is not
+    /// less than `usize::BITS`.
+    fn alloc_aligned(&mut self, align_log2: usize) -> Option<usize> {
+        assert!(align_log2 < usize::BITS as usize);
+        self.alloc_contiguous(1, align_log2)
+    }
+
+    /// Allocate a free block with a given size inside `region`, and return the first bit position.
+    /// Alignment is relative to bit 0, not to the start of the region.
+    fn alloc_contiguous_in(
+        &mut self,
+        region: Range<usize>,
+        size: usize,
+        align_log2: usize,
+    ) -> Option<usize> {
+        assert!(region.end <= Self::CAP);
+        let base = find_contiguous_in(self, region, size, align_log2)?;
+        self.remove(base..base + size);
+        Some(base)
+    }
+
     /// Find a index not less than a given key, where the bit is free.
     fn next(&self, key: usize) -> Option<usize>;
 
     /// Free an allocated bit.
     fn dealloc(&mut self, key: usize);
 
+    /// Free a block allocated by `alloc_contiguous`. Panics if any bit in the block is already free.
+    fn dealloc_contiguous(&mut self, base: usize, size: usize) {
+        for key in base..base + size {
+            assert!(!self.test(key), "double free of bit {}", key);
+        }
+        self.insert(base..base + size);
+    }
+
     /// Mark bits in the range as unallocated (available)
     fn insert(&mut self, range: Range<usize>);
 
@@ -190,6 +254,95 @@ pub trait BitAlloc: Default {
 
     /// Whether a specific bit is free
     fn test(&self, key: usize) -> bool;
+
+    /// Number of free bits remaining
+    fn count_free(&self) -> usize;
+
+    /// Lengths of the runs of consecutive free bits at both ends and of the longest one.
+    fn free_runs(&self) -> FreeRuns;
+
+    /// Length of the longest run of consecutive free bits, the largest block `alloc_contiguous`
+    /// could satisfy without alignment.
+    fn max_free_run(&self) -> usize {
+        self.free_runs().longest
+    }
+
+    /// Fragmentation of the free bits, `1 - max_free_run / count_free`: 0 if they form a single
+    /// run, close to 1 if they are scattered. 0 if no bit is free.
+    fn fragmentation(&self) -> f64 {
+        let free = self.count_free();
+        if free == 0 {
+            return 0.0;
+        }
+        1.0 - self.max_free_run() as f64 / free as f64
+    }
+
+    /// Free an allocated bit, or return an error if `key` is not less than `CAP`.
+    fn try_dealloc(&mut self, key: usize) -> Result<(), OutOfRange> {
+        check_key::<Self>(key)?;
+        self.dealloc(key);
+        Ok(())
+    }
+
+    /// Whether a specific bit is free, or an error if `key` is not less than `CAP`.
+    fn try_test(&self, key: usize) -> Result<bool, OutOfRange> {
+        check_key::<Self>(key)?;
+        Ok(self.test(key))
+    }
+
+    /// Allocate a free block like `alloc_contiguous`, or return an error if `size` exceeds `CAP`.
+    fn try_alloc_contiguous(
+        &mut self,
+        size: usize,
+        align_log2: usize,
+    ) -> Result<Option<usize>, OutOfRange> {
+        if size > Self::CAP {
+            return Err(OutOfRange {
+                value: size,
+                cap: Self::CAP,
+            });
+        }
+        Ok(self.alloc_contiguous(size, align_log2))
+    }
+
+    /// Reset in place to `DEFAULT`, with all bits unavailable.
+    fn reset(&mut self) {
+        *self = Self::DEFAULT;
+    }
+
+    /// Iterate over all free bits in ascending order.
+    fn iter_free(&self) -> impl Iterator<Item = usize> {
+        core::iter::successors(self.next(0), move |&key| {
+            if key + 1 < Self::CAP {
+                self.next(key + 1)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Runs of consecutive free bits of a bitmap. A fully free bitmap has all three equal to `CAP`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FreeRuns {
+    /// Free bits starting at bit 0.
+    pub leading: usize,
+    /// Longest run anywhere in the bitmap.
+    pub longest: usize,
+    /// Free bits ending at bit `CAP - 1`.
+    pub trailing: usize,
+}
+
+/// Check that `key` is a valid bit of `B`.
+fn check_key<B: BitAlloc>(key: usize) -> Result<(), OutOfRange> {
+    if key < B::CAP {
+        Ok(())
+    } else {
+        Err(OutOfRange {
+            value: key,
+            cap: B::CAP,
+        })
+    }
 }
 
 /// A bitmap of 256 bits
@@ -201,11 +354,84 @@ pub type BitAlloc64K = BitAllocCascade16<BitAlloc4K>;
 /// A bitmap of 1M bits
 pub type BitAlloc1M = BitAllocCascade16<BitAlloc64K>;
 
+/// Integer types usable as the summary bitset of a cascade node.
+pub trait BitWord: BitField + Copy + Eq {
+    /// Number of bits in the word, which is also the branching factor of the node.
+    const BITS: usize;
+
+    /// The word with all bits cleared.
+    const ZERO: Self;
+
+    /// Number of trailing zero bits.
+    fn trailing_zeros(self) -> u32;
+}
+
+/// An internal macro used for implementing BitWord on the standard integral types.
+macro_rules! bitword_numeric_impl {
+    ($($t:ty)*) => ($(
+        impl BitWord for $t {
+            const BITS: usize = <$t>::BITS as usize;
+
+            const ZERO: Self = 0;
+
+            #[inline]
+            fn trailing_zeros(self) -> u32 {
+                <$t>::trailing_zeros(self)
+            }
+        }
+    )*)
+}
+
+bitword_numeric_impl! { u8 u16 u32 u64 u128 usize }
+
+/// A 16-way cascade node, summarized by a `u16`.
+pub type BitAllocCascade16<T> = BitAllocCascadeN<u16, T, 16>;
+/// A 64-way cascade node, summarized by a `u64`.
+pub type BitAllocCascade64<T> = BitAllocCascadeN<u64, T, 64>;
+
 /// Implement the bit allocator by segment tree algorithm.
-#[derive(Default)]
-pub struct BitAllocCascade16<T: BitAlloc> {
-    bitset: u16, // for each bit, 1 indicates available, 0 indicates inavailable
-    sub: [T; 16],
+///
+/// Each node has `N` children and keeps a summary word `W` in which bit `i` tells whether
+/// child `i` has any free bit. `N` must equal the bit length of `W`.
+pub struct BitAllocCascadeN<W: BitWord, T: BitAlloc, const N: usize> {
+    bitset: W, // for each bit, 1 indicates available, 0 indicates inavailable
+    sub: [T; N],
+}
+
+impl<W: BitWord, T: BitAlloc, const N: usize> Default for BitAllocCascadeN<W, T, N> {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Build an allocator whose free bits are exactly the union of `ranges`.
+fn new_from_ranges<B: BitAlloc>(ranges: &[Range<usize>]) -> B {
+    let mut res = B::DEFAULT;
+    for range in ranges {
+        assert!(
+            range.start <= range.end && range.end <= B::CAP,
+            "range {:?} out of bounds 0..{}",
+            range,
+            B::CAP
+        );
+        if !range.is_empty() {
+            res.insert(range.clone());
+        }
+    }
+    res
+}
+
+impl<W: BitWord, T: BitAlloc, const N: usize> BitAllocCascadeN<W, T, N> {
+    pub fn verieasy_new_from_ranges(ranges: &[Range<usize>]) -> Self {
+        new_from_ranges(ranges)
+    }
+
+    /// The bitmap as 16-bit words, lowest bits first. This is the same as `verieasy_get`, and is
+    /// the inverse of the dense constructor `verieasy_new`.
+    #[cfg(feature = "alloc")]
+    pub fn to_bitmap(&self) -> Vec<u16> {
+        self.verieasy_get()
+    }
 }
 
 impl BitAlloc256 {
@@ -264,14 +490,18 @@ impl BitAlloc1M {
     }
 }
 
-impl<T: BitAlloc> BitAlloc for BitAllocCascade16<T> {
-    const CAP: usize = T::CAP * 16;
+impl<W: BitWord, T: BitAlloc, const N: usize> BitAlloc for BitAllocCascadeN<W, T, N> {
+    const CAP: usize = {
+        assert!(N == W::BITS, "branching factor must match the bitset word");
+        T::CAP * N
+    };
 
-    const DEFAULT: Self = BitAllocCascade16 {
-        bitset: 0,
-        sub: [T::DEFAULT; 16],
+    const DEFAULT: Self = BitAllocCascadeN {
+        bitset: W::ZERO,
+        sub: [T::DEFAULT; N],
     };
 
+    #[cfg(feature = "alloc")]
     fn verieasy_get(&self) -> Vec<u16> {
         let mut v = Vec::new();
         for child in &self.sub {
@@ -281,6 +511,14 @@ impl<T: BitAlloc> BitAlloc for BitAllocCascade16<T> {
         v
     }
 
+    fn write_bitmap(&self, out: &mut [u16]) -> usize {
+        let mut len = 0;
+        for child in &self.sub {
+            len += child.write_bitmap(&mut out[len..]);
+        }
+        len
+    }
+
     fn alloc(&mut self) -> Option<usize> {
         if self.any() {
             let i = self.bitset.trailing_zeros() as usize;
@@ -311,14 +549,52 @@ impl<T: BitAlloc> BitAlloc for BitAllocCascade16<T> {
         self.for_range(range, |sub: &mut T, range| sub.remove(range));
     }
     fn any(&self) -> bool {
-        self.bitset != 0
+        self.bitset != W::ZERO
     }
     fn test(&self, key: usize) -> bool {
         self.sub[key / T::CAP].test(key % T::CAP)
     }
+    fn count_free(&self) -> usize {
+        self.sub.iter().map(|sub| sub.count_free()).sum()
+    }
+    fn free_runs(&self) -> FreeRuns {
+        let mut runs = FreeRuns::default();
+        // Whether every child so far is fully free, so the leading run is still open
+        let mut leading_open = true;
+        for (i, sub) in self.sub.iter().enumerate() {
+            let sub_runs = if self.bitset.get_bit(i) {
+                sub.free_runs()
+            } else {
+                FreeRuns::default()
+            };
+            let full = sub_runs.leading == T::CAP;
+            if leading_open {
+                runs.leading += sub_runs.leading;
+                leading_open = full;
+            }
+            // A run may continue from the previous children into this one
+            runs.longest = runs
+                .longest
+                .max(runs.trailing + sub_runs.leading)
+                .max(sub_runs.longest);
+            runs.trailing = if full {
+                runs.trailing + T::CAP
+            } else {
+                sub_runs.trailing
+            };
+        }
+        runs
+    }
+    fn reset(&mut self) {
+        // Reset children in place rather than copying a whole `DEFAULT` tree
+        self.bitset = W::ZERO;
+        for sub in &mut self.sub {
+            sub.reset();
+        }
+    }
     fn next(&self, key: usize) -> Option<usize> {
         let idx = key / T::CAP;
-        (idx..16).find_map(|i| {
+        (idx..N).find_map(|i| {
             if self.bitset.get_bit(i) {
                 let key = if i == idx { key - T::CAP * idx } else { 0 };
                 self.sub[i].next(key).map(|x| x + T::CAP * i)
@@ -327,13 +603,23 @@ impl<T: BitAlloc> BitAlloc for BitAllocCascade16<T> {
             }
         })
     }
+    fn iter_free(&self) -> impl Iterator<Item = usize> {
+        self.sub
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.bitset.get_bit(i))
+            .flat_map(|(i, sub)| sub.iter_free().map(move |x| x + T::CAP * i))
+    }
 }
 
-impl<T: BitAlloc> BitAllocCascade16<T> {
+impl<W: BitWord, T: BitAlloc, const N: usize> BitAllocCascadeN<W, T, N> {
     fn for_range(&mut self, range: Range<usize>, f: impl Fn(&mut T, Range<usize>)) {
         let Range { start, end } = range;
         assert!(start <= end);
         assert!(end <= Self::CAP);
+        if start == end {
+            return;
+        }
         for i in start / T::CAP..=(end - 1) / T::CAP {
             let begin = if start / T::CAP == i {
                 start % T::CAP
@@ -358,9 +644,20 @@ impl<T: BitAlloc> BitAllocCascade16<T> {
 pub struct BitAlloc16(u16);
 
 impl BitAlloc16 {
+    /// Create from the raw bits, where bit `i` set means bit `i` is free. Lossless, so
+    /// `BitAlloc16::verieasy_new(bits).to_bits() == bits`.
     pub fn verieasy_new(bits: u16) -> Self {
         Self(bits)
     }
+
+    /// The raw bits, the inverse of `verieasy_new`.
+    pub fn to_bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn verieasy_new_from_ranges(ranges: &[Range<usize>]) -> Self {
+        new_from_ranges(ranges)
+    }
 }
 
 impl BitAlloc for BitAlloc16 {
@@ -368,12 +665,18 @@ impl BitAlloc for BitAlloc16 {
 
     const DEFAULT: Self = BitAlloc16(0);
 
+    #[cfg(feature = "alloc")]
     fn verieasy_get(&self) -> Vec<u16> {
         let mut v = Vec::with_capacity(1);
         v.push(self.0);
         v
     }
 
+    fn write_bitmap(&self, out: &mut [u16]) -> usize {
+        out[0] = self.0;
+        1
+    }
+
     fn alloc(&mut self) -> Option<usize> {
         if self.any() {
             let i = self.0.trailing_zeros() as usize;
@@ -395,9 +698,16 @@ impl BitAlloc for BitAlloc16 {
         self.0.set_bit(key, true);
     }
     fn insert(&mut self, range: Range<usize>) {
-        self.0.set_bits(range.clone(), 0xffff.get_bits(range));
+        // `set_bits` rejects empty ranges
+        if range.is_empty() {
+            return;
+        }
+        self.0.set_bits(range.clone(), 0xffffu16.get_bits(range));
     }
     fn remove(&mut self, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
         self.0.set_bits(range, 0);
     }
     fn any(&self) -> bool {
@@ -406,9 +716,37 @@ impl BitAlloc for BitAlloc16 {
     fn test(&self, key: usize) -> bool {
         self.0.get_bit(key)
     }
+    fn count_free(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+    fn free_runs(&self) -> FreeRuns {
+        // Each step clears the lowest bit of every run, so the step count is the longest run
+        let mut bits = self.0;
+        let mut longest = 0;
+        while bits != 0 {
+            bits &= bits << 1;
+            longest += 1;
+        }
+        FreeRuns {
+            leading: self.0.trailing_ones() as usize,
+            longest,
+            trailing: self.0.leading_ones() as usize,
+        }
+    }
     fn next(&self, key: usize) -> Option<usize> {
         (key..16).find(|&i| self.0.get_bit(i))
     }
+    fn iter_free(&self) -> impl Iterator<Item = usize> {
+        let mut bits = self.0;
+        core::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+            let i = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Some(i)
+        })
+    }
 }
 
 fn find_contiguous(
@@ -420,9 +758,21 @@ fn find_contiguous(
     if align_log2 >= 64 || capacity < (1 << align_log2) || !ba.any() {
         return None;
     }
-    let mut base = 0;
+    find_contiguous_in(ba, 0..capacity, size, align_log2)
+}
+
+fn find_contiguous_in(
+    ba: &impl BitAlloc,
+    region: Range<usize>,
+    size: usize,
+    align_log2: usize,
+) -> Option<usize> {
+    if align_log2 >= 64 || !ba.any() {
+        return None;
+    }
+    let mut base = region.start.div_ceil(1 << align_log2) << align_log2;
     let mut offset = base;
-    while offset < capacity {
+    while offset < region.end {
         if let Some(next) = ba.next(offset) {
             if next != offset {
                 // it can be guarenteed that no bit in (offset..next) is free
@@ -576,6 +926,359 @@ pub fn bitalloc1m() {
     }
 }
 
+// #[test]
+#[ignore]
+#[cfg(feature = "alloc")]
+pub fn bitalloc256_iter_free() {
+    let mut ba = BitAlloc256::default();
+    assert_eq!(ba.iter_free().next(), None);
+    ba.insert(0..BitAlloc256::CAP);
+    ba.remove(3..40);
+    ba.remove(100..101);
+    ba.remove(200..256);
+    ba.insert(250..251);
+    ba.alloc();
+    let brute: Vec<usize> = (0..BitAlloc256::CAP).filter(|&i| ba.test(i)).collect();
+    assert_eq!(ba.iter_free().collect::<Vec<_>>(), brute);
+    let mut by_next = Vec::new();
+    let mut key = 0;
+    while let Some(i) = ba.next(key) {
+        by_next.push(i);
+        key = i + 1;
+    }
+    assert_eq!(by_next, brute);
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc4k_count_free() {
+    let mut ba = BitAlloc4K::default();
+    assert_eq!(ba.count_free(), 0);
+    ba.insert(0..BitAlloc4K::CAP);
+    assert_eq!(ba.count_free(), BitAlloc4K::CAP);
+    ba.remove(17..1000);
+    ba.insert(500..520);
+    ba.remove(4000..4096);
+    for _ in 0..30 {
+        ba.alloc();
+    }
+    ba.alloc_contiguous(100, 4);
+    ba.dealloc(3);
+    let brute = (0..BitAlloc4K::CAP).filter(|&i| ba.test(i)).count();
+    assert_eq!(ba.count_free(), brute);
+}
+
+/// Longest run of free bits, by testing every bit.
+fn brute_max_free_run<B: BitAlloc>(ba: &B) -> usize {
+    let (mut longest, mut run) = (0, 0);
+    for key in 0..B::CAP {
+        run = if ba.test(key) { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    longest
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc16_max_free_run() {
+    for bits in [0u16, 1, 0x8000, 0xffff, 0x7ffe, 0b1011_0111_0000_1110] {
+        let ba = BitAlloc16::verieasy_new(bits);
+        assert_eq!(ba.max_free_run(), brute_max_free_run(&ba));
+    }
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc256_max_free_run() {
+    let mut ba = BitAlloc256::default();
+    assert_eq!(ba.max_free_run(), 0);
+    ba.insert(0..BitAlloc256::CAP);
+    assert_eq!(ba.max_free_run(), BitAlloc256::CAP);
+    // A run spanning three children
+    ba.remove(0..BitAlloc256::CAP);
+    ba.insert(13..50);
+    ba.insert(100..110);
+    assert_eq!(ba.max_free_run(), 37);
+    // A run ending at the last bit
+    ba.insert(200..256);
+    assert_eq!(ba.max_free_run(), 56);
+
+    // Random inserts and removes, with a linear congruential generator
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut rand = move |bound: usize| {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % bound
+    };
+    for _ in 0..1000 {
+        let (a, b) = (rand(BitAlloc256::CAP + 1), rand(BitAlloc256::CAP + 1));
+        let range = a.min(b)..a.max(b);
+        if rand(2) == 0 {
+            ba.insert(range);
+        } else {
+            ba.remove(range);
+        }
+        assert_eq!(ba.max_free_run(), brute_max_free_run(&ba));
+    }
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc256_fragmentation() {
+    let mut ba = BitAlloc256::default();
+    assert_eq!(ba.fragmentation(), 0.0);
+    // A single run, even across children
+    ba.insert(10..200);
+    assert_eq!(ba.fragmentation(), 0.0);
+    // Every other bit free, so the longest run is a single bit
+    ba.remove(0..BitAlloc256::CAP);
+    for key in (0..BitAlloc256::CAP).step_by(2) {
+        ba.dealloc(key);
+    }
+    assert_eq!(ba.fragmentation(), 1.0 - 1.0 / 128.0);
+    // Two equal runs
+    ba.reset();
+    ba.insert(0..16);
+    ba.insert(100..116);
+    assert_eq!(ba.fragmentation(), 0.5);
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc_dealloc_contiguous() {
+    let mut ba = BitAlloc4K::default();
+    ba.insert(0..BitAlloc4K::CAP);
+    let base = ba.alloc_contiguous(100, 4).unwrap();
+    assert_eq!(base, 0);
+    assert_eq!(ba.count_free(), BitAlloc4K::CAP - 100);
+    ba.dealloc_contiguous(base, 100);
+    assert_eq!(ba.count_free(), BitAlloc4K::CAP);
+    assert_eq!(ba.alloc_contiguous(BitAlloc4K::CAP, 0), Some(0));
+}
+
+// #[test]
+// #[should_panic]
+#[ignore]
+pub fn bitalloc_dealloc_contiguous_double_free() {
+    let mut ba = BitAlloc4K::default();
+    ba.insert(0..BitAlloc4K::CAP);
+    let base = ba.alloc_contiguous(32, 5).unwrap();
+    ba.dealloc(base + 7);
+    ba.dealloc_contiguous(base, 32);
+}
+
+// #[test]
+#[ignore]
+#[cfg(feature = "alloc")]
+pub fn bitalloc_new_from_ranges() {
+    let ba = BitAlloc16::verieasy_new_from_ranges(&[0..3, 8..8, 12..16]);
+    assert_eq!(ba.verieasy_get(), BitAlloc16::verieasy_new(0xf007).verieasy_get());
+
+    let mut bitmap = [0u16; 16];
+    bitmap[0] = 0xfff0;
+    bitmap[1] = 0x0001;
+    bitmap[5] = 0xffff;
+    bitmap[15] = 0x8000;
+    let dense = BitAlloc256::verieasy_new(bitmap);
+    let sparse = BitAlloc256::verieasy_new_from_ranges(&[4..17, 80..96, 255..256]);
+    assert_eq!(dense.verieasy_get(), sparse.verieasy_get());
+
+    let mut bitmap = [0u16; 256];
+    bitmap[64..128].fill(0xffff);
+    let dense = BitAlloc4K::verieasy_new(bitmap);
+    let sparse = BitAlloc4K::verieasy_new_from_ranges(&[1024..1500, 1400..2048]);
+    assert_eq!(dense.verieasy_get(), sparse.verieasy_get());
+    assert_eq!(sparse.count_free(), 1024);
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc_contiguous_in() {
+    let mut ba = BitAlloc4K::default();
+    ba.insert(0..BitAlloc4K::CAP);
+    assert_eq!(ba.alloc_contiguous_in(1000..2000, 16, 4), Some(1008));
+    assert_eq!(ba.alloc_contiguous_in(1000..2000, 8, 0), Some(1000));
+    assert_eq!(ba.alloc_contiguous_in(1000..2000, 100, 0), Some(1024));
+    for i in 0..BitAlloc4K::CAP {
+        assert_eq!(ba.test(i), !(1000..1008).contains(&i) && !(1008..1124).contains(&i));
+    }
+    // Enough space elsewhere, but not inside the region.
+    assert_eq!(ba.alloc_contiguous_in(1000..1200, 128, 7), None);
+    assert_eq!(ba.alloc_contiguous_in(1124..1130, 8, 0), None);
+    assert_eq!(ba.alloc_contiguous_in(3001..3002, 1, 1), None);
+    assert_eq!(ba.alloc_contiguous_in(3001..3002, 1, 0), Some(3001));
+    assert_eq!(ba.alloc_contiguous_in(1000..BitAlloc4K::CAP, 128, 7), Some(1152));
+}
+
+// #[test]
+#[ignore]
+#[cfg(feature = "alloc")]
+pub fn bitalloc_cascade64() {
+    type BitAlloc64K64 = BitAllocCascade64<BitAllocCascade64<BitAlloc16>>;
+    let mut ba16 = BitAlloc64K::default();
+    let mut ba64 = BitAlloc64K64::default();
+    assert_eq!(BitAlloc64K64::CAP, BitAlloc64K::CAP);
+    ba16.insert(0..BitAlloc64K::CAP);
+    ba64.insert(0..BitAlloc64K64::CAP);
+    ba16.remove(3..1030);
+    ba64.remove(3..1030);
+    for _ in 0..8 {
+        assert_eq!(ba16.alloc(), ba64.alloc());
+    }
+    for (size, align_log2) in [(1, 0), (2, 1), (7, 3), (64, 6), (1000, 4), (4096, 12)] {
+        assert_eq!(
+            ba16.alloc_contiguous(size, align_log2),
+            ba64.alloc_contiguous(size, align_log2)
+        );
+    }
+    for key in [0, 1, 5, 1030, 4095, 65535] {
+        ba16.dealloc(key);
+        ba64.dealloc(key);
+    }
+    ba16.remove(60000..65000);
+    ba64.remove(60000..65000);
+    for key in [0, 2, 1029, 1031, 4096, 59999, 60000, 65535] {
+        assert_eq!(ba16.next(key), ba64.next(key));
+        assert_eq!(ba16.test(key), ba64.test(key));
+    }
+    assert_eq!(ba16.verieasy_get(), ba64.verieasy_get());
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc256_alloc_aligned() {
+    let mut ba = BitAlloc256::default();
+    let mut manual = BitAlloc256::default();
+    ba.insert(0..BitAlloc256::CAP);
+    manual.insert(0..BitAlloc256::CAP);
+    ba.remove(0..5);
+    manual.remove(0..5);
+    for align_log2 in [0, 1, 2, 3, 4, 5, 6, 3, 0, 4] {
+        let key = ba.alloc_aligned(align_log2);
+        assert_eq!(key, manual.alloc_contiguous(1, align_log2));
+        assert_eq!(key.unwrap() % (1 << align_log2), 0);
+        assert!(!ba.test(key.unwrap()));
+    }
+    // Bits 0 and 128 are the only 128-aligned bits, and both are allocated
+    assert_eq!(ba.alloc_aligned(7), None);
+    assert_eq!(ba.alloc_aligned(8), None);
+    ba.insert(0..1);
+    assert_eq!(ba.alloc_aligned(8), Some(0));
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc_write_bitmap() {
+    let mut ba = BitAlloc4K::default();
+    ba.insert(0..BitAlloc4K::CAP);
+    ba.remove(3..40);
+    ba.remove(1000..3000);
+    ba.alloc();
+    let mut words = [0u16; BitAlloc4K::CAP / 16];
+    assert_eq!(ba.write_bitmap(&mut words), BitAlloc4K::CAP / 16);
+    let free: usize = words.iter().map(|w| w.count_ones() as usize).sum();
+    assert_eq!(free, ba.count_free());
+    for key in 0..BitAlloc4K::CAP {
+        assert_eq!(words[key / 16] & (1 << (key % 16)) != 0, ba.test(key));
+    }
+    #[cfg(feature = "alloc")]
+    assert_eq!(&words[..], &ba.verieasy_get()[..]);
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc16_to_bits() {
+    for bits in [0, 1, 0x8000, 0x00ff, 0xa5a5, 0xffff] {
+        assert_eq!(BitAlloc16::verieasy_new(bits).to_bits(), bits);
+    }
+    let mut ba = BitAlloc16::verieasy_new(0xffff);
+    ba.remove(4..8);
+    assert_eq!(ba.to_bits(), 0xff0f);
+}
+
+// #[test]
+#[ignore]
+#[cfg(feature = "alloc")]
+pub fn bitalloc_to_bitmap() {
+    let mut bitmap = [0u16; 16];
+    for (i, word) in bitmap.iter_mut().enumerate() {
+        *word = (i as u16).wrapping_mul(0x1357);
+    }
+    assert_eq!(BitAlloc256::verieasy_new(bitmap).to_bitmap(), bitmap);
+
+    let mut bitmap = [0u16; 256];
+    bitmap[0] = 0xffff;
+    bitmap[17] = 0x0100;
+    bitmap[255] = 0x8001;
+    let ba = BitAlloc4K::verieasy_new(bitmap);
+    assert_eq!(ba.to_bitmap(), bitmap);
+    assert_eq!(ba.count_free(), 16 + 1 + 2);
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc256_reset() {
+    let mut ba = BitAlloc256::default();
+    ba.insert(0..BitAlloc256::CAP);
+    ba.remove(10..20);
+    ba.reset();
+    assert!(!ba.any());
+    for key in 0..BitAlloc256::CAP {
+        assert!(!ba.test(key));
+    }
+    assert_eq!(ba.alloc(), None);
+    ba.insert(100..101);
+    assert_eq!(ba.alloc(), Some(100));
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc_empty_range() {
+    let mut ba16 = BitAlloc16::default();
+    ba16.insert(2..9);
+    ba16.insert(5..5);
+    ba16.remove(5..5);
+    ba16.insert(16..16);
+    assert_eq!(ba16.to_bits(), 0x01fc);
+
+    let mut ba = BitAlloc4K::default();
+    ba.insert(100..200);
+    let mut before = [0u16; BitAlloc4K::CAP / 16];
+    ba.write_bitmap(&mut before);
+    for key in [0, 5, 150, BitAlloc4K::CAP] {
+        ba.insert(key..key);
+        ba.remove(key..key);
+    }
+    let mut after = [0u16; BitAlloc4K::CAP / 16];
+    ba.write_bitmap(&mut after);
+    assert_eq!(before, after);
+    assert_eq!(ba.count_free(), 100);
+}
+
+// #[test]
+#[ignore]
+pub fn bitalloc_try_out_of_range() {
+    let mut ba = BitAlloc256::default();
+    let cap = BitAlloc256::CAP;
+    assert_eq!(ba.try_test(cap), Err(OutOfRange { value: cap, cap }));
+    assert_eq!(ba.try_dealloc(cap + 100).unwrap_err().value, cap + 100);
+    assert_eq!(
+        ba.try_alloc_contiguous(cap + 1, 0).unwrap_err().value,
+        cap + 1
+    );
+    assert!(!ba.any());
+
+    assert_eq!(ba.try_dealloc(cap - 1), Ok(()));
+    assert_eq!(ba.try_test(cap - 1), Ok(true));
+    assert_eq!(ba.try_alloc_contiguous(1, 0), Ok(Some(cap - 1)));
+    assert_eq!(ba.try_alloc_contiguous(cap, 0), Ok(None));
+
+    let mut ba16 = BitAlloc16::default();
+    assert!(ba16.try_dealloc(16).is_err());
+    assert_eq!(ba16.try_test(15), Ok(false));
+}
+
 #[ignore]
 pub fn bitalloc1m_alloc() {
     let mut ba = BitAlloc1M::default();
@@ -1,4 +1,6 @@
-use std::ops::Range;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::ops::Range;
 
 /// Macro to get a specific bit from a u16 value.
 /// Returns true if the bit at the given index is 1, false otherwise.
@@ -86,6 +88,7 @@ pub trait BitAllocView {
     where
         Self: Sized;
 
+    #[cfg(feature = "alloc")]
     fn verieasy_get(&self) -> Vec<u16>;
 
     /// Find a index not less than a given key, where the bit is free.
@@ -198,7 +201,7 @@ impl<T: BitAllocView + Copy> Clone for BitAllocCascade16<T> {
     }
 }
 
-impl<T: BitAllocView + std::marker::Copy> BitAllocView for BitAllocCascade16<T> {
+impl<T: BitAllocView + core::marker::Copy> BitAllocView for BitAllocCascade16<T> {
     // 每个子分配器的容量都是固定且相等的
     fn cap() -> usize {
         (T::cap() * 16) as usize
@@ -208,10 +211,11 @@ impl<T: BitAllocView + std::marker::Copy> BitAllocView for BitAllocCascade16<T>
     fn default() -> Self {
         BitAllocCascade16 {
             bitset: BitAlloc16 { bits: 0 },
-            sub: [T::default(); 16], // need the trait "std::marker::Copy"
+            sub: [T::default(); 16], // need the trait "core::marker::Copy"
         }
     }
 
+    #[cfg(feature = "alloc")]
     fn verieasy_get(&self) -> Vec<u16> {
         let mut v = Vec::new();
         for child in &self.sub {
@@ -270,7 +274,7 @@ impl<T: BitAllocView + std::marker::Copy> BitAllocView for BitAllocCascade16<T>
     }
 }
 
-impl<T: BitAlloc + std::marker::Copy> BitAlloc for BitAllocCascade16<T> {
+impl<T: BitAlloc + core::marker::Copy> BitAlloc for BitAllocCascade16<T> {
     fn alloc(&mut self) -> Option<usize> {
         if !self.any() {
             return None;
@@ -421,6 +425,7 @@ impl BitAllocView for BitAlloc16 {
         BitAlloc16 { bits: 0 }
     }
 
+    #[cfg(feature = "alloc")]
     fn verieasy_get(&self) -> Vec<u16> {
         let mut v = Vec::with_capacity(1);
         v.push(self.bits);
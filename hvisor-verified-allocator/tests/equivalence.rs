@@ -0,0 +1,93 @@
+//! Property tests checking that the original, verified and optimized allocators stay equivalent
+//! under random operation sequences.
+
+use core::ops::Range;
+use hvisor_verified_allocator::optimized::{self as v3, BitAlloc as _, BitAllocView as _};
+use hvisor_verified_allocator::original::{self as v1, BitAlloc as _};
+use hvisor_verified_allocator::verified_impl::{self as v2, BitAlloc as _, BitAllocView as _};
+use proptest::prelude::*;
+
+const CAP: usize = 4096;
+
+/// An allocator operation. Arguments are generated within the preconditions of the verified
+/// implementation.
+#[derive(Debug, Clone)]
+enum Op {
+    Alloc,
+    Dealloc(usize),
+    Insert(Range<usize>),
+    Remove(Range<usize>),
+    AllocContiguous(usize, usize),
+}
+
+fn range() -> impl Strategy<Value = Range<usize>> {
+    (0..CAP, 0..CAP).prop_map(|(a, b)| a.min(b)..a.max(b) + 1)
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        Just(Op::Alloc),
+        (0..CAP).prop_map(Op::Dealloc),
+        range().prop_map(Op::Insert),
+        range().prop_map(Op::Remove),
+        (1..=256usize, 0..8usize)
+            .prop_map(|(size, align_log2)| Op::AllocContiguous(size, align_log2)),
+    ]
+}
+
+/// Bitmap of the optimized allocator, in the layout of `verieasy_get`.
+fn bitmap(ba: &v3::BitAlloc4K) -> Vec<u16> {
+    (0..CAP / 16)
+        .map(|word| {
+            (0..16).fold(0u16, |acc, bit| {
+                acc | (ba.test(word * 16 + bit) as u16) << bit
+            })
+        })
+        .collect()
+}
+
+proptest! {
+    #[test]
+    fn allocators_equivalent(ops in prop::collection::vec(op(), 1..32)) {
+        let mut ba1 = v1::BitAlloc4K::default();
+        let mut ba2 = <v2::BitAlloc4K as v2::BitAllocView>::default();
+        let mut ba3 = v3::BitAlloc4K::default();
+
+        for op in ops {
+            match op {
+                Op::Alloc => {
+                    let key = ba1.alloc();
+                    prop_assert_eq!(key, ba2.alloc());
+                    prop_assert_eq!(key, ba3.alloc());
+                }
+                Op::Dealloc(key) => {
+                    // Deallocating a free bit violates the precondition, skip it for all three
+                    if ba1.test(key) {
+                        continue;
+                    }
+                    ba1.dealloc(key);
+                    ba2.dealloc(key);
+                    ba3.dealloc(key);
+                }
+                Op::Insert(range) => {
+                    ba1.insert(range.clone());
+                    ba2.insert(range.clone());
+                    ba3.insert(range);
+                }
+                Op::Remove(range) => {
+                    ba1.remove(range.clone());
+                    ba2.remove(range.clone());
+                    ba3.remove(range);
+                }
+                Op::AllocContiguous(size, align_log2) => {
+                    let base = ba1.alloc_contiguous(size, align_log2);
+                    prop_assert_eq!(base, ba2.alloc_contiguous(size, align_log2));
+                    prop_assert_eq!(base, ba3.alloc_contiguous(size, align_log2));
+                }
+            }
+            let state = ba1.verieasy_get();
+            prop_assert_eq!(&state, &ba2.verieasy_get());
+            prop_assert_eq!(&state, &bitmap(&ba3));
+        }
+    }
+}
@@ -57,9 +57,11 @@ impl TryFrom<verus_syn::Expr> for Expr {
                 Ok(Expr::Field(field))
             }
             verus_syn::Expr::Binary(bin_expr) => {
-                let bin = ExprBinary::try_from(bin_expr).map_err(|_| ())?;
+                let bin = ExprBinary::try_from(regroup_imply(bin_expr)).map_err(|_| ())?;
                 Ok(Expr::Binary(bin))
             }
+            // Generated binary expressions are always parenthesized, so grouping is kept
+            verus_syn::Expr::Paren(paren) => Expr::try_from(*paren.expr),
             verus_syn::Expr::Unary(un_expr) => {
                 let un = ExprUnary::try_from(un_expr).map_err(|_| ())?;
                 Ok(Expr::Unary(un))
@@ -76,6 +78,8 @@ impl TryFrom<verus_syn::Expr> for Expr {
                 let method = ExprMethodCall::try_from(view).map_err(|_| ())?;
                 Ok(Expr::MethodCall(method))
             }
+            // Verus macros such as `seq!` and `set!` build ghost values with no executable form.
+            verus_syn::Expr::Macro(_) => Err(()),
             _ => Err(()),
         }
     }
@@ -187,6 +191,52 @@ pub struct ExprBinary {
     pub right: Box<Expr>,
 }
 
+/// Regroup implications parsed with the wrong associativity or precedence.
+///
+/// `==>` is right-associative and binds looser than any other binary operator, so
+/// `a ==> b ==> c` is `a ==> (b ==> c)` and `a && b ==> c` is `(a && b) ==> c`. Explicitly
+/// parenthesized operands are `Expr::Paren` and are never regrouped.
+fn regroup_imply(bin: verus_syn::ExprBinary) -> verus_syn::ExprBinary {
+    let is_imply = |op: &verus_syn::BinOp| matches!(op, verus_syn::BinOp::Imply(_));
+    let binary =
+        |left: Box<verus_syn::Expr>, op, right: Box<verus_syn::Expr>| verus_syn::ExprBinary {
+            attrs: Vec::new(),
+            left,
+            op,
+            right,
+        };
+    match (&*bin.left, &*bin.right) {
+        // `(a ==> b) ==> c` becomes `a ==> (b ==> c)`
+        (verus_syn::Expr::Binary(left), _) if is_imply(&bin.op) && is_imply(&left.op) => {
+            let right = regroup_imply(binary(left.right.clone(), bin.op, bin.right));
+            binary(
+                left.left.clone(),
+                left.op,
+                Box::new(verus_syn::Expr::Binary(right)),
+            )
+        }
+        // `a && (b ==> c)` becomes `(a && b) ==> c`
+        (_, verus_syn::Expr::Binary(right)) if !is_imply(&bin.op) && is_imply(&right.op) => {
+            let left = regroup_imply(binary(bin.left, bin.op, right.left.clone()));
+            regroup_imply(binary(
+                Box::new(verus_syn::Expr::Binary(left)),
+                right.op,
+                right.right.clone(),
+            ))
+        }
+        // `(a ==> b) && c` becomes `a ==> (b && c)`
+        (verus_syn::Expr::Binary(left), _) if !is_imply(&bin.op) && is_imply(&left.op) => {
+            let right = regroup_imply(binary(left.right.clone(), bin.op, bin.right));
+            binary(
+                left.left.clone(),
+                left.op,
+                Box::new(verus_syn::Expr::Binary(right)),
+            )
+        }
+        _ => bin,
+    }
+}
+
 impl TryFrom<verus_syn::ExprBinary> for ExprBinary {
     type Error = ();
     fn try_from(bin_expr: verus_syn::ExprBinary) -> Result<Self, Self::Error> {
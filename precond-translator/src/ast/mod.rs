@@ -26,6 +26,8 @@ pub struct FunctionPrecond {
     pub signature: Signature,
     /// Precondition expressions.
     pub requires: Vec<Expr>,
+    /// `recommends` expressions, only enforced once folded into `requires`.
+    pub recommends: Vec<Expr>,
 }
 
 /// A method's impl type, signature, and its precondition expressions.
@@ -39,6 +41,20 @@ pub struct MethodPrecond {
     pub signature: Signature,
     /// Precondition expressions.
     pub requires: Vec<Expr>,
+    /// `recommends` expressions, only enforced once folded into `requires`.
+    pub recommends: Vec<Expr>,
+}
+
+impl FunctionPrecond {
+    /// Name of the generated precondition checking function.
+    pub fn checker_name(&self) -> String {
+        "verieasy_pre_".to_owned() + &self.name.to_ident()
+    }
+
+    /// Whether a checker is generated. Functions with only unfolded `recommends` have none.
+    pub fn has_checker(&self) -> bool {
+        !self.requires.is_empty() || self.recommends.is_empty()
+    }
 }
 
 impl MethodPrecond {
@@ -48,6 +64,16 @@ impl MethodPrecond {
             .as_path()
             .join(self.signature.ident.to_string())
     }
+
+    /// Name of the generated precondition checking method.
+    pub fn checker_name(&self) -> String {
+        "verieasy_pre_".to_owned() + &self.signature.ident.to_string()
+    }
+
+    /// Whether a checker is generated. Methods with only unfolded `recommends` have none.
+    pub fn has_checker(&self) -> bool {
+        !self.requires.is_empty() || self.recommends.is_empty()
+    }
 }
 
 /// A free-standing spec function.
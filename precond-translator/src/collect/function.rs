@@ -1,5 +1,5 @@
 //! Collect Verus spec functions.
-use super::path::PathResolver;
+use super::{is_generic, path::PathResolver};
 use crate::ast::{Path, Type};
 use verus_syn::{
     Block, FnMode, Generics, ImplItemFn, ItemFn, ItemImpl, ItemMod, ItemUse, Signature,
@@ -95,7 +95,7 @@ impl<'ast> Visit<'ast> for SpecFunctionCollector<'ast> {
     }
 
     fn visit_item_fn(&mut self, i: &'ast ItemFn) {
-        if !i.sig.generics.params.is_empty() {
+        if is_generic(&i.sig.generics) {
             return;
         } // Skip generic functions
         // Only collect spec functions
@@ -117,7 +117,7 @@ impl<'ast> Visit<'ast> for SpecFunctionCollector<'ast> {
     }
 
     fn visit_impl_item_fn(&mut self, i: &'ast ImplItemFn) {
-        if !i.sig.generics.params.is_empty() {
+        if is_generic(&i.sig.generics) {
             return;
         } // Skip generic functions
         // Only collect spec functions
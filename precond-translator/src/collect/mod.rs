@@ -6,3 +6,12 @@ mod precond;
 
 pub use function::SpecFunctionCollector;
 pub use precond::PrecondCollector;
+
+/// Whether a function has type or const parameters. Lifetime parameters and `where` clauses
+/// alone do not prevent generating its checker.
+fn is_generic(generics: &verus_syn::Generics) -> bool {
+    generics
+        .params
+        .iter()
+        .any(|param| !matches!(param, verus_syn::GenericParam::Lifetime(_)))
+}
@@ -26,20 +26,29 @@ impl PathResolver {
     }
 
     /// Resolve a given path to a fully qualified path.
+    ///
+    /// Fully qualified paths are relative to the crate root, like the ones produced by
+    /// `concat_module`, so leading `crate`, `self` and `super` segments are resolved away.
     pub fn resolve_path(&self, path: &Path) -> Path {
-        let first_seg = path.0.first().unwrap();
-        // Check if the first segment has a mapping.
-        let mut prefix = match self.mappings.get(first_seg) {
-            Some(p) => p.clone(),
-            None => self.concat_module(&first_seg),
-        };
-        let segments: Vec<&String> = path.0.iter().skip(1).collect();
-        if segments.is_empty() {
-            prefix
-        } else {
-            prefix.0.extend(segments.into_iter().cloned());
-            prefix
+        let supers = path.0.iter().take_while(|seg| *seg == "super").count();
+        if supers > 0 {
+            let module = &self.module[..self.module.len().saturating_sub(supers)];
+            let mut resolved = Path(module.to_vec());
+            resolved.0.extend(path.0[supers..].iter().cloned());
+            return resolved;
         }
+        let (first_seg, rest) = path.0.split_first().unwrap();
+        let mut prefix = match first_seg.as_str() {
+            "crate" => Path::empty(),
+            "self" => Path(self.module.clone()),
+            // Check if the first segment has a mapping.
+            _ => match self.mappings.get(first_seg) {
+                Some(p) => p.clone(),
+                None => self.concat_module(first_seg),
+            },
+        };
+        prefix.0.extend(rest.iter().cloned());
+        prefix
     }
 
     /// Concatenate current module with the given name.
@@ -72,17 +81,15 @@ impl PathResolver {
             UseTree::Path(use_path) => {
                 self.parse_use_tree(&*use_path.tree, prefix.join(use_path.ident.to_string()));
             }
+            // Imported paths are resolved now, so that a symbol maps to the same path whether it
+            // is used through an import, an alias, or at its definition site.
             UseTree::Name(use_name) => {
-                self.mappings.insert(
-                    use_name.ident.to_string(),
-                    prefix.join(use_name.ident.to_string()),
-                );
+                let path = self.resolve_path(&prefix.join(use_name.ident.to_string()));
+                self.mappings.insert(use_name.ident.to_string(), path);
             }
             UseTree::Rename(use_rename) => {
-                self.mappings.insert(
-                    use_rename.rename.to_string(),
-                    prefix.join(use_rename.ident.to_string()),
-                );
+                let path = self.resolve_path(&prefix.join(use_rename.ident.to_string()));
+                self.mappings.insert(use_rename.rename.to_string(), path);
             }
             UseTree::Glob(_) => {
                 // Ignore glob imports for now.
@@ -1,9 +1,12 @@
 //! Collect Verus function preconditions.
-use super::path::PathResolver;
+use super::{is_generic, path::PathResolver};
+use crate::Diagnostic;
 use crate::ast::Path;
+use quote::ToTokens;
 use verus_syn::{
-    FnMode, Generics, Ident, ImplItemFn, ItemFn, ItemImpl, ItemMod, ItemTrait, ItemUse, Requires,
-    Signature, SignatureSpec, TraitItemFn, Type,
+    ExprLet, ExprMacro, ExprMatch, ExprPath, FnMode, Generics, Ident, ImplItemFn, ItemFn, ItemImpl,
+    ItemMod, ItemTrait, ItemUse, Pat, Recommends, Requires, ReturnType, Signature, SignatureSpec,
+    Specification, TraitItemFn, Type,
     visit::{self, Visit},
 };
 
@@ -14,7 +17,9 @@ struct TraitPrecond {
     /// Function signature.
     signature: Signature,
     /// Preconditions.
-    requires: Requires,
+    requires: Option<Requires>,
+    /// Soft preconditions.
+    recommends: Option<Recommends>,
 }
 
 /// Precondition defined in free-standing function.
@@ -24,7 +29,9 @@ struct FunctionPrecond {
     /// Function signature.
     signature: Signature,
     /// Preconditions.
-    requires: Requires,
+    requires: Option<Requires>,
+    /// Soft preconditions.
+    recommends: Option<Recommends>,
 }
 
 /// Precondition defined in impl method.
@@ -36,7 +43,9 @@ struct MethodPrecond {
     /// Function signature.
     signature: Signature,
     /// Preconditions.
-    requires: Requires,
+    requires: Option<Requires>,
+    /// Soft preconditions.
+    recommends: Option<Recommends>,
 }
 
 /// Visitor that visits Verus AST and extracts preconditions of executable functions.
@@ -84,27 +93,33 @@ impl<'ast> PrecondCollector<'ast> {
     }
 
     /// Collect preconditions from the given Verus syntax tree, and transform into our AST form.
+    ///
+    /// Require clauses that cannot be converted are dropped and reported as diagnostics.
     pub fn collect(
         mut self,
         syntax: &'ast verus_syn::File,
     ) -> (
         Vec<crate::ast::FunctionPrecond>,
         Vec<crate::ast::MethodPrecond>,
+        Vec<Diagnostic>,
     ) {
         self.visit_file(syntax);
+        let mut diagnostics = Vec::new();
 
         let mut function_preconds = Vec::new();
         // Collect free-standing function preconditions
         for precondition in self.func_preconds {
-            let mut req_exprs = Vec::new();
-            for expr in &precondition.requires.exprs.exprs {
-                if let Ok(req_expr) = expr.clone().try_into() {
-                    req_exprs.push(req_expr);
-                }
-            }
+            let (req_exprs, rec_exprs) = Self::convert_clauses(
+                &precondition.func_name,
+                &precondition.signature,
+                precondition.requires.as_ref(),
+                precondition.recommends.as_ref(),
+                &mut diagnostics,
+            );
             function_preconds.push(crate::ast::FunctionPrecond {
                 name: precondition.func_name.clone(),
                 requires: req_exprs,
+                recommends: rec_exprs,
                 signature: precondition.signature.clone(),
             });
         }
@@ -112,18 +127,23 @@ impl<'ast> PrecondCollector<'ast> {
         let mut method_preconds = Vec::new();
         // Collect impl method preconditions
         for precondition in self.method_preconds {
-            let mut req_exprs = Vec::new();
-            for expr in &precondition.requires.exprs.exprs {
-                if let Ok(req_expr) = expr.clone().try_into() {
-                    req_exprs.push(req_expr);
-                }
-            }
             if let Ok(impl_type) = crate::ast::Type::try_from(precondition.impl_type) {
+                let name = impl_type
+                    .as_path()
+                    .join(precondition.signature.ident.to_string());
+                let (req_exprs, rec_exprs) = Self::convert_clauses(
+                    &name,
+                    &precondition.signature,
+                    precondition.requires.as_ref(),
+                    precondition.recommends.as_ref(),
+                    &mut diagnostics,
+                );
                 method_preconds.push(crate::ast::MethodPrecond {
                     generics: precondition.generics,
                     impl_type,
                     signature: precondition.signature,
                     requires: req_exprs,
+                    recommends: rec_exprs,
                 });
             }
         }
@@ -140,25 +160,157 @@ impl<'ast> PrecondCollector<'ast> {
                     }
                 })
                 .collect();
+            let name = precondition
+                .trait_name
+                .clone()
+                .join(precondition.signature.ident.to_string());
+            let (req_exprs, rec_exprs) = Self::convert_clauses(
+                &name,
+                &precondition.signature,
+                precondition.requires.as_ref(),
+                precondition.recommends.as_ref(),
+                &mut diagnostics,
+            );
             for (generics, impl_type) in impl_types {
-                let mut req_exprs = Vec::new();
-                for expr in &precondition.requires.exprs.exprs {
-                    if let Ok(req_expr) = expr.clone().try_into() {
-                        req_exprs.push(req_expr);
-                    }
-                }
                 if let Ok(impl_type) = crate::ast::Type::try_from(impl_type.clone()) {
                     method_preconds.push(crate::ast::MethodPrecond {
                         generics: generics.clone(),
                         impl_type,
                         signature: precondition.signature.clone(),
-                        requires: req_exprs,
+                        requires: req_exprs.clone(),
+                        recommends: rec_exprs.clone(),
                     });
                 }
             }
         }
 
-        (function_preconds, method_preconds)
+        (function_preconds, method_preconds, diagnostics)
+    }
+
+    /// Convert the require and recommend clauses of a function into our AST form.
+    fn convert_clauses(
+        item: &Path,
+        signature: &Signature,
+        requires: Option<&Requires>,
+        recommends: Option<&Recommends>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> (Vec<crate::ast::Expr>, Vec<crate::ast::Expr>) {
+        let req_exprs = requires
+            .map(|req| Self::convert_requires(item, signature, &req.exprs, diagnostics))
+            .unwrap_or_default();
+        let rec_exprs = recommends
+            .map(|rec| Self::convert_requires(item, signature, &rec.exprs, diagnostics))
+            .unwrap_or_default();
+        (req_exprs, rec_exprs)
+    }
+
+    /// Convert require (or recommend) clauses into our AST form, recording a diagnostic for each
+    /// dropped clause.
+    ///
+    /// Clauses referring to the named return value of `signature` are dropped, because the
+    /// return value is not available before the call.
+    fn convert_requires(
+        item: &Path,
+        signature: &Signature,
+        requires: &Specification,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Vec<crate::ast::Expr> {
+        let ret_binding = return_binding(signature);
+        let mut req_exprs = Vec::new();
+        for expr in &requires.exprs {
+            if let Some(name) = &ret_binding {
+                let mut finder = VarFinder { name, found: false };
+                finder.visit_expr(expr);
+                if finder.found {
+                    diagnostics.push(Diagnostic {
+                        item: item.to_string(),
+                        clause: expr.to_token_stream().to_string(),
+                        reason: format!(
+                            "refers to the return value `{}`, which is unavailable before the call",
+                            name
+                        ),
+                    });
+                    continue;
+                }
+            }
+            match crate::ast::Expr::try_from(expr.clone()) {
+                Ok(req_expr) => req_exprs.push(req_expr),
+                Err(_) => {
+                    let mut finder = UnsupportedFinder(None);
+                    finder.visit_expr(expr);
+                    let reason = finder
+                        .0
+                        .unwrap_or_else(|| "unsupported expression".to_owned());
+                    diagnostics.push(Diagnostic {
+                        item: item.to_string(),
+                        clause: expr.to_token_stream().to_string(),
+                        reason,
+                    });
+                }
+            }
+        }
+        req_exprs
+    }
+}
+
+/// Name of the return value binding of `signature`, e.g. `r` in `fn f() -> (r: u64)`.
+fn return_binding(signature: &Signature) -> Option<String> {
+    match &signature.output {
+        ReturnType::Type(_, _, Some(binding), _) => match &binding.1 {
+            Pat::Ident(pat) => Some(pat.ident.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Visitor that checks whether an expression refers to a variable by name.
+struct VarFinder<'a> {
+    name: &'a str,
+    found: bool,
+}
+
+impl<'ast> Visit<'ast> for VarFinder<'_> {
+    fn visit_expr_path(&mut self, i: &'ast ExprPath) {
+        if i.qself.is_none() && i.path.segments.len() == 1 && i.path.segments[0].ident == self.name
+        {
+            self.found = true;
+        }
+        visit::visit_expr_path(self, i);
+    }
+}
+
+/// Visitor that finds the first untranslatable form (macro or pattern) in an expression.
+struct UnsupportedFinder(Option<String>);
+
+impl<'ast> Visit<'ast> for UnsupportedFinder {
+    fn visit_expr_macro(&mut self, i: &'ast ExprMacro) {
+        if self.0.is_none() {
+            self.0 = i
+                .mac
+                .path
+                .segments
+                .last()
+                .map(|seg| format!("Verus macro `{}!` cannot be translated", seg.ident));
+        }
+    }
+
+    fn visit_expr_let(&mut self, i: &'ast ExprLet) {
+        if self.0.is_none() {
+            self.0 = Some(
+                "unsupported pattern form: `if let`/`let` patterns cannot be translated".to_owned(),
+            );
+        }
+        visit::visit_expr_let(self, i);
+    }
+
+    fn visit_expr_match(&mut self, i: &'ast ExprMatch) {
+        if self.0.is_none() {
+            self.0 = Some(
+                "unsupported pattern form: `match` expressions cannot be translated".to_owned(),
+            );
+        }
+        visit::visit_expr_match(self, i);
     }
 }
 
@@ -174,7 +326,7 @@ impl<'ast> Visit<'ast> for PrecondCollector<'ast> {
     }
 
     fn visit_item_fn(&mut self, i: &'ast ItemFn) {
-        if !i.sig.generics.params.is_empty() {
+        if is_generic(&i.sig.generics) {
             return;
         } // Skip generic functions
         self.function = Some(&i.sig);
@@ -207,7 +359,7 @@ impl<'ast> Visit<'ast> for PrecondCollector<'ast> {
     }
 
     fn visit_impl_item_fn(&mut self, i: &'ast ImplItemFn) {
-        if !i.sig.generics.params.is_empty() {
+        if is_generic(&i.sig.generics) {
             return;
         } // Skip generic functions
         self.function = Some(&i.sig);
@@ -224,10 +376,11 @@ impl<'ast> Visit<'ast> for PrecondCollector<'ast> {
         if !matches!(function.mode, FnMode::Exec(_)) && !matches!(function.mode, FnMode::Default) {
             return;
         }
-        if i.requires.is_none() {
+        if i.requires.is_none() && i.recommends.is_none() {
             return;
         }
-        let requires = i.requires.clone().unwrap();
+        let requires = i.requires.clone();
+        let recommends = i.recommends.clone();
 
         // Collect precondition
         if let Some(trait_ident) = self.trait_ {
@@ -237,6 +390,7 @@ impl<'ast> Visit<'ast> for PrecondCollector<'ast> {
                 trait_name,
                 signature: function.clone(),
                 requires,
+                recommends,
             });
             return;
         }
@@ -247,6 +401,7 @@ impl<'ast> Visit<'ast> for PrecondCollector<'ast> {
                 generics: impl_block.generics.clone(),
                 signature: function.clone(),
                 requires,
+                recommends,
             });
             return;
         }
@@ -256,6 +411,7 @@ impl<'ast> Visit<'ast> for PrecondCollector<'ast> {
             func_name,
             signature: function.clone(),
             requires,
+            recommends,
         });
     }
 }
@@ -0,0 +1,26 @@
+//! Diagnostics reported for preconditions that could not be translated.
+
+/// A spec function, method or require clause dropped during translation, with the reason why.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    /// Fully qualified name of the function the dropped item belongs to.
+    pub item: String,
+    /// Source text of the dropped clause, empty if the whole item was dropped.
+    pub clause: String,
+    /// Why the clause was dropped.
+    pub reason: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.clause.is_empty() {
+            write!(f, "dropped `{}`: {}", self.item, self.reason)
+        } else {
+            write!(
+                f,
+                "dropped `{}` in `{}`: {}",
+                self.clause, self.item, self.reason
+            )
+        }
+    }
+}
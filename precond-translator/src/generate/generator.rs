@@ -1,10 +1,12 @@
 //! Generate excutable precondition checking functions and spec functions/methods.
 
 use super::visitors::*;
+use crate::Diagnostic;
 use crate::ast::*;
 use crate::visit::{Visit, VisitMut};
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{ToTokens, quote};
+use std::collections::HashMap;
 use std::str::FromStr;
 
 /// Generate excutable precondition checking functions and spec functions/methods.
@@ -17,6 +19,10 @@ pub struct CodeGenerator {
     function_preconds: Vec<FunctionPrecond>,
     /// Collected preconditions of methods.
     method_preconds: Vec<MethodPrecond>,
+    /// Diagnostics for dropped precondition items.
+    diagnostics: Vec<Diagnostic>,
+    /// Hand-written checking functions replacing the generated ones, by checker name.
+    overrides: HashMap<String, TokenStream>,
 }
 
 impl CodeGenerator {
@@ -26,12 +32,15 @@ impl CodeGenerator {
         spec_methods: Vec<SpecMethod>,
         function_preconds: Vec<FunctionPrecond>,
         method_preconds: Vec<MethodPrecond>,
+        diagnostics: Vec<Diagnostic>,
     ) -> Self {
         let mut generstor = CodeGenerator {
             spec_functions: spec_fns,
             spec_methods,
             function_preconds,
             method_preconds,
+            diagnostics,
+            overrides: HashMap::new(),
         };
         generstor.preprocess();
         generstor
@@ -46,10 +55,10 @@ impl CodeGenerator {
         for spec_method in &self.spec_methods {
             tokens.push(self.generate_spec_method(spec_method));
         }
-        for precond in &self.function_preconds {
+        for precond in self.function_preconds.iter().filter(|p| p.has_checker()) {
             tokens.push(self.generate_function_precond(precond));
         }
-        for precond in &self.method_preconds {
+        for precond in self.method_preconds.iter().filter(|p| p.has_checker()) {
             tokens.push(self.generate_method_precond(precond));
         }
         quote! {
@@ -61,6 +70,7 @@ impl CodeGenerator {
     pub fn get_function_preconds(&self) -> Vec<String> {
         self.function_preconds
             .iter()
+            .filter(|f| f.has_checker())
             .map(|f| f.name.to_string())
             .collect()
     }
@@ -69,15 +79,103 @@ impl CodeGenerator {
     pub fn get_method_preconds(&self) -> Vec<String> {
         self.method_preconds
             .iter()
+            .filter(|f| f.has_checker())
             .map(|f| f.name().to_string())
             .collect()
     }
 
+    /// Fold the `recommends` clauses into the preconditions, so that the generated checkers
+    /// enforce them as well. Functions with only `recommends` then get a checker too.
+    ///
+    /// Recommend clauses are preprocessed like require clauses, and dropped with a diagnostic if
+    /// they cannot be translated.
+    pub fn fold_recommends(&mut self) {
+        let allowed_fns = Self::calculate_allowed_fns(&self.spec_functions, &self.spec_methods);
+        let diagnostics = &mut self.diagnostics;
+        for precond in &mut self.function_preconds {
+            for mut rec in std::mem::take(&mut precond.recommends) {
+                RemoveOld.visit_expr_mut(&mut rec);
+                let reason = Self::check_require(&allowed_fns, &rec, None);
+                if Self::record_drop(diagnostics, &precond.name, Some(&rec), reason) {
+                    RemoveSpecPrefix.visit_expr_mut(&mut rec);
+                    precond.requires.push(rec);
+                }
+            }
+        }
+        for precond in &mut self.method_preconds {
+            let name = precond.name();
+            for mut rec in std::mem::take(&mut precond.recommends) {
+                RemoveOld.visit_expr_mut(&mut rec);
+                let reason = Self::check_require(&allowed_fns, &rec, Some(&precond.impl_type));
+                if Self::record_drop(diagnostics, &name, Some(&rec), reason) {
+                    RemoveSpecPrefix.visit_expr_mut(&mut rec);
+                    precond.requires.push(rec);
+                }
+            }
+        }
+    }
+
+    /// Replace generated checking functions with hand-written ones found in `dir`.
+    ///
+    /// A file `verieasy_pre_<name>.rs` must define the checking function `verieasy_pre_<name>`,
+    /// with the signature the generated one would have. A method override applies to every method
+    /// of that name. Each replaced precondition is recorded as a diagnostic, and files matching no
+    /// collected precondition are ignored.
+    pub fn apply_overrides(&mut self, dir: &str) -> anyhow::Result<()> {
+        let functions = self
+            .function_preconds
+            .iter()
+            .filter(|precond| precond.has_checker())
+            .map(|precond| (precond.name.clone(), precond.checker_name()));
+        let methods = self
+            .method_preconds
+            .iter()
+            .filter(|precond| precond.has_checker())
+            .map(|precond| (precond.name(), precond.checker_name()));
+        for (item, checker_name) in functions.chain(methods).collect::<Vec<_>>() {
+            let path = std::path::Path::new(dir).join(format!("{}.rs", checker_name));
+            if !self.overrides.contains_key(&checker_name) {
+                if !path.is_file() {
+                    continue;
+                }
+                let code = std::fs::read_to_string(&path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+                let mut checker: syn::ItemFn = syn::parse_str(&code)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+                if checker.sig.ident != checker_name {
+                    return Err(anyhow::anyhow!(
+                        "{} must define `{}`, found `{}`",
+                        path.display(),
+                        checker_name,
+                        checker.sig.ident
+                    ));
+                }
+                // Harnesses call checkers from outside the module
+                checker.vis = syn::parse_quote!(pub);
+                self.overrides
+                    .insert(checker_name, checker.to_token_stream());
+            }
+            self.diagnostics.push(Diagnostic {
+                item: item.to_string(),
+                clause: String::new(),
+                reason: format!("generated checker replaced by `{}`", path.display()),
+            });
+        }
+        Ok(())
+    }
+
+    /// Get diagnostics for precondition items that were dropped during translation.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
     /// Preprocess for code generation.
     ///
     /// - Remove "old" function calls.
     /// - Remove non-generatable spec functions/methods from allowed list.
     /// - Remove non-generatable require expressions.
+    ///
+    /// Every removed spec function, method and require expression is recorded as a diagnostic.
     fn preprocess(&mut self) {
         // Remove "old" in spec functions and methods.
         for precond in &mut self.function_preconds {
@@ -96,20 +194,30 @@ impl CodeGenerator {
 
         let allowed_fns = Self::calculate_allowed_fns(&self.spec_functions, &self.spec_methods);
         // Remove non-generatable spec functions/methods from allowed list.
-        self.spec_functions
-            .retain(|f| Self::is_spec_fn_generatable(&allowed_fns, &f.body, None));
-        self.spec_methods
-            .retain(|m| Self::is_spec_fn_generatable(&allowed_fns, &m.body, Some(&m.impl_type)));
+        let diagnostics = &mut self.diagnostics;
+        self.spec_functions.retain(|f| {
+            let reason = Self::check_spec_fn(&allowed_fns, &f.body, None);
+            Self::record_drop(diagnostics, &f.name, None, reason)
+        });
+        self.spec_methods.retain(|m| {
+            let reason = Self::check_spec_fn(&allowed_fns, &m.body, Some(&m.impl_type));
+            Self::record_drop(diagnostics, &m.name(), None, reason)
+        });
 
         // Remove non-generatable require expressions.
         for precond in &mut self.function_preconds {
-            precond
-                .requires
-                .retain(|req| Self::is_require_generatable(&allowed_fns, req, None));
+            let name = &precond.name;
+            precond.requires.retain(|req| {
+                let reason = Self::check_require(&allowed_fns, req, None);
+                Self::record_drop(diagnostics, name, Some(req), reason)
+            });
         }
         for precond in &mut self.method_preconds {
+            let name = precond.name();
+            let impl_type = &precond.impl_type;
             precond.requires.retain(|req| {
-                Self::is_require_generatable(&allowed_fns, req, Some(&precond.impl_type))
+                let reason = Self::check_require(&allowed_fns, req, Some(impl_type));
+                Self::record_drop(diagnostics, &name, Some(req), reason)
             });
         }
 
@@ -133,6 +241,9 @@ impl CodeGenerator {
     fn generate_spec_function(&self, spec_fn: &SpecFunction) -> TokenStream {
         let fn_name = spec_fn.name.to_ident();
         let fn_name_ts = TokenStream::from_str(&fn_name).unwrap();
+        // Lifetime parameters, the only generics of collected functions
+        let fn_generics = &spec_fn.signature.generics;
+        let where_clause = &fn_generics.where_clause;
         let inputs = &spec_fn.signature.inputs;
         let output = match &spec_fn.signature.output {
             verus_syn::ReturnType::Default => quote! {},
@@ -144,7 +255,7 @@ impl CodeGenerator {
         let body_ts = generator.get_code();
 
         quote! {
-            pub fn #fn_name_ts(#inputs) #output #body_ts
+            pub fn #fn_name_ts #fn_generics (#inputs) #output #where_clause #body_ts
         }
     }
 
@@ -155,6 +266,8 @@ impl CodeGenerator {
             TokenStream::from_str(&spec_method.impl_type.as_path().to_string()).unwrap();
         let fn_name = spec_method.signature.ident.to_string();
         let fn_name_ts = TokenStream::from_str(&fn_name).unwrap();
+        let fn_generics = &spec_method.signature.generics;
+        let where_clause = &fn_generics.where_clause;
         let inputs = &spec_method.signature.inputs;
         let output = match &spec_method.signature.output {
             verus_syn::ReturnType::Default => quote! {},
@@ -167,15 +280,20 @@ impl CodeGenerator {
 
         quote! {
             impl #generics #impl_type {
-                pub fn #fn_name_ts(#inputs) #output #body_ts
+                pub fn #fn_name_ts #fn_generics (#inputs) #output #where_clause #body_ts
             }
         }
     }
 
     /// Generate checking function for a precondition of a free-standing function.
     fn generate_function_precond(&self, precond: &FunctionPrecond) -> TokenStream {
-        let fn_name = "verieasy_pre_".to_owned() + &precond.name.to_ident();
+        let fn_name = precond.checker_name();
+        if let Some(checker) = self.overrides.get(&fn_name) {
+            return checker.clone();
+        }
         let fn_name_ts = TokenStream::from_str(&fn_name).unwrap();
+        let fn_generics = &precond.signature.generics;
+        let where_clause = &fn_generics.where_clause;
         let inputs = precond.signature.inputs.clone();
 
         let mut requires = Vec::new();
@@ -187,7 +305,7 @@ impl CodeGenerator {
         }
 
         quote! {
-            pub fn #fn_name_ts(#inputs) -> bool {
+            pub fn #fn_name_ts #fn_generics (#inputs) -> bool #where_clause {
                 #(if !(#requires) { return false; })*
                 true
             }
@@ -198,8 +316,17 @@ impl CodeGenerator {
     fn generate_method_precond(&self, precond: &MethodPrecond) -> TokenStream {
         let generics = &precond.generics;
         let impl_type = TokenStream::from_str(&precond.impl_type.as_path().to_string()).unwrap();
-        let fn_name = "verieasy_pre_".to_owned() + &precond.signature.ident.to_string();
+        let fn_name = precond.checker_name();
+        if let Some(checker) = self.overrides.get(&fn_name) {
+            return quote! {
+                impl #generics #impl_type {
+                    #checker
+                }
+            };
+        }
         let fn_name_ts = TokenStream::from_str(&fn_name).unwrap();
+        let fn_generics = &precond.signature.generics;
+        let where_clause = &fn_generics.where_clause;
         let inputs = precond.signature.inputs.clone();
 
         let mut requires = Vec::new();
@@ -212,7 +339,7 @@ impl CodeGenerator {
 
         quote! {
             impl #generics #impl_type {
-                pub fn #fn_name_ts(#inputs) -> bool {
+                pub fn #fn_name_ts #fn_generics (#inputs) -> bool #where_clause {
                     #(if !(#requires) { return false; })*
                     true
                 }
@@ -220,18 +347,43 @@ impl CodeGenerator {
         }
     }
 
-    /// Check if a require expression is generatable.
-    fn is_require_generatable(allowed_fns: &[Path], req: &Expr, self_ty: Option<&Type>) -> bool {
+    /// Check if a require expression is generatable, returning the reason if it is not.
+    fn check_require(allowed_fns: &[Path], req: &Expr, self_ty: Option<&Type>) -> Option<String> {
         let mut checker = CheckFnCall::new(allowed_fns, self_ty);
         checker.visit_expr(req);
-        !checker.aborted
+        checker.aborted
     }
 
-    /// Check if a spec function or method is generatable.
-    fn is_spec_fn_generatable(allowed_fns: &[Path], body: &Block, self_ty: Option<&Type>) -> bool {
+    /// Check if a spec function or method is generatable, returning the reason if it is not.
+    fn check_spec_fn(allowed_fns: &[Path], body: &Block, self_ty: Option<&Type>) -> Option<String> {
         let mut checker = CheckFnCall::new(allowed_fns, self_ty);
         checker.visit_block(body);
-        !checker.aborted
+        checker.aborted
+    }
+
+    /// Record a diagnostic if `reason` is set. Returns whether the item should be kept.
+    fn record_drop(
+        diagnostics: &mut Vec<Diagnostic>,
+        item: &Path,
+        clause: Option<&Expr>,
+        reason: Option<String>,
+    ) -> bool {
+        let Some(reason) = reason else {
+            return true;
+        };
+        let clause = clause
+            .map(|req| {
+                let mut generator = AstToCode::new();
+                generator.visit_expr(req);
+                generator.get_code().to_string()
+            })
+            .unwrap_or_default();
+        diagnostics.push(Diagnostic {
+            item: item.to_string(),
+            clause,
+            reason,
+        });
+        false
     }
 
     /// Calculate the allowed functions and methods for generating.
@@ -246,16 +398,14 @@ impl CodeGenerator {
         // Iterate until no more functions can be removed.
         loop {
             for spec_fn in spec_fns {
-                if !Self::is_spec_fn_generatable(&allowed_fns, &spec_fn.body, None) {
+                if Self::check_spec_fn(&allowed_fns, &spec_fn.body, None).is_some() {
                     allowed_fns.retain(|p| *p != spec_fn.name);
                 }
             }
             for method in spec_methods {
-                if !Self::is_spec_fn_generatable(
-                    &allowed_fns,
-                    &method.body,
-                    Some(&method.impl_type),
-                ) {
+                if Self::check_spec_fn(&allowed_fns, &method.body, Some(&method.impl_type))
+                    .is_some()
+                {
                     allowed_fns.retain(|p| *p != method.name());
                 }
             }
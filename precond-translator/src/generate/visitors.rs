@@ -105,8 +105,9 @@ impl Visit for AstToCode {
         let right = self.stack.pop().unwrap();
         let left = self.stack.pop().unwrap();
         let expr = if let BinaryOp::Imply = binary.op {
+            // Operands are parenthesized, so chained implications keep their grouping
             quote! {
-                (!#left || #right)
+                (!(#left) || (#right))
             }
         } else {
             let op = match binary.op {
@@ -194,8 +195,8 @@ pub struct CheckFnCall<'a> {
     fn_list: &'a [Path],
     /// Self type, for checking method calls.
     self_ty: Option<&'a Type>,
-    /// Whether an invalid function call was found.
-    pub aborted: bool,
+    /// Reason for the first invalid function call found, if any.
+    pub aborted: Option<String>,
 }
 
 impl<'a> CheckFnCall<'a> {
@@ -203,13 +204,16 @@ impl<'a> CheckFnCall<'a> {
         CheckFnCall {
             fn_list,
             self_ty,
-            aborted: false,
+            aborted: None,
         }
     }
 }
 
 impl<'a> Visit for CheckFnCall<'a> {
     fn visit_expr_call(&mut self, call: &ExprCall) {
+        if self.aborted.is_some() {
+            return;
+        }
         if call.func.path.0.last().unwrap().starts_with("spec_") {
             // We assume function with "spec_" prefix always have an exec version.
             visit::visit_expr_call(self, call);
@@ -224,7 +228,10 @@ impl<'a> Visit for CheckFnCall<'a> {
                 func_path
             } else {
                 // No self type info, abort.
-                self.aborted = true;
+                self.aborted = Some(format!(
+                    "calls `{}` outside of an impl block",
+                    call.func.path.to_string()
+                ));
                 return;
             }
         } else {
@@ -236,13 +243,19 @@ impl<'a> Visit for CheckFnCall<'a> {
             .iter()
             .any(|p| p.to_string() == func_path.to_string())
         {
-            self.aborted = true;
+            self.aborted = Some(format!(
+                "calls `{}`, which has no executable version",
+                func_path.to_string()
+            ));
             return;
         }
         visit::visit_expr_call(self, call);
     }
 
     fn visit_expr_method_call(&mut self, method_call: &ExprMethodCall) {
+        if self.aborted.is_some() {
+            return;
+        }
         if method_call.method.starts_with("spec_") {
             // We assume method with "spec_" prefix always have an exec version.
             visit::visit_expr_method_call(self, method_call);
@@ -259,13 +272,19 @@ impl<'a> Visit for CheckFnCall<'a> {
                 .iter()
                 .any(|p| p.to_string() == func_path.to_string())
             {
-                self.aborted = true;
+                self.aborted = Some(format!(
+                    "calls method `{}`, which has no executable version",
+                    func_path.to_string()
+                ));
                 return;
             }
             visit::visit_expr_method_call(self, method_call);
         } else {
             // No self type info, abort.
-            self.aborted = true;
+            self.aborted = Some(format!(
+                "calls method `{}` outside of an impl block",
+                method_call.method
+            ));
         }
     }
 }
@@ -6,29 +6,39 @@ use verus_syn::File;
 
 mod ast;
 mod collect;
+mod diagnostic;
 mod generate;
 mod visit;
 
+pub use diagnostic::Diagnostic;
+
 /// Collect preconditions and spec functions/methods from a Verus file, then create a code generator
 /// for generating executable precondition checking functions and spec functions/methods.
 pub fn parse_file_and_create_generator(file_path: &str) -> anyhow::Result<CodeGenerator> {
     let file = std::fs::read_to_string(file_path)
-        .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file_path, e))?
+        .map_err(|e| anyhow::anyhow!("Failed to read file {}: {}", file_path, e))?;
+    create_generator(&file)
+        .map_err(|e| anyhow::anyhow!("Failed to parse file {}: {}", file_path, e))
+}
+
+/// Create a code generator from Verus source code.
+fn create_generator(source: &str) -> anyhow::Result<CodeGenerator> {
+    let source = source
         // Remove verus! { ... } wrapper if exists
         // TODO: better way to handle this?
         .replace("verus! {", "")
         .replace("} // verus!", "");
-    let syntax: File = verus_syn::parse_file(&file)
-        .map_err(|e| anyhow::anyhow!("Failed to parse file {}: {}", file_path, e))?;
+    let syntax: File = verus_syn::parse_file(&source)?;
 
     let (spec_fns, spec_methods) = SpecFunctionCollector::new().collect(&syntax);
-    let (func_preconds, method_preconds) = PrecondCollector::new().collect(&syntax);
+    let (func_preconds, method_preconds, diagnostics) = PrecondCollector::new().collect(&syntax);
 
     Ok(CodeGenerator::new(
         spec_fns,
         spec_methods,
         func_preconds,
         method_preconds,
+        diagnostics,
     ))
 }
 
@@ -40,3 +50,262 @@ fn main() {
     let code = prettyplease::unparse(&syn::parse2(code).unwrap());
     std::fs::write("pre.rs", code).unwrap();
 }
+
+#[cfg(test)]
+#[test]
+fn macro_precondition_diagnostic() {
+    let generator = create_generator(
+        "fn push(v: u64)
+            requires
+                seq![1u64, 2u64].contains(v),
+                v < 10,
+        {}",
+    )
+    .unwrap();
+    let diagnostics = generator.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].item, "push");
+    assert!(diagnostics[0].reason.contains("seq!"));
+}
+
+#[cfg(test)]
+#[test]
+fn non_exec_call_diagnostic() {
+    let generator = create_generator(
+        "fn is_valid(v: u64) -> bool { v < 10 }
+
+        fn push(v: u64)
+            requires
+                is_valid(v),
+        {}",
+    )
+    .unwrap();
+    let diagnostics = generator.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].item, "push");
+    assert!(diagnostics[0].reason.contains("`is_valid`"));
+}
+
+#[cfg(test)]
+#[test]
+fn generic_impl_method_precondition() {
+    let generator = create_generator(
+        "impl<T: BitAlloc> BitAllocCascade16<T> {
+            fn dealloc(&mut self, key: usize)
+                requires
+                    key < 4096,
+            {}
+        }",
+    )
+    .unwrap();
+    assert!(generator.diagnostics().is_empty());
+    assert_eq!(
+        generator.get_method_preconds(),
+        vec!["BitAllocCascade16<T>::dealloc".to_owned()]
+    );
+    let code = generator.generate_all().to_string();
+    assert!(code.contains("impl < T : BitAlloc > BitAllocCascade16 < T >"));
+    assert!(code.contains("verieasy_pre_dealloc"));
+}
+
+#[cfg(test)]
+#[test]
+fn aliased_trait_precondition() {
+    let generator = create_generator(
+        "mod alloc {
+            pub trait BitAlloc {
+                fn dealloc(&mut self, key: usize)
+                    requires
+                        key < 16;
+            }
+        }
+
+        use crate::alloc::BitAlloc as Alloc;
+
+        impl Alloc for BitAlloc16 {
+            fn dealloc(&mut self, key: usize) {}
+        }",
+    )
+    .unwrap();
+    assert!(generator.diagnostics().is_empty());
+    assert_eq!(
+        generator.get_method_preconds(),
+        vec!["BitAlloc16::dealloc".to_owned()]
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn named_return_precondition() {
+    let generator = create_generator(
+        "fn take(v: u64) -> (r: u64)
+            requires
+                v < 10,
+                r > v,
+            ensures
+                r == v + 1,
+        {
+            v + 1
+        }",
+    )
+    .unwrap();
+    let diagnostics = generator.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].item, "take");
+    assert!(diagnostics[0].reason.contains("`r`"));
+    let code = generator.generate_all().to_string();
+    assert!(code.contains("verieasy_pre_take"));
+    assert!(!code.contains("r >"));
+}
+
+#[cfg(test)]
+#[test]
+fn lifetime_generic_precondition() {
+    let generator = create_generator(
+        "fn first<'a>(v: &'a [u64], n: u64) -> u64
+            requires
+                n < 10,
+        {
+            v[0]
+        }",
+    )
+    .unwrap();
+    assert!(generator.diagnostics().is_empty());
+    assert_eq!(generator.get_function_preconds(), vec!["first".to_owned()]);
+    let code = generator.generate_all().to_string();
+    assert!(code.contains("verieasy_pre_first < 'a >"));
+}
+
+#[cfg(test)]
+#[test]
+fn recommends_only_precondition() {
+    let mut generator = create_generator(
+        "fn push(v: u64)
+            recommends
+                v < 10,
+        {}",
+    )
+    .unwrap();
+    assert!(generator.get_function_preconds().is_empty());
+    let code = generator.generate_all().to_string();
+    assert!(!code.contains("verieasy_pre_push"));
+
+    generator.fold_recommends();
+    assert!(generator.diagnostics().is_empty());
+    assert_eq!(generator.get_function_preconds(), vec!["push".to_owned()]);
+    let code = generator.generate_all().to_string();
+    assert!(code.contains("verieasy_pre_push"));
+    assert!(code.contains("v < 10"));
+}
+
+#[cfg(test)]
+#[test]
+fn precondition_override_file() {
+    let dir = std::env::temp_dir().join(format!(
+        "verieasy_precondition_override-{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("verieasy_pre_push.rs"),
+        "fn verieasy_pre_push(v: u64) -> bool { v % 2 == 0 }",
+    )
+    .unwrap();
+    let mut generator = create_generator(
+        "fn push(v: u64)
+            requires
+                v < 10,
+        {}
+
+        fn pop(v: u64)
+            requires
+                v > 0,
+        {}",
+    )
+    .unwrap();
+    generator.apply_overrides(dir.to_str().unwrap()).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let diagnostics = generator.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].item, "push");
+    assert!(diagnostics[0].reason.contains("verieasy_pre_push.rs"));
+    let code = generator.generate_all().to_string();
+    assert!(code.contains("pub fn verieasy_pre_push (v : u64) -> bool { v % 2 == 0 }"));
+    assert!(!code.contains("v < 10"));
+    assert!(code.contains("v > 0"));
+}
+
+/// Evaluate a boolean expression over variables `a`, `b` and `c`.
+#[cfg(test)]
+fn eval_bool(expr: &ast::Expr, vars: [bool; 3]) -> bool {
+    use ast::{BinaryOp, Expr, UnaryOp};
+    match expr {
+        Expr::Path(path) => match path.path.to_string().as_str() {
+            "a" => vars[0],
+            "b" => vars[1],
+            "c" => vars[2],
+            other => panic!("unknown variable `{}`", other),
+        },
+        Expr::Unary(unary) => match unary.op {
+            UnaryOp::Not => !eval_bool(&unary.expr, vars),
+        },
+        Expr::Binary(binary) => {
+            let (left, right) = (
+                eval_bool(&binary.left, vars),
+                eval_bool(&binary.right, vars),
+            );
+            match binary.op {
+                BinaryOp::And => left && right,
+                BinaryOp::Or => left || right,
+                BinaryOp::Imply => !left || right,
+                op => panic!("unexpected operator {:?}", op),
+            }
+        }
+        other => panic!("unexpected expression {:?}", other),
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn chained_implication_truth_table() {
+    let cases: [(&str, fn(bool, bool, bool) -> bool); 5] = [
+        ("a ==> b ==> c", |a, b, c| !a || (!b || c)),
+        ("(a ==> b) ==> c", |a, b, c| !(!a || b) || c),
+        ("a && b ==> c", |a, b, c| !(a && b) || c),
+        ("a ==> b || c", |a, b, c| !a || (b || c)),
+        ("!a ==> b && c", |a, b, c| a || (b && c)),
+    ];
+    for (source, reference) in cases {
+        let expr =
+            ast::Expr::try_from(verus_syn::parse_str::<verus_syn::Expr>(source).unwrap()).unwrap();
+        for bits in 0..8 {
+            let vars = [bits & 1 != 0, bits & 2 != 0, bits & 4 != 0];
+            assert_eq!(
+                eval_bool(&expr, vars),
+                reference(vars[0], vars[1], vars[2]),
+                "`{}` with {:?}",
+                source,
+                vars
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn if_let_precondition_diagnostic() {
+    let generator = create_generator(
+        "fn take(v: Option<u64>)
+            requires
+                if let Some(x) = v { x < 10 } else { true },
+                v.is_some(),
+        {}",
+    )
+    .unwrap();
+    let diagnostics = generator.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].item, "take");
+    assert!(diagnostics[0].reason.contains("unsupported pattern"));
+    assert!(diagnostics[0].reason.contains("if let"));
+}
@@ -0,0 +1,163 @@
+//! Cache of verified functions, persisted across runs.
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+/// FNV-1a hash of `parts`, stable across Rust versions and platforms unlike `DefaultHasher`.
+///
+/// Parts are separated by a byte that never occurs in UTF-8, so that moving text from one part
+/// to the next changes the hash.
+pub fn stable_hash(parts: &[&str]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET_BASIS;
+    for part in parts {
+        for byte in part.bytes().chain([0xff]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    hash
+}
+
+/// A function verified by a formal component in a previous run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// Function name.
+    pub function: String,
+    /// Name of the formal component that verified the function.
+    pub component: String,
+    /// [`stable_hash`] of both function bodies, the signature, the precondition checker and the
+    /// component settings at verification time.
+    pub hash: u64,
+}
+
+/// Verified-function cache stored as a JSON file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VerifiedCache {
+    /// Cached entries.
+    pub entries: Vec<CacheEntry>,
+}
+
+impl VerifiedCache {
+    /// Load the cache from a JSON file. A missing file yields an empty cache.
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        if !std::path::Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read cache file: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| anyhow!("Failed to parse cache file: {}", e))
+    }
+
+    /// Store the cache to a JSON file.
+    pub fn store(&self, path: &str) -> anyhow::Result<()> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| anyhow!("Failed to serialize cache: {}", e))?;
+        std::fs::write(path, content).map_err(|e| anyhow!("Failed to write cache file: {}", e))
+    }
+
+    /// Whether `function` was verified by `component` with the same hash.
+    pub fn lookup(&self, function: &str, component: &str, hash: u64) -> bool {
+        self.entries.iter().any(|entry| {
+            entry.function == function && entry.component == component && entry.hash == hash
+        })
+    }
+
+    /// Record that `function` was verified by `component`, replacing any stale entry.
+    pub fn insert(&mut self, function: &str, component: &str, hash: u64) {
+        self.entries.retain(|entry| entry.function != function);
+        self.entries.push(CacheEntry {
+            function: function.to_owned(),
+            component: component.to_owned(),
+            hash,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckResult, Checker, CheckerOptions, Component, Source, log::init_test_logger};
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    /// Formal component verifying every function under checking and counting its runs.
+    struct CountingFormal {
+        name: &'static str,
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl Component for CountingFormal {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn is_formal(&self) -> bool {
+            true
+        }
+
+        fn run(&self, checker: &Checker) -> CheckResult {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            CheckResult {
+                ok: checker
+                    .under_checking_funcs
+                    .iter()
+                    .map(|func| func.metadata.name.clone())
+                    .collect(),
+                ..Default::default()
+            }
+        }
+    }
+
+    /// Check `f` against `body2` with a single counting component named `component`.
+    fn run_once(cache_path: &str, component: &'static str, body2: &str, runs: &Arc<AtomicUsize>) {
+        let mut checker = Checker::new(
+            Source::from_string("a.rs", "pub fn f(x: u8) -> u8 { x }").unwrap(),
+            Source::from_string("b.rs", &format!("pub fn f(x: u8) -> u8 {{ {} }}", body2)).unwrap(),
+            vec![Box::new(CountingFormal {
+                name: component,
+                runs: runs.clone(),
+            })],
+            Vec::new(),
+            CheckerOptions {
+                cache_path: Some(cache_path.to_owned()),
+                ..Default::default()
+            },
+        );
+        checker.run_all();
+        assert_eq!(checker.verified_funcs.len(), 1);
+    }
+
+    #[test]
+    fn second_run_reuses_verdicts() {
+        init_test_logger();
+        let path =
+            std::env::temp_dir().join(format!("veri-easy-cache-{}.json", std::process::id()));
+        let path = path.to_string_lossy();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        run_once(&path, "Formal", "x + 0", &runs);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        // Unchanged function and component: served from the cache
+        run_once(&path, "Formal", "x + 0", &runs);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        // A component outside the current workflow is not trusted
+        run_once(&path, "Other", "x + 0", &runs);
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        // A changed body is checked again
+        run_once(&path, "Other", "x | 0", &runs);
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        let _ = std::fs::remove_file(path.as_ref());
+    }
+
+    #[test]
+    fn stable_hash_separates_parts() {
+        assert_eq!(stable_hash(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_hash(&["ab", "c"]), stable_hash(&["ab", "c"]));
+        assert_ne!(stable_hash(&["ab", "c"]), stable_hash(&["a", "bc"]));
+    }
+}
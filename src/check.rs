@@ -1,9 +1,22 @@
 //! Veri-easy functional equivalence checker.
 use anyhow::Error;
+use quote::ToTokens;
+use regex::Regex;
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
 
 use crate::{
-    collect::{FunctionCollector, PathResolver, SymbolCollector, TypeCollector},
-    defs::{CommonFunction, Function, InstantiatedType, Path, PreciseType, Precondition, Type},
+    cache::{VerifiedCache, stable_hash},
+    collect::{
+        ConstCollector, DEFAULT_NONDETERMINISTIC, FunctionCollector, PathResolver, SymbolCollector,
+        TypeCollector, comparable_types, mutable_globals, nondeterministic_symbols, remove_defined,
+    },
+    defs::{
+        CommonFunction, Function, FunctionRole, InstantiatedType, Path, PreciseType, Precondition,
+        Type,
+    },
     log,
 };
 
@@ -19,31 +32,52 @@ pub struct Source {
     pub symbols: Vec<Path>,
     /// Instantiated generic types.
     pub inst_types: Vec<InstantiatedType>,
+    /// Types deriving `PartialEq` and `Debug`.
+    pub comparable_types: Vec<Path>,
 }
 
 impl Source {
     /// Open a source file from path and parse its content.
     pub fn open(path: &str) -> anyhow::Result<Self> {
         let content =
-            std::fs::read_to_string(&path).map_err(|_| anyhow::anyhow!("Failed to read source"))?;
+            std::fs::read_to_string(path).map_err(|_| anyhow::anyhow!("Failed to read source"))?;
+        Self::from_string(path, &content)
+    }
+
+    /// Parse a source from in-memory content. `name` is used in place of the file path.
+    pub fn from_string(name: &str, content: &str) -> anyhow::Result<Self> {
+        let mut content = content.to_owned();
         let mut syntax = syn::parse_file(&content)
             .map_err(|_| anyhow::anyhow!("Failed to parse source file"))?;
 
         // Resolve paths
         PathResolver::new().resolve_paths(&mut syntax);
         // Collect functions
-        let unique_funcs = FunctionCollector::new().collect(&syntax);
+        let mut unique_funcs = FunctionCollector::new().collect(&syntax);
+        // Role attributes are only known to Veri-easy, remove them before the source is compiled
+        for attr in FunctionRole::ATTRIBUTES {
+            let re = Regex::new(&format!(r"#\s*\[\s*{}\s*\]", attr)).unwrap();
+            content = re.replace_all(&content, "").into_owned();
+        }
+        // Collect types whose values can be compared as a whole
+        let comparable_types = comparable_types(&syntax);
+        // Collect crate-level constants, checked through appended wrapper functions
+        let (const_funcs, const_wrappers) =
+            ConstCollector::new().collect(&syntax, &comparable_types);
+        unique_funcs.extend(const_funcs);
+        content.push_str(&const_wrappers);
         // Collect symbols
         let symbols = SymbolCollector::new().collect(&syntax);
         // Collect instantiated generic types
         let inst_types = TypeCollector::new().collect(&syntax);
 
         Ok(Self {
-            path: path.to_owned(),
+            path: name.to_owned(),
             content,
             unique_funcs,
             symbols,
             inst_types,
+            comparable_types,
         })
     }
 
@@ -51,6 +85,13 @@ impl Source {
     pub fn append_content(&mut self, additional: &str) {
         self.content.push_str(additional);
     }
+
+    /// Append generated precondition code to the source, except for the functions and methods
+    /// the source already defines.
+    pub fn append_preconditions(&mut self, code: &str) {
+        let code = remove_defined(code, &self.content);
+        self.append_content(&code);
+    }
 }
 
 /// Typed check result
@@ -62,6 +103,27 @@ pub struct CheckResult {
     pub ok: Vec<Path>,
     /// Functions that failed the consistency check
     pub fail: Vec<Path>,
+    /// Per-function check time, if the component reports it
+    pub timings: Vec<(Path, Duration)>,
+    /// Counterexamples of failed functions, if the component reports them
+    pub counterexamples: Vec<(Path, String)>,
+    /// Failed functions a formal component found a concrete counterexample for. Unlike its other
+    /// failures, which are only undetermined, these are definitely inconsistent.
+    pub refuted: Vec<Path>,
+}
+
+impl Default for CheckResult {
+    /// A successful check result without any checked function.
+    fn default() -> Self {
+        Self {
+            status: Ok(()),
+            ok: Vec::new(),
+            fail: Vec::new(),
+            timings: Vec::new(),
+            counterexamples: Vec::new(),
+            refuted: Vec::new(),
+        }
+    }
 }
 
 impl CheckResult {
@@ -69,8 +131,7 @@ impl CheckResult {
     pub fn failed(e: Error) -> Self {
         Self {
             status: Err(e),
-            ok: Vec::new(),
-            fail: Vec::new(),
+            ..Default::default()
         }
     }
 }
@@ -88,10 +149,57 @@ pub trait Component {
         None
     }
 
+    /// Settings that change what the component proves, part of the cache key of the functions it
+    /// verifies. Cached verdicts are reused only while these settings are unchanged.
+    fn cache_key(&self) -> String {
+        String::new()
+    }
+
     /// Run the check component.
     fn run(&self, checker: &Checker) -> CheckResult;
 }
 
+/// Verdict of a component on a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verdict {
+    /// Passed a formal component.
+    Verified,
+    /// Passed a testing component.
+    Tested,
+    /// Failed a testing component, or was refuted by a formal component.
+    Failed,
+    /// Failed a formal component without being refuted.
+    Undetermined,
+}
+
+/// Exit code when all functions are verified.
+pub const EXIT_OK: i32 = 0;
+/// Exit code when a function failed a testing component or was refuted by a formal component.
+pub const EXIT_FAILED: i32 = 1;
+/// Exit code when a function was not verified, even if it was tested.
+pub const EXIT_UNCHECKED: i32 = 2;
+/// Exit code when the configuration or a source could not be loaded.
+pub const EXIT_ERROR: i32 = 3;
+
+/// Options of a [`Checker`], all disabled by default.
+#[derive(Debug, Clone, Default)]
+pub struct CheckerOptions {
+    /// Stop on the first function failed by a testing component.
+    pub strict: bool,
+    /// Stop when a formal component leaves any function undetermined.
+    pub strict_formal: bool,
+    /// Skip functions failed by a testing component in later formal components.
+    pub fail_fast_testing: bool,
+    /// Path of the verified-function cache file.
+    pub cache_path: Option<String>,
+    /// Directory to which components copy their generated harness sources.
+    pub dump_harness: Option<String>,
+    /// Draw pointer-sized integer arguments of harnesses from 64-bit domains.
+    pub fixed_width_usize: bool,
+    /// Also pair free functions whose signatures differ only in integer widths.
+    pub width_tolerant: bool,
+}
+
 /// The main Checker structure.
 ///
 /// Check function consistency between two sources through multiple components.
@@ -116,20 +224,38 @@ pub struct Checker {
     pub constructors: Vec<CommonFunction>,
     /// Getters (not checked directly).
     pub getters: Vec<CommonFunction>,
+    /// Types whose whole state is compared after method calls if they have no getter.
+    pub comparable_types: Vec<Type>,
     /// Preconditions (used to filter out tests that do not satisfy preconditions).
     pub preconditions: Vec<Precondition>,
-    /// Strict mode: exit on first error.
-    pub strict: bool,
+    /// Checker options.
+    options: CheckerOptions,
+    /// Functions verified by formal components in previous runs.
+    cache: VerifiedCache,
+    /// Wall-clock time spent by each executed component.
+    pub timings: Vec<(String, Duration)>,
+    /// Counterexamples reported by testing components.
+    pub counterexamples: Vec<(Path, String)>,
+    /// Verdicts of the executed components on each function, in order.
+    verdicts: Vec<(Path, String, Verdict)>,
 }
 
 impl Checker {
+    /// Create a checker running `steps` on the functions common to both sources.
     pub fn new(
         src1: Source,
         src2: Source,
         steps: Vec<Box<dyn Component>>,
         preconditions: Vec<Precondition>,
-        strict: bool,
+        options: CheckerOptions,
     ) -> Self {
+        let cache = match &options.cache_path {
+            Some(path) => VerifiedCache::load(path).unwrap_or_else(|e| {
+                log!(Brief, Warning, "{}. Starting with an empty cache.", e);
+                VerifiedCache::default()
+            }),
+            None => VerifiedCache::default(),
+        };
         let mut checker = Self {
             src1,
             src2,
@@ -140,15 +266,73 @@ impl Checker {
             failed_funcs: Vec::new(),
             constructors: Vec::new(),
             getters: Vec::new(),
+            comparable_types: Vec::new(),
             preconditions,
-            strict,
+            options,
+            cache,
+            timings: Vec::new(),
+            counterexamples: Vec::new(),
+            verdicts: Vec::new(),
         };
         checker.preprocess();
         checker
     }
 
+    /// Check two in-memory sources with the given components, without preconditions or cache.
+    ///
+    /// The sources are written to a temporary directory for the duration of the check, so that
+    /// components reading the source files (e.g. External) find them. Returns the checker after
+    /// all components have run, so that the verified, tested and failed functions can be
+    /// inspected.
+    pub fn check_from_strings(
+        src1: &str,
+        src2: &str,
+        components: Vec<Box<dyn Component>>,
+    ) -> anyhow::Result<Self> {
+        static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "veri-easy-{}-{}",
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| anyhow::anyhow!("Failed to create temporary directory: {}", e))?;
+        let sources = [("src1.rs", src1), ("src2.rs", src2)].map(|(name, content)| {
+            let path = dir.join(name);
+            std::fs::write(&path, content)
+                .map_err(|e| anyhow::anyhow!("Failed to write temporary source: {}", e))?;
+            Source::from_string(&path.to_string_lossy(), content)
+        });
+        let res = match sources {
+            [Ok(src1), Ok(src2)] => {
+                let mut checker = Self::new(src1, src2, components, Vec::new(), Default::default());
+                checker.run_all();
+                Ok(checker)
+            }
+            [Err(e), _] | [_, Err(e)] => Err(e),
+        };
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            log!(
+                Normal,
+                Warning,
+                "Failed to remove temporary directory `{}`: {}",
+                dir.display(),
+                e
+            );
+        }
+        res
+    }
+
+    /// Options the checker was created with.
+    pub fn options(&self) -> &CheckerOptions {
+        &self.options
+    }
+
     /// Run all steps in order
     pub fn run_all(&mut self) {
+        let precondition_sources = self.precondition_sources();
+        self.apply_cache(&precondition_sources);
+
         for component in &self.components {
             if self.under_checking_funcs.is_empty() {
                 log!(
@@ -160,8 +344,18 @@ impl Checker {
             }
 
             Self::log_component(component.as_ref());
+            if component.is_formal() && self.options.fail_fast_testing {
+                Self::skip_failed_funcs(
+                    &mut self.under_checking_funcs,
+                    &self.failed_funcs,
+                    component.name(),
+                );
+            }
 
+            let start = Instant::now();
             let res = component.run(&self);
+            self.timings
+                .push((component.name().to_owned(), start.elapsed()));
             if let Err(e) = res.status {
                 log!(
                     Brief,
@@ -178,6 +372,9 @@ impl Checker {
                 "Component `{}` completed.",
                 component.name()
             );
+            for (name, time) in &res.timings {
+                log!(Verbose, Info, "`{:?}` took {:.2?}", name, time);
+            }
 
             for name in &res.ok {
                 log!(Brief, Ok, "`{:?}` passed", name);
@@ -186,8 +383,18 @@ impl Checker {
                     .iter()
                     .find(|func2| func2.metadata.name == *name)
                 {
+                    let verdict = if component.is_formal() {
+                        Verdict::Verified
+                    } else {
+                        Verdict::Tested
+                    };
+                    self.verdicts
+                        .push((name.clone(), component.name().to_owned(), verdict));
                     if component.is_formal() {
                         // Formal component provides enough evidence to verify the function
+                        let hash =
+                            Self::cache_hash(func, component.as_ref(), &precondition_sources);
+                        self.cache.insert(&name.to_string(), component.name(), hash);
                         self.verified_funcs.push(func.clone());
                         // So we move it to verified_funcs, and need not check it further
                         self.under_checking_funcs
@@ -207,18 +414,41 @@ impl Checker {
             }
 
             for name in &res.fail {
-                if component.is_formal() {
-                    log!(Brief, Unsure, "`{:?}` undetermined", name);
-                } else {
+                // Formal failures are undetermined unless the component refuted the function
+                let definite = !component.is_formal() || res.refuted.contains(name);
+                if definite {
                     log!(Brief, Error, "`{:?}` failed", name);
+                } else {
+                    log!(Brief, Unsure, "`{:?}` undetermined", name);
+                }
+                if let Some((_, counterexample)) =
+                    res.counterexamples.iter().find(|(func, _)| func == name)
+                {
+                    log!(Brief, Simple, "{}", counterexample);
                 }
                 if let Some(func) = self
                     .under_checking_funcs
                     .iter()
                     .find(|func2| func2.metadata.name == *name)
                 {
-                    if !component.is_formal() {
-                        // Testing component provides evidence to show the function is inconsistent
+                    let verdict = if definite {
+                        Verdict::Failed
+                    } else {
+                        Verdict::Undetermined
+                    };
+                    self.verdicts
+                        .push((name.clone(), component.name().to_owned(), verdict));
+                    if definite {
+                        if self.options.fail_fast_testing && !component.is_formal() {
+                            log!(
+                                Normal,
+                                Info,
+                                "`{:?}` failed by `{}`, later formal components will skip it",
+                                name,
+                                component.name()
+                            );
+                        }
+                        // The component provides evidence to show the function is inconsistent
                         self.failed_funcs.push(func.clone());
                         // So we move it to failed_funcs, and need not check it further
                         self.under_checking_funcs
@@ -227,8 +457,11 @@ impl Checker {
                 }
             }
 
-            if !component.is_formal() && !res.fail.is_empty() && self.strict {
-                // Strict mode: stop on first error from testing component
+            self.counterexamples.extend(res.counterexamples);
+
+            let refuted = component.is_formal() && !res.refuted.is_empty();
+            if (!component.is_formal() && !res.fail.is_empty() || refuted) && self.options.strict {
+                // Strict mode: stop on first error from testing component or counterexample
                 log!(
                     Brief,
                     Warning,
@@ -236,6 +469,17 @@ impl Checker {
                 );
                 break;
             }
+            let undetermined =
+                component.is_formal() && res.fail.iter().any(|name| !res.refuted.contains(name));
+            if undetermined && self.options.strict_formal {
+                // Strict formal mode: stop on first undetermined result from formal component
+                log!(
+                    Brief,
+                    Warning,
+                    "Strict formal mode enabled, stopping further checks."
+                );
+                break;
+            }
 
             log!(
                 Normal,
@@ -246,22 +490,283 @@ impl Checker {
             self.print_state();
             log!(Brief, Simple, "");
         }
+
+        if let Some(path) = &self.options.cache_path
+            && let Err(e) = self.cache.store(path)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
     }
 
-    /// Print test results summary
-    pub fn print_summary(&self) {
-        log!(Brief, Simple, "");
+    /// Exclude functions that refer to nondeterministic symbols in either source.
+    ///
+    /// The symbols are `DEFAULT_NONDETERMINISTIC` and `denylist`, without those in `allowlist`,
+    /// plus the `static mut` items and thread-locals declared in each source.
+    pub fn exclude_nondeterministic(&mut self, denylist: &[String], allowlist: &[String]) {
+        let symbols = |src: &Source| {
+            let mut symbols: Vec<String> = DEFAULT_NONDETERMINISTIC
+                .iter()
+                .map(|s| s.to_string())
+                .chain(denylist.iter().cloned())
+                .collect();
+            if let Ok(syntax) = syn::parse_file(&src.content) {
+                symbols.extend(mutable_globals(&syntax));
+            }
+            symbols
+                .iter()
+                .filter(|s| !allowlist.contains(s))
+                .map(|s| Path::from(s.as_str()))
+                .collect::<Vec<_>>()
+        };
+        let (symbols1, symbols2) = (symbols(&self.src1), symbols(&self.src2));
+        self.under_checking_funcs.retain(|func| {
+            let mut found = nondeterministic_symbols(&func.body1, &symbols1);
+            for symbol in nondeterministic_symbols(&func.body2, &symbols2) {
+                if !found.contains(&symbol) {
+                    found.push(symbol);
+                }
+            }
+            if !found.is_empty() {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{:?}` uses nondeterministic symbols {:?}, it will not be checked",
+                    func.metadata.name,
+                    found
+                );
+            }
+            found.is_empty()
+        });
+    }
+
+    /// Restrict checking to functions whose body or signature in source 2 differs from `baseline`,
+    /// and to the functions calling them.
+    ///
+    /// `baseline` is an earlier version of source 2. Functions are matched by name, falling back to
+    /// identifier and signature for functions renamed after instantiated generic types. Callers are
+    /// found by identifier in the bodies, until no more are found.
+    pub fn retain_changed(&mut self, baseline: &Source) {
+        let mut keep = self
+            .under_checking_funcs
+            .iter()
+            .map(|func| {
+                let signature = func.signature2.as_ref().unwrap_or(&func.metadata.signature);
+                let base = match baseline
+                    .unique_funcs
+                    .iter()
+                    .find(|f| f.metadata.name == func.metadata.name)
+                {
+                    Some(f) => (f.metadata.signature == *signature).then_some(f),
+                    None => baseline.unique_funcs.iter().find(|f| {
+                        f.metadata.ident() == func.metadata.ident()
+                            && f.metadata.signature == *signature
+                            && f.body == func.body2
+                    }),
+                };
+                base.is_none_or(|base| base.body != func.body2)
+            })
+            .collect::<Vec<_>>();
+
+        // Callers of changed functions may behave differently as well
+        loop {
+            let changed = self
+                .under_checking_funcs
+                .iter()
+                .zip(&keep)
+                .filter(|(_, keep)| **keep)
+                .map(|(func, _)| func.metadata.ident())
+                .collect::<Vec<_>>();
+            let mut found = false;
+            for (func, keep) in self.under_checking_funcs.iter().zip(keep.iter_mut()) {
+                if !*keep && references_any(&func.body2, &changed) {
+                    log!(
+                        Normal,
+                        Info,
+                        "`{:?}` calls a function changed since baseline, keeping",
+                        func.metadata.name
+                    );
+                    *keep = true;
+                    found = true;
+                }
+            }
+            if !found {
+                break;
+            }
+        }
+
+        let mut keep = keep.into_iter();
+        self.under_checking_funcs.retain(|func| {
+            let keep = keep.next().unwrap();
+            if !keep {
+                log!(
+                    Normal,
+                    Info,
+                    "`{:?}` unchanged since baseline, skipping",
+                    func.metadata.name
+                );
+            }
+            keep
+        });
+    }
+
+    /// Pair free functions whose signatures differ only in integer widths, e.g. a `u16` function
+    /// widened to `u32`, except those already `matched`. Harnesses feed both with the narrower
+    /// types and compare the results as the wider ones.
+    fn width_tolerant_pairs(&self, matched: &[CommonFunction]) -> Vec<CommonFunction> {
+        let mut pairs = Vec::new();
+        for func in &self.src1.unique_funcs {
+            let is_matched = matched
+                .iter()
+                .any(|f| f.metadata.name == func.metadata.name);
+            if func.metadata.impl_type.is_some() || is_matched {
+                continue;
+            }
+            let Some(func2) = self.src2.unique_funcs.iter().find(|func2| {
+                func.metadata.name == func2.metadata.name
+                    && func
+                        .metadata
+                        .signature
+                        .eq_up_to_int_width(&func2.metadata.signature)
+            }) else {
+                continue;
+            };
+            log!(
+                Brief,
+                Info,
+                "`{:?}` is compared over the narrower integer types of both sources",
+                func.metadata.name
+            );
+            let mut common =
+                CommonFunction::new(func.metadata.clone(), func.body.clone(), func2.body.clone());
+            common.asyncness.1 = func2.metadata.signature.0.asyncness.is_some();
+            common.signature2 = Some(func2.metadata.signature.clone());
+            pairs.push(common);
+        }
+        pairs
+    }
+
+    /// Keep only the first `max` functions under checking, warning about the skipped ones.
+    ///
+    /// Constructors and getters are not functions under checking, so retained methods keep them.
+    pub fn limit_functions(&mut self, max: usize) {
+        if self.under_checking_funcs.len() <= max {
+            return;
+        }
+        let skipped = self.under_checking_funcs.split_off(max);
         log!(
             Brief,
-            Critical,
-            "Check Summary: Verified: {}, Tested: {}, Failed: {}",
-            self.verified_funcs.len(),
-            self.tested_funcs.len(),
-            self.failed_funcs.len(),
+            Warning,
+            "Checking only the first {} functions, skipping {:?}",
+            max,
+            skipped
         );
+    }
 
-        let unchecked: Vec<&Path> = self
-            .under_checking_funcs
+    /// Make sure functions failed by earlier testing components are not checked by `component`.
+    fn skip_failed_funcs(
+        under_checking: &mut Vec<CommonFunction>,
+        failed: &[CommonFunction],
+        component: &str,
+    ) {
+        under_checking.retain(|func| {
+            let is_failed = failed.iter().any(|f| f.metadata.name == func.metadata.name);
+            if is_failed {
+                log!(
+                    Normal,
+                    Info,
+                    "`{:?}` already failed testing, skipping in `{}`",
+                    func.metadata.name,
+                    component
+                );
+            }
+            !is_failed
+        });
+    }
+
+    /// Move functions verified in a previous run out of checking, if neither they nor the
+    /// settings of the verifying component changed since then. Only components of the current
+    /// workflow are trusted.
+    fn apply_cache(&mut self, precondition_sources: &[(Path, String)]) {
+        let mut cached = Vec::new();
+        for func in &self.under_checking_funcs {
+            let name = func.metadata.name.to_string();
+            let Some(component) = self.components.iter().find(|component| {
+                let hash = Self::cache_hash(func, component.as_ref(), precondition_sources);
+                self.cache.lookup(&name, component.name(), hash)
+            }) else {
+                continue;
+            };
+            log!(
+                Brief,
+                Ok,
+                "`{:?}` verified by `{}` in a previous run, skipping",
+                func.metadata.name,
+                component.name()
+            );
+            self.verdicts.push((
+                func.metadata.name.clone(),
+                format!("{} (cached)", component.name()),
+                Verdict::Verified,
+            ));
+            cached.push(func.clone());
+        }
+        self.under_checking_funcs
+            .retain(|func| !cached.iter().any(|c| c.metadata.name == func.metadata.name));
+        self.verified_funcs.extend(cached);
+    }
+
+    /// Source of the precondition checker of every function that has one, if a cache is used.
+    fn precondition_sources(&self) -> Vec<(Path, String)> {
+        if self.options.cache_path.is_none() || self.preconditions.is_empty() {
+            return Vec::new();
+        }
+        let Ok(syntax) = syn::parse_file(&self.src2.content) else {
+            return Vec::new();
+        };
+        let checkers = FunctionCollector::new().collect(&syntax);
+        self.preconditions
+            .iter()
+            .filter_map(|precondition| {
+                let checker_name = precondition.checker_name();
+                checkers
+                    .iter()
+                    .find(|checker| checker.metadata.name == checker_name)
+                    .map(|checker| (precondition.name.clone(), checker.body.clone()))
+            })
+            .collect()
+    }
+
+    /// Cache key of `func` verified by `component`: both bodies, the signature, the precondition
+    /// checker and the component settings.
+    fn cache_hash(
+        func: &CommonFunction,
+        component: &dyn Component,
+        precondition_sources: &[(Path, String)],
+    ) -> u64 {
+        // Width-tolerant pairs have a second signature
+        let signature = [Some(&func.metadata.signature), func.signature2.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|signature| signature.0.to_token_stream().to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let precondition = precondition_sources
+            .iter()
+            .find(|(name, _)| *name == func.metadata.name)
+            .map_or("", |(_, source)| source.as_str());
+        stable_hash(&[
+            &func.body1,
+            &func.body2,
+            &signature,
+            precondition,
+            component.name(),
+            &component.cache_key(),
+        ])
+    }
+
+    /// Functions checked neither formally nor by testing.
+    fn unchecked_funcs(&self) -> Vec<&Path> {
+        self.under_checking_funcs
             .iter()
             .filter(|f| {
                 !self
@@ -274,7 +779,36 @@ impl Checker {
                         .any(|vf| vf.metadata.name == f.metadata.name)
             })
             .map(|f| &f.metadata.name)
-            .collect();
+            .collect()
+    }
+
+    /// Process exit code summarizing the outcome: `EXIT_FAILED` if any function failed a testing
+    /// component or was refuted by a formal component, `EXIT_UNCHECKED` if any function was not
+    /// verified, including functions only tested and functions a formal component failed
+    /// without refuting them, and `EXIT_OK` if every function was verified.
+    pub fn exit_code(&self) -> i32 {
+        if !self.failed_funcs.is_empty() {
+            EXIT_FAILED
+        } else if !self.under_checking_funcs.is_empty() {
+            EXIT_UNCHECKED
+        } else {
+            EXIT_OK
+        }
+    }
+
+    /// Print test results summary
+    pub fn print_summary(&self) {
+        log!(Brief, Simple, "");
+        log!(
+            Brief,
+            Critical,
+            "Check Summary: Verified: {}, Tested: {}, Failed: {}",
+            self.verified_funcs.len(),
+            self.tested_funcs.len(),
+            self.failed_funcs.len(),
+        );
+
+        let unchecked = self.unchecked_funcs();
         if unchecked.is_empty() {
             // If all functions are checked, log success
             log!(Brief, Ok, "All functions have been checked, and no counterexamples were detected.");
@@ -293,6 +827,188 @@ impl Checker {
             let names: Vec<&Path> = self.failed_funcs.iter().map(|f| &f.metadata.name).collect();
             log!(Brief, Error, "Some functions failed checks: {:?}", names);
         }
+
+        self.print_table();
+        self.print_timings();
+    }
+
+    /// Print a summary combining the results of several workflow runs on the same sources.
+    ///
+    /// A function counts as failed if any run failed it, otherwise as verified if any run
+    /// verified it, otherwise as tested if any run tested it.
+    pub fn print_combined_summary(runs: &[(&str, Checker)]) {
+        let (verified, tested, failed) = Self::combined_results(runs);
+
+        log!(Brief, Simple, "");
+        let configs: Vec<&str> = runs.iter().map(|(path, _)| *path).collect();
+        log!(
+            Brief,
+            Critical,
+            "Combined Summary of {:?}: Verified: {}, Tested: {}, Failed: {}",
+            configs,
+            verified.len(),
+            tested.len(),
+            failed.len()
+        );
+        log!(Brief, Info, "Verified: {:?}", verified);
+        log!(Brief, Info, "Tested: {:?}", tested);
+        if !failed.is_empty() {
+            log!(Brief, Error, "Failed: {:?}", failed);
+        }
+    }
+
+    /// Verified, tested and failed functions over all `runs`. A function failed by any run is
+    /// failed, otherwise one verified by any run is verified.
+    fn combined_results<'a>(
+        runs: &'a [(&str, Checker)],
+    ) -> (Vec<&'a Path>, Vec<&'a Path>, Vec<&'a Path>) {
+        let names = |select: fn(&Checker) -> &Vec<CommonFunction>| {
+            let mut names: Vec<&Path> = Vec::new();
+            for (_, checker) in runs {
+                for func in select(checker) {
+                    if !names.contains(&&func.metadata.name) {
+                        names.push(&func.metadata.name);
+                    }
+                }
+            }
+            names
+        };
+        let failed = names(|c| &c.failed_funcs);
+        let mut verified = names(|c| &c.verified_funcs);
+        verified.retain(|name| !failed.contains(name));
+        let mut tested = names(|c| &c.tested_funcs);
+        tested.retain(|name| !failed.contains(name) && !verified.contains(name));
+        (verified, tested, failed)
+    }
+
+    /// Print a table of the final state of every function, with the components that verified,
+    /// tested, failed it or left it undetermined.
+    fn print_table(&self) {
+        let states = self.final_states();
+        if states.is_empty() {
+            return;
+        }
+        let mut rows = vec![[
+            "Function".to_owned(),
+            "State".to_owned(),
+            "Verified by".to_owned(),
+            "Tested by".to_owned(),
+            "Failed by".to_owned(),
+            "Undetermined by".to_owned(),
+        ]];
+        rows.extend(states);
+        let mut widths = [0; 6];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.len());
+            }
+        }
+
+        log!(Brief, Simple, "");
+        for row in rows {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(cell, width)| format!("{:<width$}", cell))
+                .collect::<Vec<_>>()
+                .join("  ");
+            log!(Brief, Simple, "  {}", line.trim_end());
+        }
+    }
+
+    /// Summary table row of every function: its name, final state, and the components that
+    /// verified, tested, failed it or left it undetermined ("-" if none).
+    fn final_states(&self) -> Vec<[String; 6]> {
+        let mut names: Vec<&Path> = Vec::new();
+        for func in [
+            &self.verified_funcs,
+            &self.failed_funcs,
+            &self.under_checking_funcs,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if !names.contains(&&func.metadata.name) {
+                names.push(&func.metadata.name);
+            }
+        }
+        let contains = |funcs: &Vec<CommonFunction>, name: &Path| {
+            funcs.iter().any(|func| func.metadata.name == *name)
+        };
+        let by = |name: &Path, verdict: Verdict| {
+            let components = self
+                .verdicts
+                .iter()
+                .filter(|(func, _, v)| func == name && *v == verdict)
+                .map(|(_, component, _)| component.as_str())
+                .collect::<Vec<_>>();
+            if components.is_empty() {
+                "-".to_owned()
+            } else {
+                components.join(", ")
+            }
+        };
+
+        let mut rows = Vec::new();
+        for name in names {
+            let state = if contains(&self.failed_funcs, name) {
+                "Failed"
+            } else if contains(&self.verified_funcs, name) {
+                "Verified"
+            } else if contains(&self.tested_funcs, name) {
+                "Tested"
+            } else {
+                "Undetermined"
+            };
+            rows.push([
+                format!("{:?}", name),
+                state.to_owned(),
+                by(name, Verdict::Verified),
+                by(name, Verdict::Tested),
+                by(name, Verdict::Failed),
+                by(name, Verdict::Undetermined),
+            ]);
+        }
+        rows
+    }
+
+    /// Print time spent by each component
+    fn print_timings(&self) {
+        if self.timings.is_empty() {
+            return;
+        }
+        log!(Normal, Simple, "");
+        log!(Normal, Info, "Component timings:");
+        for (name, time) in &self.timings {
+            log!(Normal, Simple, "  {:<20} {:>10.2?}", name, time);
+        }
+        let total: Duration = self.timings.iter().map(|(_, time)| *time).sum();
+        log!(Normal, Simple, "  {:<20} {:>10.2?}", "Total", total);
+    }
+
+    /// Print matched common functions, constructors, getters and unique functions of each source.
+    pub fn print_functions(&self) {
+        let print = |title: &str, names: Vec<&Path>| {
+            log!(Brief, Critical, "{} ({}):", title, names.len());
+            for name in names {
+                log!(Brief, Simple, "  {:?}", name);
+            }
+        };
+        let common = [
+            ("Common functions", &self.under_checking_funcs),
+            ("Constructors", &self.constructors),
+            ("Getters", &self.getters),
+        ];
+        for (title, funcs) in common {
+            print(title, funcs.iter().map(|f| &f.metadata.name).collect());
+        }
+        let unique = [
+            ("Source 1 unique functions", &self.src1.unique_funcs),
+            ("Source 2 unique functions", &self.src2.unique_funcs),
+        ];
+        for (title, funcs) in unique {
+            print(title, funcs.iter().map(|f| &f.metadata.name).collect());
+        }
     }
 
     /// Print current state of the checker
@@ -330,11 +1046,55 @@ impl Checker {
                 func.metadata.name == func2.metadata.name
                     && func.metadata.signature == func2.metadata.signature
             }) {
-                common_funcs.push(CommonFunction::new(
-                    func.metadata.clone(),
-                    func.body.clone(),
-                    func2.body.clone(),
-                ));
+                // Attribute drift (e.g. an added `#[inline]`) is reported but not checked
+                if let Some((only1, only2)) = attribute_drift(&func.attrs, &func2.attrs) {
+                    log!(
+                        Normal,
+                        Info,
+                        "Attributes of `{:?}` differ: only in source 1 {:?}, only in source 2 {:?}",
+                        func.metadata.name,
+                        only1,
+                        only2
+                    );
+                }
+                // A role attribute in either source is enough
+                let mut metadata = func.metadata.clone();
+                metadata.role = metadata.role.or(func2.metadata.role);
+                let mut common =
+                    CommonFunction::new(metadata, func.body.clone(), func2.body.clone());
+                // Signatures match regardless of `async`, so a sync function may become async
+                common.asyncness.1 = func2.metadata.signature.0.asyncness.is_some();
+                common_funcs.push(common);
+            }
+        }
+
+        if self.options.width_tolerant {
+            let pairs = self.width_tolerant_pairs(&common_funcs);
+            common_funcs.extend(pairs);
+        }
+
+        // Warn about functions with the same name but different signatures
+        for func in &self.src1.unique_funcs {
+            if common_funcs
+                .iter()
+                .any(|f| f.metadata.name == func.metadata.name)
+            {
+                continue;
+            }
+            if let Some(func2) = self
+                .src2
+                .unique_funcs
+                .iter()
+                .find(|func2| func.metadata.name == func2.metadata.name)
+                && let Some(reason) = func.metadata.signature.mismatch(&func2.metadata.signature)
+            {
+                log!(
+                    Brief,
+                    Warning,
+                    "`{:?}` exists in both sources but signatures differ ({}), it will not be checked",
+                    func.metadata.name,
+                    reason
+                );
             }
         }
 
@@ -350,18 +1110,15 @@ impl Checker {
                 .any(|func2| func.metadata.name == func2.metadata.name)
         });
 
-        // Get the common instantiated generic types
-        let mut common_inst_types = Vec::new();
-        for inst_type in &self.src1.inst_types {
-            if let Some(_) = self
-                .src2
-                .inst_types
-                .iter()
-                .find(|inst_type2| inst_type == *inst_type2)
-            {
-                common_inst_types.push(inst_type.clone());
+        // Instantiated generic types of both sources. An alias declared in only one source is
+        // declared in the other one as well once used, so that harnesses can name it in both.
+        let mut inst_types = self.src1.inst_types.clone();
+        for inst_type in &self.src2.inst_types {
+            if !inst_types.iter().any(|t| t.alias == inst_type.alias) {
+                inst_types.push(inst_type.clone());
             }
         }
+        let mut used_inst_types: Vec<InstantiatedType> = Vec::new();
 
         // If a common function has name `Foo<T>::foo()`, and there is an instantiated
         // type `FB = Foo<Bar>`, We need to replace `Foo<T>::foo()` with `FB::foo()`
@@ -371,8 +1128,8 @@ impl Checker {
             let mut renamed = false;
             if let Some(impl_type) = &func.metadata.impl_type {
                 // Check against instantiated types
-                for inst_type in &self.src1.inst_types {
-                    if inst_type.concrete.eq_ignore_generics(impl_type) {
+                for inst_type in &inst_types {
+                    if inst_type.concrete.eq_generic_shape(impl_type) {
                         let mut func = func.clone();
                         // Update the impl_type to the instantiated alias type
                         func.metadata.impl_type =
@@ -380,6 +1137,9 @@ impl Checker {
                         func.metadata.name = inst_type.alias.clone().join(func.metadata.ident());
                         updated_common_funcs.push(func);
                         renamed = true;
+                        if !used_inst_types.contains(inst_type) {
+                            used_inst_types.push(inst_type.clone());
+                        }
                     }
                 }
             }
@@ -387,14 +1147,34 @@ impl Checker {
                 updated_common_funcs.push(func);
             }
         }
+        for inst_type in &used_inst_types {
+            let decl = format!(
+                "\npub type {:?} = {:?};\n",
+                inst_type.alias,
+                inst_type.concrete.to_path()
+            );
+            for src in [&mut self.src1, &mut self.src2] {
+                if !src.inst_types.iter().any(|t| t.alias == inst_type.alias) {
+                    log!(
+                        Verbose,
+                        Info,
+                        "Declare `{:?}` in `{}`",
+                        inst_type.alias,
+                        src.path
+                    );
+                    src.append_content(&decl);
+                }
+            }
+        }
 
         // Update precondition check functions similarly
         let mut updated_preconditions = Vec::new();
         for func in &self.preconditions {
             let mut renamed = false;
             if let Some(impl_type) = &func.impl_type {
-                // Check against instantiated types
-                for inst_type in &self.src1.inst_types {
+                // Check against instantiated types. Precondition types are parsed from names, so
+                // only base paths are compared.
+                for inst_type in &inst_types {
                     if inst_type.concrete.eq_ignore_generics(impl_type) {
                         let mut func = func.clone();
                         // Update the impl_type to the instantiated alias type
@@ -424,6 +1204,29 @@ impl Checker {
             .cloned()
             .collect();
 
+        // Types deriving `PartialEq` and `Debug` in both sources, looked up through their alias
+        let base_path = |ty: &Type| match ty {
+            Type::Generic(generic) => generic.path.clone(),
+            Type::Precise(precise) => inst_types
+                .iter()
+                .find(|inst_type| inst_type.alias == precise.0)
+                .map(|inst_type| match &inst_type.concrete {
+                    Type::Generic(generic) => generic.path.clone(),
+                    Type::Precise(concrete) => concrete.0.clone(),
+                })
+                .unwrap_or_else(|| precise.0.clone()),
+        };
+        self.comparable_types = self
+            .constructors
+            .iter()
+            .filter_map(|constructor| constructor.metadata.impl_type.clone())
+            .filter(|ty| {
+                let path = base_path(ty);
+                self.src1.comparable_types.contains(&path)
+                    && self.src2.comparable_types.contains(&path)
+            })
+            .collect();
+
         updated_common_funcs.retain(|f| !f.metadata.is_constructor() && !f.metadata.is_getter());
         self.under_checking_funcs = updated_common_funcs;
     }
@@ -447,3 +1250,470 @@ impl Checker {
         }
     }
 }
+
+/// Whether the token string `body` mentions any of `idents`.
+fn references_any(body: &str, idents: &[String]) -> bool {
+    body.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .any(|token| idents.iter().any(|ident| ident == token))
+}
+
+/// Attributes only in `attrs1` and only in `attrs2`, or `None` if both have the same attributes.
+fn attribute_drift<'a>(
+    attrs1: &'a [String],
+    attrs2: &'a [String],
+) -> Option<(Vec<&'a String>, Vec<&'a String>)> {
+    let only1: Vec<_> = attrs1.iter().filter(|a| !attrs2.contains(a)).collect();
+    let only2: Vec<_> = attrs2.iter().filter(|a| !attrs1.contains(a)).collect();
+    (!only1.is_empty() || !only2.is_empty()).then_some((only1, only2))
+}
+
+/// Checker running `components` over two in-memory sources, without preconditions and with
+/// default options, for unit tests.
+#[cfg(test)]
+pub(crate) fn test_checker(src1: &str, src2: &str, components: Vec<Box<dyn Component>>) -> Checker {
+    crate::log::init_test_logger();
+    Checker::new(
+        Source::from_string("a.rs", src1).unwrap(),
+        Source::from_string("b.rs", src2).unwrap(),
+        components,
+        Vec::new(),
+        CheckerOptions::default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{Identical, Kani},
+        config::KaniConfig,
+        log::init_test_logger,
+    };
+
+    /// Sorted names of `funcs`.
+    fn names(funcs: &[CommonFunction]) -> Vec<String> {
+        let mut names = funcs
+            .iter()
+            .map(|f| f.metadata.name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Component passing or failing every function under checking.
+    struct Uniform {
+        name: &'static str,
+        formal: bool,
+        pass: bool,
+    }
+
+    impl Uniform {
+        fn boxed(name: &'static str, formal: bool, pass: bool) -> Box<dyn Component> {
+            Box::new(Self { name, formal, pass })
+        }
+    }
+
+    impl Component for Uniform {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn is_formal(&self) -> bool {
+            self.formal
+        }
+
+        fn run(&self, checker: &Checker) -> CheckResult {
+            let funcs = checker
+                .under_checking_funcs
+                .iter()
+                .map(|func| func.metadata.name.clone())
+                .collect::<Vec<_>>();
+            let (ok, fail) = if self.pass {
+                (funcs, vec![])
+            } else {
+                (vec![], funcs)
+            };
+            CheckResult {
+                status: Ok(()),
+                ok,
+                fail,
+                ..Default::default()
+            }
+        }
+    }
+
+    const SRC: &str = "pub fn f(x: u8) -> u8 { x }
+        pub fn g(x: u8) -> u8 { x }";
+
+    #[test]
+    fn retain_changed_keeps_changed_functions_and_callers() {
+        let src1 = "pub fn helper(x: u8) -> u8 { x + 1 }
+            pub fn caller(x: u8) -> u8 { helper(x) * 2 }
+            pub fn same(x: u8) -> u8 { x }";
+        let baseline = src1;
+        let src2 = "pub fn helper(x: u8) -> u8 { 1 + x }
+            pub fn caller(x: u8) -> u8 { helper(x) * 2 }
+            pub fn same(x: u8) -> u8 { x }";
+        let mut checker = test_checker(src1, src2, vec![]);
+        checker.retain_changed(&Source::from_string("base.rs", baseline).unwrap());
+        assert_eq!(names(&checker.under_checking_funcs), ["caller", "helper"]);
+    }
+
+    #[test]
+    fn retain_changed_compares_signatures() {
+        let src = "pub fn f(x: u8) -> u8 { x }";
+        let mut checker = test_checker(src, src, vec![]);
+        let baseline = Source::from_string("base.rs", "pub fn f(x: u16) -> u16 { x }").unwrap();
+        checker.retain_changed(&baseline);
+        assert_eq!(checker.under_checking_funcs.len(), 1);
+    }
+
+    #[test]
+    fn one_timing_per_executed_component() {
+        let mut checker = test_checker(
+            SRC,
+            SRC,
+            vec![
+                Uniform::boxed("Testing", false, true),
+                Uniform::boxed("Formal", true, true),
+                // Nothing is left to check when it comes
+                Uniform::boxed("Unused", true, true),
+            ],
+        );
+        checker.run_all();
+        let components = checker
+            .timings
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(components, ["Testing", "Formal"]);
+    }
+
+    #[test]
+    fn common_functions_are_listed() {
+        let src1 = "pub struct S(u8);
+            impl S {
+                pub fn verieasy_new(x: u8) -> Self { S(x) }
+                #[verieasy_getter]
+                pub fn get(&self) -> u8 { self.0 }
+            }
+            pub fn f(x: u8) -> u8 { x }
+            pub fn only1(x: u8) -> u8 { x }";
+        let src2 = src1.replace("only1", "only2");
+        let checker = test_checker(src1, &src2, vec![]);
+        checker.print_functions();
+
+        assert_eq!(names(&checker.under_checking_funcs), ["f"]);
+        assert_eq!(names(&checker.constructors), ["S::verieasy_new"]);
+        assert_eq!(names(&checker.getters), ["S::get"]);
+        let unique = |src: &Source| {
+            src.unique_funcs
+                .iter()
+                .map(|f| f.metadata.name.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(unique(&checker.src1), ["only1"]);
+        assert_eq!(unique(&checker.src2), ["only2"]);
+    }
+
+    #[test]
+    fn signature_mismatch_is_explained() {
+        let src1 = "pub fn f(x: u8, y: bool) -> u8 { x }";
+        let src2 = "pub fn f(x: u8, y: char) -> u8 { x }";
+        let checker = test_checker(src1, src2, vec![]);
+        assert!(checker.under_checking_funcs.is_empty());
+
+        // The pair found by the diagnostics pass, reported with the differing argument
+        let sig1 = &checker.src1.unique_funcs[0].metadata.signature;
+        let sig2 = &checker.src2.unique_funcs[0].metadata.signature;
+        let reason = sig1.mismatch(sig2).unwrap();
+        assert_eq!(reason, "argument 1 `y` has type `bool` vs `char`");
+        assert_eq!(sig1.mismatch(sig1), None);
+    }
+
+    #[test]
+    fn attribute_drift_is_informational() {
+        let src1 = "pub fn f(x: u8) -> u8 { x }";
+        let src2 = "#[inline] pub fn f(x: u8) -> u8 { x }";
+        let attrs1 = &Source::from_string("a.rs", src1).unwrap().unique_funcs[0].attrs;
+        let attrs2 = &Source::from_string("b.rs", src2).unwrap().unique_funcs[0].attrs;
+        let (only1, only2) = attribute_drift(attrs1, attrs2).unwrap();
+        assert!(only1.is_empty());
+        assert_eq!(only2, [&"# [inline]".to_owned()]);
+        assert_eq!(attribute_drift(attrs2, attrs2), None);
+
+        // The drift does not keep the function from being checked
+        let mut checker = test_checker(src1, src2, vec![Uniform::boxed("Formal", true, true)]);
+        checker.run_all();
+        assert_eq!(names(&checker.verified_funcs), ["f"]);
+    }
+
+    #[test]
+    fn combined_results_merge_runs() {
+        let src2 = "pub fn f(x: u8) -> u8 { x }
+            pub fn g(x: u8) -> u8 { x + 0 }";
+        let mut fast = test_checker(SRC, src2, vec![Box::new(Identical)]);
+        fast.run_all();
+        let testing = Uniform::boxed("Testing", false, true);
+        let mut thorough = test_checker(SRC, src2, vec![testing]);
+        thorough.run_all();
+
+        // `g` differs syntactically, so only the second run covers it
+        let runs = [("fast.toml", fast), ("thorough.toml", thorough)];
+        let (verified, tested, failed) = Checker::combined_results(&runs);
+        assert_eq!(verified, [&Path::from("f")]);
+        assert_eq!(tested, [&Path::from("g")]);
+        assert!(failed.is_empty());
+    }
+
+    #[test]
+    fn nondeterministic_functions_are_excluded() {
+        let src = "static mut COUNT: u32 = 0;
+            pub fn stamp() -> u64 {
+                let now = std::time::SystemTime::now();
+                now.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs()
+            }
+            pub fn bump() -> u32 { unsafe { COUNT += 1; COUNT } }
+            pub fn pure(x: u8) -> u8 { x }
+            pub fn pid() -> u32 { std::process::id() }";
+        let mut checker = test_checker(src, src, vec![]);
+        let body = &checker.under_checking_funcs[0].body1;
+        let found = nondeterministic_symbols(body, &[Path::from("SystemTime::now")]);
+        assert_eq!(found, ["SystemTime::now"]);
+
+        // `process::id` is allowed explicitly
+        checker.exclude_nondeterministic(&[], &["process::id".to_owned()]);
+        assert_eq!(names(&checker.under_checking_funcs), ["pid", "pure"]);
+    }
+
+    #[test]
+    fn lifetime_generic_functions_are_checked() {
+        let src = "pub fn f<'a>(x: &'a u8) -> u8 { *x }
+            pub fn g<T: Copy>(x: T) -> T { x }
+            pub fn h<const N: usize>() -> usize { N }";
+        let mut checker = test_checker(src, src, vec![Box::new(Identical)]);
+        // Type and const generics cannot be instantiated by harnesses
+        assert_eq!(names(&checker.under_checking_funcs), ["f"]);
+        checker.run_all();
+        assert_eq!(names(&checker.verified_funcs), ["f"]);
+    }
+
+    #[test]
+    fn alias_in_one_source_renames_methods() {
+        let src1 = "pub struct Foo<T>(T);
+            impl<T> Foo<T> {
+                pub fn len(&self) -> u8 { 1 }
+            }";
+        let src2 = format!("{}\npub type FB = Foo<u8>;", src1);
+        let checker = test_checker(src1, &src2, vec![]);
+        assert_eq!(names(&checker.under_checking_funcs), ["FB::len"]);
+        // The alias is declared in the source lacking it, so harnesses can name it
+        assert!(checker.src1.content.contains("pub type FB = Foo<u8>;"));
+    }
+
+    #[test]
+    fn undetermined_formal_result_stops_strict_formal() {
+        let run = |strict_formal| {
+            let components = vec![
+                Uniform::boxed("kani", true, false),
+                Uniform::boxed("pbt", false, true),
+            ];
+            let mut checker = test_checker(SRC, SRC, components);
+            checker.options.strict_formal = strict_formal;
+            checker.run_all();
+            checker
+        };
+        assert_eq!(names(&run(false).tested_funcs), ["f", "g"]);
+        // Testing is never reached
+        let checker = run(true);
+        assert!(checker.tested_funcs.is_empty());
+        assert_eq!(names(&checker.under_checking_funcs), ["f", "g"]);
+    }
+
+    /// Formal component refuting `g` with a counterexample.
+    struct RefuteG;
+
+    impl Component for RefuteG {
+        fn name(&self) -> &str {
+            "exhaustive"
+        }
+
+        fn is_formal(&self) -> bool {
+            true
+        }
+
+        fn run(&self, _checker: &Checker) -> CheckResult {
+            CheckResult {
+                fail: vec![Path::from("g")],
+                counterexamples: vec![(Path::from("g"), "g(0)".to_owned())],
+                refuted: vec![Path::from("g")],
+                ..Default::default()
+            }
+        }
+    }
+
+    #[test]
+    fn refutation_stops_strict_but_not_strict_formal() {
+        let run = |strict, strict_formal| {
+            let components = vec![Box::new(RefuteG), Uniform::boxed("pbt", false, true)];
+            let mut checker = test_checker(SRC, SRC, components);
+            checker.options.strict = strict;
+            checker.options.strict_formal = strict_formal;
+            checker.run_all();
+            checker
+        };
+        // A refuted function fails, it is not undetermined
+        let checker = run(false, true);
+        assert_eq!(names(&checker.failed_funcs), ["g"]);
+        assert_eq!(names(&checker.tested_funcs), ["f"]);
+        // Testing is never reached
+        let checker = run(true, false);
+        assert_eq!(names(&checker.failed_funcs), ["g"]);
+        assert!(checker.tested_funcs.is_empty());
+    }
+
+    #[test]
+    fn exit_code_reflects_outcome() {
+        let exit_code = |components| {
+            let mut checker = test_checker(SRC, SRC, components);
+            checker.run_all();
+            checker.exit_code()
+        };
+        assert_eq!(exit_code(vec![Uniform::boxed("kani", true, true)]), EXIT_OK);
+        // Testing alone does not verify functions
+        let tested = exit_code(vec![Uniform::boxed("pbt", false, true)]);
+        assert_eq!(tested, EXIT_UNCHECKED);
+        let components = vec![
+            Uniform::boxed("pbt", false, true),
+            Uniform::boxed("kani", true, true),
+        ];
+        assert_eq!(exit_code(components), EXIT_OK);
+        let failed = exit_code(vec![Uniform::boxed("pbt", false, false)]);
+        assert_eq!(failed, EXIT_FAILED);
+        let undetermined = exit_code(vec![Uniform::boxed("kani", true, false)]);
+        assert_eq!(undetermined, EXIT_UNCHECKED);
+    }
+
+    #[test]
+    fn table_lists_every_function_state() {
+        let src2 = "pub fn f(x: u8) -> u8 { x }
+            pub fn g(x: u8) -> u8 { x + 0 }";
+        let components = vec![
+            Box::new(Identical) as Box<dyn Component>,
+            Uniform::boxed("kani", true, false),
+            Uniform::boxed("pbt", false, true),
+        ];
+        let mut checker = test_checker(SRC, src2, components);
+        checker.run_all();
+        let states = checker.final_states();
+        let row = |cells: [&str; 6]| cells.map(str::to_owned);
+        assert_eq!(states.len(), 2);
+        assert!(states.contains(&row(["f", "Verified", "Identical", "-", "-", "-"])));
+        assert!(states.contains(&row(["g", "Tested", "-", "pbt", "-", "kani"])));
+    }
+
+    #[test]
+    fn trait_default_methods_are_checked() {
+        let src1 = "pub trait Shape {
+                fn sides(&self) -> u8;
+                fn corners(&self) -> u8 { self.sides() }
+            }";
+        let src2 = src1.replace("self.sides()", "self.sides() + 0");
+        let mut same = test_checker(src1, src1, vec![Box::new(Identical)]);
+        assert_eq!(names(&same.under_checking_funcs), ["Shape::corners"]);
+        same.run_all();
+        assert_eq!(names(&same.verified_funcs), ["Shape::corners"]);
+
+        let mut changed = test_checker(src1, &src2, vec![Box::new(Identical)]);
+        changed.run_all();
+        assert!(changed.verified_funcs.is_empty());
+    }
+
+    #[test]
+    fn tagged_methods_are_constructors_and_getters() {
+        let src = "pub struct S(u8);
+            impl S {
+                #[verieasy_constructor]
+                pub fn new(x: u8) -> Self { S(x) }
+                #[verieasy_getter]
+                pub fn snapshot(&self) -> u8 { self.0 }
+                pub fn verieasy_get(&self) -> u8 { self.0 }
+                pub fn bump(&mut self) { self.0 += 1; }
+            }";
+        let checker = test_checker(src, src, vec![]);
+        assert_eq!(names(&checker.constructors), ["S::new"]);
+        assert_eq!(names(&checker.getters), ["S::snapshot", "S::verieasy_get"]);
+        assert_eq!(names(&checker.under_checking_funcs), ["S::bump"]);
+    }
+
+    #[test]
+    fn function_cap_keeps_constructors() {
+        let src = "pub fn f(x: u8) -> u8 { x }
+            pub struct S(u8);
+            impl S {
+                pub fn verieasy_new(x: u8) -> Self { S(x) }
+                #[verieasy_getter]
+                pub fn get(&self) -> u8 { self.0 }
+                pub fn bump(&mut self) { self.0 += 1; }
+            }
+            pub fn g(x: u8) -> u8 { x }";
+        let mut checker = test_checker(src, src, vec![]);
+        let order = checker
+            .under_checking_funcs
+            .iter()
+            .map(|func| func.metadata.name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(order, ["f", "S::bump", "g"]);
+        checker.limit_functions(2);
+        assert_eq!(names(&checker.under_checking_funcs), ["S::bump", "f"]);
+        assert_eq!(names(&checker.constructors), ["S::verieasy_new"]);
+        assert_eq!(names(&checker.getters), ["S::get"]);
+    }
+
+    #[test]
+    fn widened_pairs_are_common_functions() {
+        init_test_logger();
+        let src1 = "pub fn f(x: u16) -> u16 { x }
+            pub fn g(x: u8) -> u8 { x }";
+        let src2 = "pub fn f(x: u32) -> u32 { x }
+            pub fn g(x: u8) -> u8 { x }";
+        let options = CheckerOptions {
+            width_tolerant: true,
+            ..Default::default()
+        };
+        let source = |name, src| Source::from_string(name, src).unwrap();
+        let widened = Checker::new(
+            source("a.rs", src1),
+            source("b.rs", src2),
+            vec![],
+            Vec::new(),
+            options,
+        );
+        assert_eq!(names(&widened.under_checking_funcs), ["f", "g"]);
+        // `f` is no longer reported as unique to either source
+        assert!(widened.src1.unique_funcs.is_empty());
+        assert!(widened.src2.unique_funcs.is_empty());
+
+        let exact = test_checker(src1, src2, vec![]);
+        assert_eq!(names(&exact.under_checking_funcs), ["g"]);
+        assert_eq!(exact.src1.unique_funcs.len(), 1);
+    }
+
+    #[test]
+    fn cache_hash_tracks_bodies_and_settings() {
+        let hash = |src2: &str, config: KaniConfig| {
+            let checker = test_checker(SRC, src2, vec![]);
+            let func = &checker.under_checking_funcs[0];
+            Checker::cache_hash(func, &Kani::new(config), &[])
+        };
+        let changed = SRC.replacen("{ x }", "{ x + 0 }", 1);
+        let catch_panic = KaniConfig {
+            catch_panic: true,
+            ..Default::default()
+        };
+        let base = hash(SRC, KaniConfig::default());
+        assert_eq!(base, hash(SRC, KaniConfig::default()));
+        assert_ne!(base, hash(&changed, KaniConfig::default()));
+        assert_ne!(base, hash(SRC, catch_panic));
+    }
+}
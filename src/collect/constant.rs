@@ -0,0 +1,108 @@
+//! Collect crate-level constants from a Rust program.
+//!
+//! Each constant `NAME` is checked as a zero-argument function `verieasy_const_NAME`, whose
+//! wrapper is appended to the source so that harnesses can call it like any other function.
+
+use crate::{
+    defs::{Function, FunctionMetadata, Path, Signature},
+    generate::is_comparable_item,
+    log,
+};
+use quote::{ToTokens, format_ident, quote};
+use syn::{Item, ItemConst, visit_mut::VisitMut};
+
+/// Collector of constants defined at the crate root.
+pub struct ConstCollector {
+    /// Collected constants.
+    consts: Vec<ItemConst>,
+}
+
+impl ConstCollector {
+    /// Create a new ConstCollector.
+    pub fn new() -> Self {
+        Self { consts: Vec::new() }
+    }
+
+    /// Collect constants, returning the functions to check and the wrapper code to be
+    /// appended to the source.
+    ///
+    /// Constants whose values are not known to be comparable are skipped, `comparable_types`
+    /// lists the types of the source that derive `PartialEq` and `Debug`.
+    pub fn collect(
+        mut self,
+        syntax: &syn::File,
+        comparable_types: &[Path],
+    ) -> (Vec<Function>, String) {
+        for item in &syntax.items {
+            if let Item::Const(item) = item {
+                if item.ident == "_" || !item.generics.params.is_empty() {
+                    continue;
+                } // Skip unnamed and generic constants
+                if item.attrs.iter().any(|attr| attr.path().is_ident("ignore")) {
+                    continue;
+                } // Skip constants marked with #[ignore]
+                if !is_comparable_const(&item.ty, comparable_types) {
+                    log!(
+                        Normal,
+                        Warning,
+                        "Constant `{}` of type `{}` is not comparable, it will not be checked",
+                        item.ident,
+                        item.ty.to_token_stream()
+                    );
+                    continue;
+                }
+                let mut item = item.clone();
+                // Wrappers return the value, elided lifetimes of constants are `'static`
+                StaticLifetimes.visit_type_mut(&mut item.ty);
+                self.consts.push(item);
+            }
+        }
+
+        let mut functions = Vec::new();
+        let mut wrappers = String::new();
+        for item in self.consts {
+            let (ident, ty, expr) = (&item.ident, &item.ty, &item.expr);
+            let wrapper = format_ident!("verieasy_const_{}", ident);
+            let signature: syn::Signature = syn::parse_quote! { fn #wrapper() -> #ty };
+            functions.push(Function::new(
+                FunctionMetadata::new(Path(vec![wrapper.to_string()]), Signature(signature), None),
+                quote! { { #expr } }.to_string(),
+                Vec::new(),
+            ));
+            wrappers.push_str(
+                &quote! {
+                    #[allow(non_snake_case)]
+                    pub fn #wrapper() -> #ty {
+                        #ident
+                    }
+                }
+                .to_string(),
+            );
+            wrappers.push('\n');
+        }
+        (functions, wrappers)
+    }
+}
+
+/// If values of the constant type `ty` are known to implement `PartialEq` and `Debug`.
+fn is_comparable_const(ty: &syn::Type, comparable_types: &[Path]) -> bool {
+    match ty {
+        syn::Type::Reference(ty) => is_comparable_const(&ty.elem, comparable_types),
+        syn::Type::Path(path) if path.qself.is_none() && !is_comparable_item(ty) => {
+            comparable_types.contains(&Path::from(path.path.clone()))
+        }
+        _ => is_comparable_item(ty),
+    }
+}
+
+/// Visitor that makes elided reference lifetimes `'static`.
+struct StaticLifetimes;
+
+impl VisitMut for StaticLifetimes {
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(syn::parse_quote!('static));
+        }
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+}
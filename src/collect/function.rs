@@ -2,10 +2,11 @@
 
 use crate::{
     collect::path::ModuleStack,
-    defs::{Path, Type},
+    defs::{FunctionRole, Path, Type},
 };
 use syn::{
-    Block, File, ImplItemFn, ItemFn, ItemImpl, ItemMod, Signature,
+    Attribute, Block, File, GenericParam, Generics, ImplItemFn, ItemFn, ItemImpl, ItemMod,
+    ItemTrait, Signature, TraitItemFn,
     visit::{self, Visit},
 };
 
@@ -19,14 +20,27 @@ struct Function {
     impl_type: Option<Type>,
     /// Function body.
     body: Block,
+    /// Attributes other than doc comments.
+    attrs: Vec<String>,
+    /// Role given by an attribute.
+    role: Option<FunctionRole>,
 }
 
-/// Visitor that collects free functions and impl methods.
+/// Visitor that collects free functions, impl methods and trait default methods.
+///
+/// Methods tagged `#[verieasy_constructor]` or `#[verieasy_getter]` keep their role, so that
+/// they are used as constructors or getters without being renamed.
+///
+/// Default methods are named `TraitName::method`, with the trait as their impl type. Only those
+/// taking `self` are collected, since associated functions of a trait cannot be called without
+/// naming an implementor.
 pub struct FunctionCollector<'ast> {
     /// Collected functions.
     functions: Vec<Function>,
     /// Currently visited impl block.
     impl_block: Option<&'ast ItemImpl>,
+    /// Currently visited trait.
+    trait_: Option<&'ast ItemTrait>,
     /// Module stack.
     module: ModuleStack,
 }
@@ -36,6 +50,7 @@ impl<'ast> FunctionCollector<'ast> {
         Self {
             functions: Vec::new(),
             impl_block: None,
+            trait_: None,
             module: ModuleStack::new(),
         }
     }
@@ -45,13 +60,16 @@ impl<'ast> FunctionCollector<'ast> {
         let mut functions = Vec::new();
         for func in self.functions {
             let body = func.body;
+            let mut metadata = crate::defs::FunctionMetadata::new(
+                func.name,
+                crate::defs::Signature(func.signature),
+                func.impl_type,
+            );
+            metadata.role = func.role;
             functions.push(crate::defs::Function::new(
-                crate::defs::FunctionMetadata::new(
-                    func.name,
-                    crate::defs::Signature(func.signature),
-                    func.impl_type,
-                ),
+                metadata,
                 quote::quote! { #body }.to_string(),
+                func.attrs,
             ));
         }
         functions
@@ -66,7 +84,7 @@ impl<'ast> Visit<'ast> for FunctionCollector<'ast> {
     }
 
     fn visit_item_fn(&mut self, i: &'ast ItemFn) {
-        if !i.sig.generics.params.is_empty() {
+        if is_generic(&i.sig.generics) {
             return;
         } // Skip generic functions
         if i.attrs.iter().any(|attr| attr.path().is_ident("ignore")) {
@@ -79,6 +97,8 @@ impl<'ast> Visit<'ast> for FunctionCollector<'ast> {
             signature: i.sig.clone(),
             impl_type: None,
             body: (*i.block).clone(),
+            attrs: non_doc_attrs(&i.attrs),
+            role: None,
         });
     }
 
@@ -89,7 +109,7 @@ impl<'ast> Visit<'ast> for FunctionCollector<'ast> {
     }
 
     fn visit_impl_item_fn(&mut self, i: &'ast ImplItemFn) {
-        if !i.sig.generics.params.is_empty() {
+        if is_generic(&i.sig.generics) {
             return;
         } // Skip generic functions
         if i.attrs.iter().any(|attr| attr.path().is_ident("ignore")) {
@@ -105,7 +125,59 @@ impl<'ast> Visit<'ast> for FunctionCollector<'ast> {
                 impl_type: Some(self_ty),
                 signature: i.sig.clone(),
                 body: i.block.clone(),
+                attrs: non_doc_attrs(&i.attrs),
+                role: FunctionRole::from_attrs(&i.attrs),
             });
         }
     }
+
+    fn visit_item_trait(&mut self, i: &'ast ItemTrait) {
+        if is_generic(&i.generics) {
+            return;
+        } // Skip generic traits
+        self.trait_ = Some(i);
+        visit::visit_item_trait(self, i);
+        self.trait_ = None;
+    }
+
+    fn visit_trait_item_fn(&mut self, i: &'ast TraitItemFn) {
+        let Some(block) = &i.default else {
+            return;
+        }; // Skip methods without a default body
+        if is_generic(&i.sig.generics) || i.sig.receiver().is_none() {
+            return;
+        } // Skip generic methods and associated functions
+        if i.attrs.iter().any(|attr| attr.path().is_ident("ignore")) {
+            return;
+        } // Skip functions marked with #[ignore]
+
+        let trait_ = self.trait_.unwrap();
+        let trait_path = self.module.concat(&trait_.ident.to_string());
+        self.functions.push(Function {
+            name: trait_path.clone().join(i.sig.ident.to_string()),
+            impl_type: Some(Type::from_path(trait_path)),
+            signature: i.sig.clone(),
+            body: block.clone(),
+            attrs: non_doc_attrs(&i.attrs),
+            role: FunctionRole::from_attrs(&i.attrs),
+        });
+    }
+}
+
+/// Whether a function has type or const parameters. Lifetime parameters and `where` clauses
+/// alone do not prevent calling it without turbofish.
+fn is_generic(generics: &Generics) -> bool {
+    generics
+        .params
+        .iter()
+        .any(|param| !matches!(param, GenericParam::Lifetime(_)))
+}
+
+/// Stringify attributes, skipping doc comments.
+fn non_doc_attrs(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| !attr.path().is_ident("doc"))
+        .map(|attr| quote::quote! { #attr }.to_string())
+        .collect()
 }
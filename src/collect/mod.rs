@@ -1,13 +1,17 @@
 //! Collect functions from two programs.
 
+mod constant;
 mod function;
+mod nondet;
 mod path;
 mod precond;
 mod symbol;
 mod types;
 
+pub use constant::ConstCollector;
 pub use function::FunctionCollector;
+pub use nondet::{DEFAULT_NONDETERMINISTIC, mutable_globals, nondeterministic_symbols};
 pub use path::PathResolver;
-pub use precond::collect_preconds;
+pub use precond::{collect_preconds, remove_defined, translate_preconds};
 pub use symbol::SymbolCollector;
-pub use types::TypeCollector;
+pub use types::{TypeCollector, comparable_types};
@@ -0,0 +1,94 @@
+//! Detect functions whose results depend on global state or the environment.
+//!
+//! Such functions cannot be compared deterministically, since two calls of the same function
+//! may already disagree. A function is nondeterministic if its body refers to a symbol of the
+//! denylist, to a `static mut`, or to a thread-local declared in the same source.
+
+use crate::defs::Path;
+use syn::{
+    ExprPath, ItemMacro, ItemStatic, StaticMutability,
+    visit::{self, Visit},
+};
+
+/// Symbols treated as nondeterministic by default. A symbol matches every path ending with its
+/// segments, so `SystemTime::now` matches `std::time::SystemTime::now`.
+pub const DEFAULT_NONDETERMINISTIC: &[&str] = &[
+    "SystemTime::now",
+    "Instant::now",
+    "rand::random",
+    "rand::thread_rng",
+    "rand::rng",
+    "env::var",
+    "env::vars",
+    "process::id",
+    "thread::current",
+];
+
+/// Collect the names of `static mut` items and thread-locals declared in a source.
+pub fn mutable_globals(syntax: &syn::File) -> Vec<String> {
+    let mut collector = GlobalCollector(Vec::new());
+    collector.visit_file(syntax);
+    collector.0
+}
+
+/// Get the symbols of `denylist` referred to by a stringified function body.
+pub fn nondeterministic_symbols(body: &str, denylist: &[Path]) -> Vec<String> {
+    let Ok(block) = syn::parse_str::<syn::Block>(body) else {
+        return Vec::new();
+    };
+    let mut finder = SymbolFinder {
+        denylist,
+        found: Vec::new(),
+    };
+    finder.visit_block(&block);
+    finder.found
+}
+
+/// Visitor that collects `static mut` items and thread-locals.
+struct GlobalCollector(Vec<String>);
+
+impl<'ast> Visit<'ast> for GlobalCollector {
+    fn visit_item_static(&mut self, i: &'ast ItemStatic) {
+        if let StaticMutability::Mut(_) = i.mutability {
+            self.0.push(i.ident.to_string());
+        }
+    }
+
+    fn visit_item_macro(&mut self, i: &'ast ItemMacro) {
+        if i.mac.path.is_ident("thread_local") {
+            // Declarations have the form `static NAME: T = init;`
+            let mut tokens = i.mac.tokens.clone().into_iter();
+            while let Some(token) = tokens.next() {
+                if let proc_macro2::TokenTree::Ident(ident) = token
+                    && ident == "static"
+                    && let Some(proc_macro2::TokenTree::Ident(name)) = tokens.next()
+                {
+                    self.0.push(name.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Visitor that finds paths matching a denylist.
+struct SymbolFinder<'a> {
+    /// Nondeterministic symbols.
+    denylist: &'a [Path],
+    /// Symbols found in the visited body.
+    found: Vec<String>,
+}
+
+impl<'ast> Visit<'ast> for SymbolFinder<'_> {
+    fn visit_expr_path(&mut self, i: &'ast ExprPath) {
+        let path = Path::from(i.path.clone());
+        for symbol in self.denylist {
+            if path.segments().ends_with(symbol.segments()) {
+                let symbol = symbol.to_string();
+                if !self.found.contains(&symbol) {
+                    self.found.push(symbol);
+                }
+            }
+        }
+        visit::visit_expr_path(self, i);
+    }
+}
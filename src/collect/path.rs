@@ -80,8 +80,7 @@ impl PathResolver {
     /// Resolve a given path to a fully qualified path.
     fn resolve_path(&self, path: &Path) -> Path {
         // Separate the first segment and the rest.
-        let first_seg = path.0.first().unwrap();
-        let rest: Vec<String> = path.0.iter().skip(1).cloned().collect();
+        let (first_seg, rest) = path.segments().split_first().unwrap();
 
         // Determine the prefix based on the first segment.
         let mut prefix = match first_seg.as_str() {
@@ -95,12 +94,8 @@ impl PathResolver {
         };
 
         // Append the rest of the segments.
-        if rest.is_empty() {
-            prefix
-        } else {
-            prefix.0.extend(rest.into_iter());
-            prefix
-        }
+        prefix.0.extend(rest.iter().cloned());
+        prefix
     }
 
     /// Enter a new module scope.
@@ -1,12 +1,32 @@
 //! Collect preconditions using `precond-translator` crate.
 
-use crate::defs::{Path, Precondition};
+use crate::{
+    defs::{ArgBound, Path, Precondition, Type},
+    log,
+};
 use anyhow::Result;
+use syn::{BinOp, Expr, ImplItem, Item, UnOp};
 
 /// Calls the Verus precondition collector, returns the generated code and precondition list.
-pub fn collect_preconds(verus_src: &str) -> Result<(String, Vec<Precondition>)> {
+///
+/// `recommends` clauses are enforced as well if `enforce_recommends` is set. Checkers defined by
+/// `verieasy_pre_<name>.rs` files of `overrides` replace the generated ones.
+pub fn collect_preconds(
+    verus_src: &str,
+    enforce_recommends: bool,
+    overrides: Option<&str>,
+) -> Result<(String, Vec<Precondition>)> {
     // Construct the precondition generator from the Verus source code.
-    let precond_gen = precond_translator::parse_file_and_create_generator(verus_src)?;
+    let mut precond_gen = precond_translator::parse_file_and_create_generator(verus_src)?;
+    if enforce_recommends {
+        precond_gen.fold_recommends();
+    }
+    if let Some(dir) = overrides {
+        precond_gen.apply_overrides(dir)?;
+    }
+    for diagnostic in precond_gen.diagnostics() {
+        log!(Brief, Warning, "Precondition {}", diagnostic);
+    }
 
     // Generate all precondition code.
     let code = precond_gen.generate_all();
@@ -15,11 +35,400 @@ pub fn collect_preconds(verus_src: &str) -> Result<(String, Vec<Precondition>)>
     // Collect function and method preconditions.
     let mut precondtions = Vec::new();
     for func in precond_gen.get_function_preconds() {
-        precondtions.push(Precondition::new(Path::from_str(&func), false));
+        precondtions.push(Precondition::new(Path::from(func.as_str()), false));
     }
     for method in precond_gen.get_method_preconds() {
-        precondtions.push(Precondition::new(Path::from_str(&method), true));
+        precondtions.push(Precondition::new(Path::from(method.as_str()), true));
+    }
+
+    // Derive argument ranges from the generated checkers
+    if let Ok(syntax) = syn::parse_file(&code) {
+        let checkers = checker_fns(&syntax);
+        for precondition in &mut precondtions {
+            let checker_name = match &precondition.impl_type {
+                Some(impl_type) => impl_type
+                    .to_path()
+                    .join(precondition.checker_name().last().unwrap().clone()),
+                None => precondition.checker_name(),
+            };
+            if let Some((_, (sig, block))) = checkers.iter().find(|(name, _)| *name == checker_name)
+            {
+                precondition.bounds = arg_bounds(sig, block);
+            }
+            for bound in &precondition.bounds {
+                let end = |end: Option<i128>| end.map_or(String::new(), |end| end.to_string());
+                log!(
+                    Verbose,
+                    Info,
+                    "Precondition of `{:?}` bounds `{}` to `{}..={}`",
+                    precondition.name,
+                    bound.arg,
+                    end(bound.min),
+                    end(bound.max)
+                );
+            }
+        }
     }
 
     Ok((code, precondtions))
 }
+
+/// Translate the preconditions and spec functions of a Verus file, write the generated code to
+/// `output` (stdout if `None`), and log the translated checkers and the dropped items.
+pub fn translate_preconds(verus_src: &str, output: Option<&str>) -> Result<()> {
+    let precond_gen = precond_translator::parse_file_and_create_generator(verus_src)?;
+    let code = prettyplease::unparse(&syn::parse2(precond_gen.generate_all())?);
+    match output {
+        Some(path) => std::fs::write(path, &code)
+            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", path, e))?,
+        None => print!("{}", code),
+    }
+
+    let functions = precond_gen.get_function_preconds();
+    let methods = precond_gen.get_method_preconds();
+    log!(
+        Brief,
+        Info,
+        "Translated preconditions of {} functions and {} methods",
+        functions.len(),
+        methods.len()
+    );
+    for name in functions.iter().chain(&methods) {
+        log!(Normal, Simple, "  {}", name);
+    }
+    let diagnostics = precond_gen.diagnostics();
+    if diagnostics.is_empty() {
+        log!(Brief, Ok, "No precondition item was dropped");
+    } else {
+        log!(Brief, Warning, "Dropped {} items:", diagnostics.len());
+        for diagnostic in diagnostics {
+            log!(Brief, Simple, "  {}", diagnostic);
+        }
+    }
+    Ok(())
+}
+
+/// Remove generated functions and methods already defined in `source`, the code they are
+/// appended to, e.g. exec versions of spec functions when preconditions are collected from the
+/// source itself.
+///
+/// Both codes are parsed, so generic and `#[ignore]`d items of the source are found as well.
+pub fn remove_defined(code: &str, source: &str) -> String {
+    let (Ok(mut syntax), Ok(source)) = (syn::parse_file(code), syn::parse_file(source)) else {
+        return code.to_owned();
+    };
+    let defined = defined_functions(&source);
+    let is_defined = |name: Path| {
+        let found = defined.contains(&name);
+        if found {
+            log!(
+                Verbose,
+                Info,
+                "`{:?}` is already defined, not appended",
+                name
+            );
+        }
+        found
+    };
+    syntax.items.retain_mut(|item| match item {
+        Item::Fn(func) => !is_defined(Path(vec![func.sig.ident.to_string()])),
+        Item::Impl(item_impl) => {
+            let Ok(self_ty) = Type::try_from(*item_impl.self_ty.clone()) else {
+                return true;
+            };
+            item_impl.items.retain(|item| match item {
+                ImplItem::Fn(method) => {
+                    !is_defined(self_ty.to_path().join(method.sig.ident.to_string()))
+                }
+                _ => true,
+            });
+            !item_impl.items.is_empty()
+        }
+        _ => true,
+    });
+    prettyplease::unparse(&syntax)
+}
+
+/// Crate-level functions and methods of crate-level impl blocks named like checkers, with their
+/// paths.
+fn checker_fns(syntax: &syn::File) -> Vec<(Path, (&syn::Signature, &syn::Block))> {
+    let mut checkers = Vec::new();
+    for item in &syntax.items {
+        match item {
+            Item::Fn(func) => {
+                let path = Path(vec![func.sig.ident.to_string()]);
+                checkers.push((path, (&func.sig, &*func.block)));
+            }
+            Item::Impl(item_impl) => {
+                let Ok(self_ty) = Type::try_from(*item_impl.self_ty.clone()) else {
+                    continue;
+                };
+                for item in &item_impl.items {
+                    if let ImplItem::Fn(method) = item {
+                        let path = self_ty.to_path().join(method.sig.ident.to_string());
+                        checkers.push((path, (&method.sig, &method.block)));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    checkers
+        .into_iter()
+        .filter(|(path, _)| path.last().unwrap().starts_with("verieasy_pre_"))
+        .collect()
+}
+
+/// Ranges of the integer arguments of a checker implied by its clauses of the form
+/// `arg < lit`, `arg <= lit`, `arg > lit` or `arg >= lit` (or mirrored). Other clauses are
+/// ignored, they are still enforced by calling the checker.
+///
+/// Generated checkers return `false` on the first failing clause (`if !(clause) { return false; }`,
+/// without `else`), override files may also return a conjunction.
+fn arg_bounds(sig: &syn::Signature, block: &syn::Block) -> Vec<ArgBound> {
+    let args = sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) => match &*pat_type.pat {
+                syn::Pat::Ident(pat_ident) => Some(pat_ident.ident.to_string()),
+                _ => None,
+            },
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let mut clauses = Vec::new();
+    for stmt in &block.stmts {
+        match stmt {
+            syn::Stmt::Expr(Expr::If(expr_if), _) => {
+                if let Expr::Unary(unary) = &*expr_if.cond
+                    && let UnOp::Not(_) = unary.op
+                    && expr_if.else_branch.is_none()
+                    && returns_false(&expr_if.then_branch)
+                {
+                    conjuncts(&unary.expr, &mut clauses);
+                }
+            }
+            syn::Stmt::Expr(expr, None) => conjuncts(expr, &mut clauses),
+            _ => (),
+        }
+    }
+
+    let mut bounds: Vec<ArgBound> = Vec::new();
+    for clause in clauses {
+        let Expr::Binary(binary) = clause else {
+            continue;
+        };
+        let (left, right) = (unparen(&binary.left), unparen(&binary.right));
+        // Normalize to `arg op lit`
+        let (arg, lit, op) = match (arg_name(left, &args), int_lit(right)) {
+            (Some(arg), Some(lit)) => (arg, lit, binary.op),
+            _ => match (int_lit(left), arg_name(right, &args)) {
+                (Some(lit), Some(arg)) => {
+                    let op = match binary.op {
+                        BinOp::Lt(_) => BinOp::Gt(Default::default()),
+                        BinOp::Le(_) => BinOp::Ge(Default::default()),
+                        BinOp::Gt(_) => BinOp::Lt(Default::default()),
+                        BinOp::Ge(_) => BinOp::Le(Default::default()),
+                        op => op,
+                    };
+                    (arg, lit, op)
+                }
+                _ => continue,
+            },
+        };
+        let (min, max) = match op {
+            BinOp::Lt(_) => (None, lit.checked_sub(1)),
+            BinOp::Le(_) => (None, Some(lit)),
+            BinOp::Gt(_) => (lit.checked_add(1), None),
+            BinOp::Ge(_) => (Some(lit), None),
+            _ => continue,
+        };
+        let index = match bounds.iter().position(|bound| bound.arg == arg) {
+            Some(index) => index,
+            None => {
+                bounds.push(ArgBound {
+                    arg,
+                    min: None,
+                    max: None,
+                });
+                bounds.len() - 1
+            }
+        };
+        let bound = &mut bounds[index];
+        bound.min = bound.min.max(min);
+        bound.max = match (bound.max, max) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+    bounds
+}
+
+/// Whether `block` is exactly `{ return false; }`.
+fn returns_false(block: &syn::Block) -> bool {
+    let [syn::Stmt::Expr(Expr::Return(ret), _)] = &block.stmts[..] else {
+        return false;
+    };
+    matches!(
+        ret.expr.as_deref(),
+        Some(Expr::Lit(syn::ExprLit { lit: syn::Lit::Bool(lit), .. })) if !lit.value
+    )
+}
+
+/// Split a conjunction into its clauses.
+fn conjuncts<'a>(expr: &'a Expr, clauses: &mut Vec<&'a Expr>) {
+    match unparen(expr) {
+        Expr::Binary(binary) if matches!(binary.op, BinOp::And(_)) => {
+            conjuncts(&binary.left, clauses);
+            conjuncts(&binary.right, clauses);
+        }
+        Expr::Lit(lit) if matches!(&lit.lit, syn::Lit::Bool(b) if b.value) => (),
+        expr => clauses.push(expr),
+    }
+}
+
+/// Strip parentheses and invisible groups.
+fn unparen(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Paren(paren) => unparen(&paren.expr),
+        Expr::Group(group) => unparen(&group.expr),
+        expr => expr,
+    }
+}
+
+/// Name of the argument among `args` that `expr` refers to.
+fn arg_name(expr: &Expr, args: &[String]) -> Option<String> {
+    let Expr::Path(path) = expr else {
+        return None;
+    };
+    let ident = path.path.get_ident()?.to_string();
+    args.contains(&ident).then_some(ident)
+}
+
+/// Value of an integer literal, possibly negated.
+fn int_lit(expr: &Expr) -> Option<i128> {
+    match unparen(expr) {
+        Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(lit),
+            ..
+        }) => lit.base10_parse().ok(),
+        Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => {
+            int_lit(&unary.expr).and_then(i128::checked_neg)
+        }
+        _ => None,
+    }
+}
+
+/// Names of the crate-level functions and of the methods of crate-level impl blocks.
+fn defined_functions(syntax: &syn::File) -> Vec<Path> {
+    let mut defined = Vec::new();
+    for item in &syntax.items {
+        match item {
+            Item::Fn(func) => defined.push(Path(vec![func.sig.ident.to_string()])),
+            Item::Impl(item_impl) => {
+                let Ok(self_ty) = Type::try_from(*item_impl.self_ty.clone()) else {
+                    continue;
+                };
+                for item in &item_impl.items {
+                    if let ImplItem::Fn(method) = item {
+                        defined.push(self_ty.to_path().join(method.sig.ident.to_string()));
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    defined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::init_test_logger;
+
+    #[test]
+    fn defined_items_are_not_appended() {
+        init_test_logger();
+        let code = "pub fn is_small(x: u64) -> bool { x < 10 }
+            pub fn verieasy_pre_f(x: u64) -> bool { is_small(x) }
+            impl S {
+                pub fn is_empty(&self) -> bool { self.0 == 0 }
+                pub fn verieasy_pre_pop(&self) -> bool { !self.is_empty() }
+            }";
+        let source = "pub struct S(u64);
+            fn is_small(x: u64) -> bool { x < 10 }
+            impl S {
+                #[ignore]
+                fn is_empty(&self) -> bool { self.0 == 0 }
+            }";
+        let code = remove_defined(code, source);
+        assert!(!code.contains("fn is_small"));
+        assert!(!code.contains("fn is_empty"));
+        assert!(code.contains("fn verieasy_pre_f"));
+        assert!(code.contains("fn verieasy_pre_pop"));
+    }
+
+    #[test]
+    fn translation_produces_exec_functions() {
+        init_test_logger();
+        let dir = std::env::temp_dir().join(format!("veri-easy-translate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let verus_src = dir.join("spec.rs");
+        let output = dir.join("pre.rs");
+        std::fs::write(
+            &verus_src,
+            "spec fn is_small(v: u64) -> bool { v < 10 }
+
+            fn push(v: u64)
+                requires
+                    is_small(v),
+            {}",
+        )
+        .unwrap();
+        let res = translate_preconds(verus_src.to_str().unwrap(), output.to_str());
+        let code = std::fs::read_to_string(&output);
+        std::fs::remove_dir_all(&dir).unwrap();
+        res.unwrap();
+        let code = code.unwrap();
+        assert!(code.contains("pub fn is_small(v: u64) -> bool"));
+        assert!(code.contains("pub fn verieasy_pre_push(v: u64) -> bool"));
+    }
+
+    #[test]
+    fn simple_clauses_bound_arguments() {
+        // Generated from `requires size < 4096, 8 <= align, flags != 0`
+        let checker: syn::ItemFn = syn::parse_quote! {
+            pub fn verieasy_pre_alloc(size: usize, align: usize, flags: u8) -> bool {
+                if !(size < 4096) { return false; }
+                if !(8 <= align) { return false; }
+                if !(flags != 0) { return false; }
+                true
+            }
+        };
+        let bound = |arg: &str, min, max| ArgBound {
+            arg: arg.to_owned(),
+            min,
+            max,
+        };
+        let bounds = arg_bounds(&checker.sig, &checker.block);
+        let expected = [
+            bound("size", None, Some(4095)),
+            bound("align", Some(8), None),
+        ];
+        assert_eq!(bounds, expected);
+    }
+
+    #[test]
+    fn only_early_false_returns_are_clauses() {
+        let checker: syn::ItemFn = syn::parse_quote! {
+            pub fn verieasy_pre_f(x: u8, y: u8) -> bool {
+                if !(x < 10) { return true; }
+                if !(y < 10) { return false; } else { return true; }
+                if !(x > 2) { log(); return false; }
+                true
+            }
+        };
+        assert!(arg_bounds(&checker.sig, &checker.block).is_empty());
+    }
+}
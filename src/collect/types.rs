@@ -3,8 +3,14 @@
 //! Only explicit instantiations (like `type FooBar = Foo<Bar>`) are collected. The alias
 //! type (`FooBar`) should not contain any generics.
 
-use crate::defs::{InstantiatedType, Path, Type};
-use syn::{ItemType, visit::Visit};
+use crate::{
+    collect::path::ModuleStack,
+    defs::{InstantiatedType, Path, Type},
+};
+use syn::{
+    Attribute, ItemEnum, ItemMod, ItemStruct, ItemType,
+    visit::{self, Visit},
+};
 
 /// Visitor that collects instantiations of generic types.
 pub struct TypeCollector {
@@ -43,3 +49,59 @@ impl<'ast> Visit<'ast> for TypeCollector {
         self.types.push(i.clone());
     }
 }
+
+/// Collect the types deriving both `PartialEq` and `Debug`.
+///
+/// Derived implementations are structural, so two values of such types are equal exactly when
+/// their `Debug` representations are (up to floating-point zeros and NaNs). This lets harnesses
+/// compare values of the same type defined in two different modules.
+pub fn comparable_types(syntax: &syn::File) -> Vec<Path> {
+    let mut collector = DeriveCollector {
+        module: ModuleStack::new(),
+        types: Vec::new(),
+    };
+    collector.visit_file(syntax);
+    collector.types
+}
+
+/// Visitor that collects types deriving `PartialEq` and `Debug`.
+struct DeriveCollector {
+    /// Module stack.
+    module: ModuleStack,
+    /// Collected types.
+    types: Vec<Path>,
+}
+
+impl DeriveCollector {
+    /// Record the type `ident` if its attributes derive `PartialEq` and `Debug`.
+    fn add(&mut self, ident: &syn::Ident, attrs: &[Attribute]) {
+        let mut derived = Vec::new();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+            let _ = attr.parse_nested_meta(|meta| {
+                if let Some(segment) = meta.path.segments.last() {
+                    derived.push(segment.ident.to_string());
+                }
+                Ok(())
+            });
+        }
+        if derived.iter().any(|d| d == "PartialEq") && derived.iter().any(|d| d == "Debug") {
+            self.types.push(self.module.concat(&ident.to_string()));
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for DeriveCollector {
+    fn visit_item_mod(&mut self, i: &'ast ItemMod) {
+        self.module.push(&i.ident.to_string());
+        visit::visit_item_mod(self, i);
+        self.module.pop();
+    }
+
+    fn visit_item_struct(&mut self, i: &'ast ItemStruct) {
+        self.add(&i.ident, &i.attrs);
+    }
+
+    fn visit_item_enum(&mut self, i: &'ast ItemEnum) {
+        self.add(&i.ident, &i.attrs);
+    }
+}
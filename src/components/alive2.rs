@@ -1,7 +1,7 @@
 //! Alive2 step: use alive-tv to check function equivalence.
 
 use anyhow::{Result, anyhow};
-use std::{io::BufRead, process::Command};
+use std::{collections::HashMap, io::BufRead};
 use syn::{
     Attribute, File, ImplItemFn, ItemFn, ItemImpl,
     visit_mut::{self, VisitMut},
@@ -11,6 +11,8 @@ use crate::{
     check::{CheckResult, Checker, Component},
     config::Alive2Config,
     defs::Path,
+    log,
+    utils::run_command_with_retries,
 };
 
 /// Alive2 step: use alive-tv to check function equivalence.
@@ -33,17 +35,14 @@ impl Alive2 {
         let tmp_path = "tmp.rs";
         std::fs::write(&tmp_path, exported).map_err(|_| anyhow!("Failed to write tmp file"))?;
 
-        Command::new("rustc")
-            .args([
-                "--emit=llvm-ir",
-                "--crate-type=lib",
-                tmp_path,
-                "-o",
-                output_path,
-            ])
-            .stderr(std::fs::File::open("/dev/null").unwrap())
-            .status()
-            .map(|_| ())
+        let args = [
+            "--emit=llvm-ir",
+            "--crate-type=lib",
+            tmp_path,
+            "-o",
+            output_path,
+        ];
+        run_command_with_retries("rustc", &args, None, None, self.config.retries)
             .map_err(|_| anyhow!("Failed to compile to llvm-ir"))?;
         std::fs::remove_file(tmp_path).map_err(|_| anyhow!("Failed to remove tmp file"))
     }
@@ -55,42 +54,62 @@ impl Alive2 {
 
     /// Run alive-tv on the two LLVM IR files and save the output.
     fn run_alive2(&self, ir1: &str, ir2: &str, output_path: &str) -> anyhow::Result<()> {
-        let output_file =
-            std::fs::File::create(output_path).map_err(|_| anyhow!("Failed to create tmp file"))?;
-        Command::new(self.config.alive2_path.clone())
-            .args([ir1, ir2])
-            .stdout(output_file)
-            .status()
-            .map_err(|_| anyhow!("Failed to run alive-tv"))?;
+        // The output file is truncated by every attempt
+        run_command_with_retries(
+            &self.config.alive2_path,
+            &[ir1, ir2],
+            Some(output_path),
+            None,
+            self.config.retries,
+        )
+        .map_err(|_| anyhow!("Failed to run alive-tv"))?;
         Ok(())
     }
 
     /// Analyze the output of alive-tv and produce a CheckResult.
     fn analyze_alive2_output(&self, output_path: &str) -> CheckResult {
-        let mut res = CheckResult {
-            status: Ok(()),
-            ok: vec![],
-            fail: vec![],
-        };
+        let mut res = CheckResult::default();
 
         let file = std::fs::File::open(output_path).unwrap();
         let reader = std::io::BufReader::new(file);
         let mut func_name: Option<String> = None;
+        // Function that does not verify, and the lines of its counterexample read so far
+        let mut counterexample: Option<(String, Vec<String>)> = None;
 
         for line in reader.lines() {
             let line = line.unwrap();
+            // The counterexample lasts until the next function or the final summary
+            if let Some((_, lines)) = &mut counterexample {
+                if !line.starts_with("define")
+                    && !line.starts_with("----")
+                    && !line.starts_with("Summary:")
+                {
+                    lines.push(line);
+                    continue;
+                }
+                push_counterexample(&mut res, counterexample.take().unwrap());
+            }
             if line.starts_with("define") {
                 if func_name.is_none() {
                     let at = line.find("@").unwrap();
                     let parenthese = line.find('(').unwrap();
-                    func_name = Some(line[at + 1..parenthese].to_string().replace("___", "::"));
+                    // Names with `::` are quoted in LLVM IR
+                    func_name = Some(line[at + 1..parenthese].trim_matches('"').to_string());
                 }
             } else if line.starts_with("Transformation seems to be correct!") {
-                res.ok.push(Path::from_str(&func_name.take().unwrap()));
+                res.ok.push(Path::from(func_name.take().unwrap().as_str()));
+            } else if line.starts_with("Transformation doesn't verify!") {
+                if let Some(name) = func_name.take() {
+                    res.fail.push(Path::from(name.as_str()));
+                    counterexample = Some((name, Vec::new()));
+                }
             } else if line.starts_with("ERROR") {
                 func_name = None;
             }
         }
+        if let Some(counterexample) = counterexample {
+            push_counterexample(&mut res, counterexample);
+        }
 
         res
     }
@@ -150,23 +169,54 @@ impl Component for Alive2 {
     }
 }
 
+/// Attach the counterexample printed by Alive2 for a function that does not verify.
+fn push_counterexample(res: &mut CheckResult, (name, lines): (String, Vec<String>)) {
+    let text = lines.join("\n").trim().to_owned();
+    if !text.is_empty() {
+        res.counterexamples.push((Path::from(name.as_str()), text));
+    }
+}
+
 /// Visitor that sets `#[export_name = "..."]` on functions and impl methods.
+///
+/// Export names are the `::`-separated paths of the functions. Unlike identifiers mangled with
+/// `___` (see `Path::to_ident`), they are not restricted to identifier characters, so the paths
+/// read back from the LLVM IR are exact even if segments contain underscores.
+///
+/// A path can be exported twice, e.g. by methods of the same name in two trait impls of a type.
+/// Duplicates get a `#N` suffix, so that rustc does not fail on multiply defined symbols.
 struct FnExporter {
     scope_stack: Vec<String>,
+    /// Number of functions exported under each path.
+    exported: HashMap<String, usize>,
 }
 
 impl FnExporter {
     fn new() -> Self {
         Self {
             scope_stack: Vec::new(),
+            exported: HashMap::new(),
         }
     }
-    fn concat_name(&self, name: &str) -> String {
-        if self.scope_stack.is_empty() {
-            name.to_string()
-        } else {
-            self.scope_stack.join("___") + "___" + name
+    fn concat_name(&mut self, name: &str) -> String {
+        let mut segments = self.scope_stack.clone();
+        segments.push(name.to_owned());
+        let name = Path(segments).to_string();
+
+        let count = self.exported.entry(name.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            return name;
         }
+        let unique = format!("{}#{}", name, count);
+        log!(
+            Normal,
+            Warning,
+            "Export name `{}` is used by several functions, exported as `{}`",
+            name,
+            unique
+        );
+        unique
     }
 }
 
@@ -189,9 +239,11 @@ impl VisitMut for FnExporter {
 
     fn visit_item_impl_mut(&mut self, node: &mut ItemImpl) {
         if node.generics.lt_token.is_none() {
-            self.scope_stack.push(type_to_string(&node.self_ty, "___"));
+            let segments = type_segments(&node.self_ty);
+            let depth = segments.len();
+            self.scope_stack.extend(segments);
             visit_mut::visit_item_impl_mut(self, node);
-            self.scope_stack.pop();
+            self.scope_stack.truncate(self.scope_stack.len() - depth);
         }
         // skip impl block with generic params
     }
@@ -212,16 +264,111 @@ fn export_functions(src: &str) -> Result<String> {
     Ok(prettyplease::unparse(&syntax))
 }
 
-/// Convert a type to a string
-fn type_to_string(ty: &syn::Type, sep: &str) -> String {
+/// Get the path segments of a type
+fn type_segments(ty: &syn::Type) -> Vec<String> {
     match ty {
         syn::Type::Path(tp) => tp
             .path
             .segments
             .iter()
             .map(|seg| seg.ident.to_string())
-            .collect::<Vec<_>>()
-            .join(sep),
-        _ => "unsupported".to_owned(),
+            .collect(),
+        _ => vec!["unsupported".to_owned()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::init_test_logger;
+
+    /// Analyze `output` as if written by alive-tv, in a temporary file named after `test`.
+    fn analyze(test: &str, output: &str) -> CheckResult {
+        let file = format!("veri-easy-alive2-{}-{}", test, std::process::id());
+        let path = std::env::temp_dir().join(file);
+        std::fs::write(&path, output).unwrap();
+        let alive2 = Alive2::new(Alive2Config::default());
+        let res = alive2.analyze_alive2_output(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        res
+    }
+
+    #[test]
+    fn nested_module_paths_round_trip() {
+        init_test_logger();
+        let src = "mod a { mod b { mod c { pub fn split__at(x: u8) -> u8 { x } } } }";
+        let exported = export_functions(src).unwrap();
+        assert!(exported.contains(r#"#[export_name = "a::b::c::split__at"]"#));
+
+        let res = analyze(
+            "nested",
+            r#"define i8 @"a::b::c::split__at"(i8 %x) {
+Transformation seems to be correct!
+"#,
+        );
+        assert_eq!(res.ok, [Path::from("a::b::c::split__at")]);
+    }
+
+    #[test]
+    fn duplicate_export_names_are_disambiguated() {
+        init_test_logger();
+        let src = "mod a { pub fn f() {} }
+            mod b { pub fn f() {} }
+            pub struct S;
+            impl From<u8> for S { fn from(_: u8) -> Self { S } }
+            impl From<u16> for S { fn from(_: u16) -> Self { S } }";
+        let exported = export_functions(src).unwrap();
+        for name in ["a::f", "b::f", "S::from", "S::from#2"] {
+            let attr = format!("#[export_name = \"{}\"]", name);
+            assert_eq!(exported.matches(&attr).count(), 1, "{}", name);
+        }
+    }
+
+    #[test]
+    fn counterexample_is_attached_to_its_function() {
+        init_test_logger();
+        let res = analyze(
+            "counterexample",
+            r#"----------------------------------------
+define i8 @"f"(i8 %x) {
+  ret i8 %x
+}
+=>
+define i8 @"f"(i8 %x) {
+  %r = add i8 %x, 1
+  ret i8 %r
+}
+Transformation doesn't verify!
+
+ERROR: Value mismatch
+
+Example:
+i8 %x = #x00 (0)
+
+Source value: #x00 (0)
+Target value: #x01 (1)
+
+----------------------------------------
+define i8 @"g"(i8 %x) {
+  ret i8 %x
+}
+=>
+define i8 @"g"(i8 %x) {
+  ret i8 %x
+}
+Transformation seems to be correct!
+
+Summary:
+  1 correct transformations
+  1 incorrect transformations
+"#,
+        );
+        assert_eq!(res.ok, [Path::from("g")]);
+        assert_eq!(res.fail, [Path::from("f")]);
+        assert_eq!(res.counterexamples.len(), 1);
+        let (name, counterexample) = &res.counterexamples[0];
+        assert_eq!(*name, Path::from("f"));
+        assert!(counterexample.starts_with("ERROR: Value mismatch"));
+        assert!(counterexample.ends_with("Target value: #x01 (1)"));
     }
 }
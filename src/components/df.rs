@@ -3,15 +3,27 @@
 use anyhow::anyhow;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
+use rand::{RngCore, SeedableRng, rngs::StdRng};
 use regex::Regex;
-use std::io::{BufRead, BufReader, Write};
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+use syn::visit::Visit;
 
 use crate::{
     check::{CheckResult, Checker, Component},
-    config::DiffFuzzConfig,
+    config::{DiffFuzzConfig, Format},
     defs::{CommonFunction, Path, Precondition},
-    generate::{FunctionCollection, HarnessBackend, HarnessGenerator},
-    utils::{create_harness_project, run_command},
+    generate::{
+        FunctionCallArgs, FunctionCollection, HarnessBackend, HarnessGenerator, MethodHarness,
+        bind_call, block_on_if_async, capture_output_helper, collect_if_iterator,
+        map_panic_payload, panic_message_helper, return_mismatch, state_mismatches,
+        struct_call_args,
+    },
+    log,
+    utils::{create_harness_project, dump_harness, run_command, run_command_with_retries},
 };
 
 /// Differential fuzzing harness generator backend.
@@ -22,6 +34,277 @@ struct DFHarnessBackend {
     catch_panic: bool,
     /// Enable log in fuzzing harness
     harness_log: bool,
+    /// Generate a replay harness that runs a single input file instead of fuzzing.
+    replay: bool,
+    /// Serialization format of fuzzer input.
+    format: Format,
+    /// Panic on mismatch instead of only logging it.
+    panic_on_mismatch: bool,
+    /// Capture and compare stdout and stderr of each call.
+    capture_output: bool,
+    /// Compare methods borrowing their receiver in call sequences.
+    stateful: bool,
+    /// Compare the messages of caught panics.
+    compare_panic_payload: bool,
+    /// Names of the methods preconditioning the receivers before a compared method call.
+    setup_methods: Vec<String>,
+}
+
+impl DFHarnessBackend {
+    /// Decode `input` into `ty`, yielding `Result<ty, _>`.
+    fn decode(&self, ty: &syn::Ident, input: TokenStream) -> TokenStream {
+        match self.format {
+            Format::Postcard => quote! { postcard::from_bytes::<#ty>(#input) },
+            Format::Bincode => quote! {
+                bincode::serde::decode_from_slice::<#ty, _>(#input, bincode::config::standard())
+                    .map(|(args, _)| args)
+            },
+            Format::Cbor => quote! { ciborium::from_reader::<#ty, _>(#input) },
+        }
+    }
+
+    /// Decode a prefix of `input` into `ty`, yielding `Result<(ty, &[u8]), _>`.
+    fn decode_prefix(&self, ty: &syn::Ident, input: TokenStream) -> TokenStream {
+        match self.format {
+            Format::Postcard => quote! { postcard::take_from_bytes::<#ty>(#input) },
+            Format::Bincode => quote! {
+                bincode::serde::decode_from_slice::<#ty, _>(#input, bincode::config::standard())
+                    .map(|(args, len)| (args, &(#input)[len..]))
+            },
+            Format::Cbor => quote! {{
+                let mut reader = #input;
+                ciborium::from_reader::<#ty, _>(&mut reader).map(|args| (args, reader))
+            }},
+        }
+    }
+
+    /// Output check code reporting an `OUTPUT MISMATCH` and running `fail` if the captured output
+    /// differs.
+    fn output_check(
+        &self,
+        fn_name: &str,
+        arg_report: TokenStream,
+        fail: TokenStream,
+    ) -> Option<TokenStream> {
+        self.capture_output.then(|| {
+            quote! {
+                if out1 != out2 {
+                    outputln!("OUTPUT MISMATCH: {}", #fn_name);
+                    #arg_report
+                    #fail
+                }
+            }
+        })
+    }
+
+    /// Harness function comparing a sequence of method calls on the receivers built by
+    /// `constructor`. After the constructor arguments, each step of the input is a byte selecting
+    /// one of `steps`, followed by the arguments of that method. The sequence ends when the input
+    /// runs out or the implementations diverge.
+    fn sequence_harness(
+        &self,
+        constructor: &CommonFunction,
+        steps: &[&CommonFunction],
+        fixed_width: bool,
+    ) -> TokenStream {
+        let constr_name = &constructor.metadata.name;
+        let seq_fn_name = sequence_fn_name(constructor);
+        let constructor_arg_struct = format_ident!("Args{}", constr_name.to_ident());
+        let constructor_args = struct_call_args(constructor, fixed_width);
+        let s1_construct = self.construct(quote! {mod1}, constr_name, &constructor_args);
+        let s2_construct = self.construct(quote! {mod2}, constr_name, &constructor_args);
+        let decode_constr_args = self.decode_prefix(&constructor_arg_struct, quote! { input });
+        let step_count = steps.len();
+        let step_arms = steps.iter().enumerate().map(|(i, method)| {
+            let step_fn_name = format_ident!("step_{}", method.metadata.name.to_ident());
+            quote! {
+                #i => #step_fn_name(&mut s1, &mut s2, remain, &mut trace),
+            }
+        });
+
+        quote! {
+            fn #seq_fn_name(input: &[u8]) -> bool {
+                // Constructor arguments
+                let (constr_arg_struct, mut input) = match #decode_constr_args {
+                    Ok((args, remain)) => (args, remain),
+                    Err(_) => return true,
+                };
+                // Calls reported on mismatch
+                let mut trace = vec![format!("constructor: {:?}", constr_arg_struct)];
+
+                // Construct s1 and s2
+                let mut s1 = #s1_construct;
+                let mut s2 = #s2_construct;
+                // Apply each step to both receivers until they diverge
+                while let Some((selector, remain)) = input.split_first() {
+                    let step = match *selector as usize % #step_count {
+                        #(#step_arms)*
+                        _ => unreachable!(),
+                    };
+                    match step {
+                        Step::Next(remain) => input = remain,
+                        Step::Stop => break,
+                        Step::Mismatch => return false,
+                    }
+                }
+                true
+            }
+        }
+    }
+
+    /// Whether `method` is one of the configured setup methods, which must take `&mut self`.
+    fn is_setup_method(&self, method: &CommonFunction) -> bool {
+        let mutably_borrows = method.metadata.signature.0.inputs.iter().any(|arg| {
+            matches!(
+                arg,
+                syn::FnArg::Receiver(rec) if rec.reference.is_some() && rec.mutability.is_some()
+            )
+        });
+        mutably_borrows
+            && method
+                .metadata
+                .name
+                .last()
+                .is_some_and(|name| self.setup_methods.iter().any(|setup| setup == name))
+    }
+
+    /// Generate the function preconditioning both receivers of the type built by `constructor`
+    /// with a sequence of `setups` calls decoded from the input: a count byte, then a (method
+    /// selector byte, arguments) pair per call. Calls violating their precondition are skipped.
+    /// The input is discarded if a call panics in both implementations, and reported as a
+    /// mismatch if it panics in one of them only. Yields the remaining input.
+    fn setup_harness(
+        &self,
+        constructor: &CommonFunction,
+        setups: &[&CommonFunction],
+        collection: &FunctionCollection,
+    ) -> TokenStream {
+        let self_ty = constructor.metadata.name.parent().unwrap();
+        let setup_fn_name = setup_fn_name(constructor);
+        if setups.is_empty() {
+            return quote! {
+                fn #setup_fn_name<'a>(
+                    s1: &mut mod1::#self_ty,
+                    s2: &mut mod2::#self_ty,
+                    input: &'a [u8],
+                    trace: &mut Vec<String>,
+                ) -> Setup<'a> {
+                    Setup::Done(input)
+                }
+            };
+        }
+
+        let setup_count = setups.len();
+        let setup_arms = setups.iter().enumerate().map(|(i, method)| {
+            let fn_name = &method.metadata.name;
+            let fn_name_string = fn_name.to_string();
+            let method_arg_struct = format_ident!("Args{}", fn_name.to_ident());
+            let method_args = struct_call_args(method, collection.fixed_width_usize);
+            let decode_method_args = self.decode_prefix(&method_arg_struct, quote! { remain });
+            let precondition = self
+                .use_preconditions
+                .then(|| collection.get_precondition(method))
+                .flatten()
+                .map(|pre| {
+                    let check_fn_name = pre.checker_name();
+                    quote! { s2.#check_fn_name(#(method_arg_struct.#method_args),*) }
+                })
+                .unwrap_or(quote! { true });
+            let [call1, call2] = [
+                (quote! {mod1}, quote! {s1}, method.asyncness.0),
+                (quote! {mod2}, quote! {s2}, method.asyncness.1),
+            ]
+            .map(|(mod_, s, is_async)| {
+                block_on_if_async(
+                    quote! { #mod_::#fn_name(&mut *#s, #(method_arg_struct.#method_args),*) },
+                    is_async,
+                )
+            });
+            // A setup call panicking in both implementations only makes the input invalid, but
+            // a panic in one of them is a divergence
+            let calls = if self.catch_panic {
+                quote! {
+                    let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        #call1;
+                    }));
+                    let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        #call2;
+                    }));
+                    match (r1.is_ok(), r2.is_ok()) {
+                        (true, true) => (),
+                        (false, false) => return Setup::Discard,
+                        _ => return Setup::Mismatch,
+                    }
+                }
+            } else {
+                quote! {
+                    #call1;
+                    #call2;
+                }
+            };
+            quote! {
+                #i => {
+                    let (method_arg_struct, remain) = match #decode_method_args {
+                        Ok((args, remain)) => (args, remain),
+                        Err(_) => return Setup::Discard,
+                    };
+                    if #precondition {
+                        trace.push(format!("setup {}: {:?}", #fn_name_string, method_arg_struct));
+                        #calls
+                    }
+                    remain
+                }
+            }
+        });
+
+        quote! {
+            fn #setup_fn_name<'a>(
+                s1: &mut mod1::#self_ty,
+                s2: &mut mod2::#self_ty,
+                input: &'a [u8],
+                trace: &mut Vec<String>,
+            ) -> Setup<'a> {
+                // Number of setup calls
+                let Some((count, mut input)) = input.split_first() else {
+                    return Setup::Discard;
+                };
+                for _ in 0..*count {
+                    let Some((selector, remain)) = input.split_first() else {
+                        return Setup::Discard;
+                    };
+                    input = match *selector as usize % #setup_count {
+                        #(#setup_arms)*
+                        _ => unreachable!(),
+                    };
+                }
+                Setup::Done(input)
+            }
+        }
+    }
+
+    /// Constructor call of `mod_`, returning `true` from the harness if it panics and panic
+    /// catch is enabled.
+    fn construct(
+        &self,
+        mod_: TokenStream,
+        constr_name: &Path,
+        constructor_args: &[TokenStream],
+    ) -> TokenStream {
+        if self.catch_panic {
+            quote! {
+                match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    #mod_::#constr_name(#(constr_arg_struct.#constructor_args),*)
+                })) {
+                    Ok(s) => s,
+                    Err(_) => return true,
+                }
+            }
+        } else {
+            quote! {
+                #mod_::#constr_name(#(constr_arg_struct.#constructor_args),*)
+            }
+        }
+    }
 }
 
 impl HarnessBackend for DFHarnessBackend {
@@ -34,7 +317,7 @@ impl HarnessBackend for DFHarnessBackend {
     fn make_harness_for_function(
         &self,
         function: &CommonFunction,
-        function_args: &[TokenStream],
+        call_args: &FunctionCallArgs,
         precondition: Option<&Precondition>,
     ) -> TokenStream {
         let fn_name = &function.metadata.name;
@@ -51,8 +334,9 @@ impl HarnessBackend for DFHarnessBackend {
             .then(|| {
                 precondition.map(|pre| {
                     let check_fn_name = pre.checker_name();
+                    let args = &call_args.precondition;
                     quote! {
-                        if !#check_fn_name(#(function_arg_struct.#function_args),*) {
+                        if !#check_fn_name(#(#args),*) {
                             return true;
                         }
                     }
@@ -60,22 +344,33 @@ impl HarnessBackend for DFHarnessBackend {
             })
             .flatten();
         // Function call with panic catch if enabled
-        let fn_call = |mod_: TokenStream| {
+        let fn_call = |index: usize, mod_: TokenStream, args: &[TokenStream], is_async: bool| {
+            let call = call_args.widen_result(index, quote! { #mod_::#fn_name(#(#args),*) });
+            let call = block_on_if_async(call, is_async);
+            let call = collect_if_iterator(call, function);
             if self.catch_panic {
+                let map_err = map_panic_payload(self.compare_panic_payload);
                 quote! {
                     std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                        #mod_::#fn_name(#(function_arg_struct.#function_args),*)
+                        #call
                     }))
-                    .map_err(|_| ())
+                    #map_err
                 }
             } else {
-                quote! {
-                    #mod_::#fn_name(#(function_arg_struct.#function_args),*)
-                }
+                call
             }
         };
-        let r1_call = fn_call(quote! {mod1});
-        let r2_call = fn_call(quote! {mod2});
+        let r1_call = bind_call(
+            1,
+            fn_call(1, quote! {mod1}, &call_args.mod1, function.asyncness.0),
+            self.capture_output,
+        );
+        let r2_call = bind_call(
+            2,
+            fn_call(2, quote! {mod2}, &call_args.mod2, function.asyncness.1),
+            self.capture_output,
+        );
+        let setup = &call_args.setup;
 
         // Error report message
         let err_report = quote! {
@@ -83,46 +378,78 @@ impl HarnessBackend for DFHarnessBackend {
             outputln!("function: {:?}", function_arg_struct);
         };
         // Return value check code
+        let mismatch = return_mismatch(function, self.catch_panic);
         let retv_check = quote! {
-            if r1 != r2 {
+            if #mismatch {
                 #err_report
                 return false;
             }
         };
+        // Mutated `&mut` argument check code
+        let mut_arg_check = call_args.mut_mismatch.as_ref().map(|mismatch| {
+            quote! {
+                if #mismatch {
+                    #err_report
+                    return false;
+                }
+            }
+        });
+        let output_check = self.output_check(
+            &fn_name_string,
+            quote! { outputln!("function: {:?}", function_arg_struct); },
+            quote! { return false; },
+        );
+        let decode_args = self.decode(&function_arg_struct, quote! { &input[..] });
 
         quote! {
             #[inline(always)]
             fn #test_fn_name(input: &[u8]) -> bool {
                 // Function arguments
-                let function_arg_struct = match postcard::from_bytes::<#function_arg_struct>(&input[..]) {
+                let function_arg_struct = match #decode_args {
                     Ok(args) => args,
                     Err(_) => return true,
                 };
                 // Precondition check
                 #precondition
                 // Do function call
-                let r1 = #r1_call;
-                let r2 = #r2_call;
+                #setup
+                #r1_call
+                #r2_call
 
                 #retv_check
+                #mut_arg_check
+                #output_check
                 true
             }
         }
     }
 
-    fn make_harness_for_method(
-        &self,
-        method: &CommonFunction,
-        constructor: &CommonFunction,
-        getter: Option<&CommonFunction>,
-        method_args: &[TokenStream],
-        constructor_args: &[TokenStream],
-        receiver_prefix: TokenStream,
-        precondition: Option<&Precondition>,
-    ) -> TokenStream {
+    fn make_harness_for_method(&self, harness: MethodHarness) -> TokenStream {
+        let MethodHarness {
+            method,
+            constructor,
+            getters,
+            compare_state,
+            ref method_args,
+            ref constructor_args,
+            receiver_prefix,
+            precondition,
+            guard,
+        } = harness;
         let fn_name = &method.metadata.name;
         let fn_name_string = fn_name.to_string();
         let constr_name = &constructor.metadata.name;
+        // In stateful mode, methods borrowing their receiver become steps of call sequences
+        let step = self.stateful && borrows_receiver(method);
+        // Leave the input or step without comparing, and report a mismatch
+        let (skip, fail) = if step {
+            (
+                quote! { return Step::Next(remain); },
+                quote! { return Step::Mismatch; },
+            )
+        } else {
+            (quote! { return true; }, quote! { return false; })
+        };
 
         // Test function name
         let test_fn_name = format_ident!("check_{}", fn_name.to_ident());
@@ -139,89 +466,161 @@ impl HarnessBackend for DFHarnessBackend {
                     let check_fn_name = pre.checker_name();
                     quote! {
                         if !s2.#check_fn_name(#(method_arg_struct.#method_args),*) {
-                            return true;
+                            #skip
                         }
                     }
                 })
             })
             .flatten();
-        // Constructor call with panic catch if enabled
-        let constr_call = |mod_: TokenStream| {
-            if self.catch_panic {
+        // Method guard check. With panic catch, a panicking guard lets the call proceed, so that
+        // the panic is compared by the call itself.
+        let guard = guard.map(|guard| {
+            let guard = if self.catch_panic {
                 quote! {
-                    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                        #mod_::#constr_name(#(constr_arg_struct.#constructor_args),*)
-                    })) {
-                        Ok(s) => s,
-                        Err(_) => return true,
-                    }
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| #guard))
+                        .unwrap_or(true)
                 }
             } else {
-                quote! {
-                    #mod_::#constr_name(#(constr_arg_struct.#constructor_args),*)
+                guard
+            };
+            quote! {
+                if !(#guard) {
+                    #skip
                 }
             }
-        };
-        let s1_construct = constr_call(quote! {mod1});
-        let s2_construct = constr_call(quote! {mod2});
-        // Method call with panic catch if enabled
-        let method_call = |mod_: TokenStream, s: TokenStream| {
-            if self.catch_panic {
-                quote! {
-                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                        #mod_::#fn_name(
-                            #receiver_prefix #s, #(method_arg_struct.#method_args),*
-                        )
-                    }))
-                    .map_err(|_| ())
-                }
+        });
+        // Method call with panic catch if enabled. Steps borrow the receivers of the sequence.
+        let method_call = |mod_: TokenStream, s: TokenStream, is_async: bool| {
+            let s = if step {
+                quote! { (*#s) }
             } else {
+                s
+            };
+            let call = block_on_if_async(
                 quote! {
                     #mod_::#fn_name(
                         #receiver_prefix #s, #(method_arg_struct.#method_args),*
                     )
+                },
+                is_async,
+            );
+            let call = collect_if_iterator(call, method);
+            if self.catch_panic {
+                let map_err = map_panic_payload(self.compare_panic_payload);
+                quote! {
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        #call
+                    }))
+                    #map_err
                 }
+            } else {
+                call
             }
         };
-        let r1_call = method_call(quote! {mod1}, quote! {s1});
-        let r2_call = method_call(quote! {mod2}, quote! {s2});
+        let r1_call = bind_call(
+            1,
+            method_call(quote! {mod1}, quote! {s1}, method.asyncness.0),
+            self.capture_output,
+        );
+        let r2_call = bind_call(
+            2,
+            method_call(quote! {mod2}, quote! {s2}, method.asyncness.1),
+            self.capture_output,
+        );
 
+        // Arguments of the mismatching call, or the whole call sequence for steps
+        let arg_report = if step {
+            quote! {
+                for call in trace.iter() {
+                    outputln!("{}", call);
+                }
+            }
+        } else if !self.setup_methods.is_empty() {
+            quote! {
+                outputln!("contructor: {:?}", constr_arg_struct);
+                for call in trace.iter() {
+                    outputln!("{}", call);
+                }
+                outputln!("method: {:?}", method_arg_struct);
+            }
+        } else {
+            quote! {
+                outputln!("contructor: {:?}", constr_arg_struct);
+                outputln!("method: {:?}", method_arg_struct);
+            }
+        };
         // Error report message
         let err_report = quote! {
             outputln!("MISMATCH: {}", #fn_name_string);
-            outputln!("contructor: {:?}", constr_arg_struct);
-            outputln!("method: {:?}", method_arg_struct);
+            #arg_report
         };
         // Return value check code
+        let mismatch = return_mismatch(method, self.catch_panic);
         let retv_check = quote! {
-            if r1 != r2 {
+            if #mismatch {
                 #err_report
-                return false;
+                #fail
             }
         };
-        // If a getter is provided, generate state check code after method call
-        let state_check = getter.map(|getter| {
-            let getter = &getter.metadata.signature.0.ident;
-            quote! {
-                if s1.#getter() != s2.#getter() {
-                    #err_report
-                    return false;
+        // If the state can be compared, check it after the method call, naming the getter that
+        // diverged
+        let state_check = state_mismatches(getters, compare_state)
+            .into_iter()
+            .map(|(state, mismatch)| {
+                quote! {
+                    if #mismatch {
+                        #err_report
+                        outputln!("state differs: {}", #state);
+                        #fail
+                    }
                 }
-            }
-        });
+            })
+            .collect::<Vec<_>>();
 
-        quote! {
-            #[inline(always)]
-            fn #test_fn_name(input: &[u8]) -> bool {
-                // Constructor arguments
-                let (constr_arg_struct, remain) = match postcard::take_from_bytes::<#constructor_arg_struct>(
-                    &input[..]
-                ) {
-                    Ok((args, remain)) => (args, remain),
-                    Err(_) => return true,
-                };
+        let output_check = self.output_check(&fn_name_string, arg_report, fail);
+
+        if step {
+            let step_fn_name = format_ident!("step_{}", fn_name.to_ident());
+            let self_ty = fn_name.parent().unwrap();
+            let decode_method_args = self.decode_prefix(&method_arg_struct, quote! { input });
+            return quote! {
+                #[inline(always)]
+                fn #step_fn_name<'a>(
+                    s1: &mut mod1::#self_ty,
+                    s2: &mut mod2::#self_ty,
+                    input: &'a [u8],
+                    trace: &mut Vec<String>,
+                ) -> Step<'a> {
+                    // Method arguments
+                    let (method_arg_struct, remain) = match #decode_method_args {
+                        Ok((args, remain)) => (args, remain),
+                        Err(_) => return Step::Stop,
+                    };
+                    // Precondition check
+                    #precondition
+                    #guard
+                    trace.push(format!("method {}: {:?}", #fn_name_string, method_arg_struct));
+                    // Do method call
+                    #r1_call
+                    #r2_call
+
+                    #retv_check
+                    #(#state_check)*
+                    #output_check
+                    Step::Next(remain)
+                }
+            };
+        }
+
+        let s1_construct = self.construct(quote! {mod1}, constr_name, constructor_args);
+        let s2_construct = self.construct(quote! {mod2}, constr_name, constructor_args);
+        let decode_constr_args = self.decode_prefix(&constructor_arg_struct, quote! { &input[..] });
+        let decode_method_args = self.decode(&method_arg_struct, quote! { &remain[..] });
+        // Without setup methods, the receivers are only constructed once all arguments decode
+        let construct = if self.setup_methods.is_empty() {
+            quote! {
                 // Method arguments
-                let method_arg_struct = match postcard::from_bytes::<#method_arg_struct>(&remain[..]) {
+                let method_arg_struct = match #decode_method_args {
                     Ok(args) => args,
                     Err(_) => return true,
                 };
@@ -229,47 +628,181 @@ impl HarnessBackend for DFHarnessBackend {
                 // Construct s1 and s2
                 let mut s1 = #s1_construct;
                 let mut s2 = #s2_construct;
+            }
+        } else {
+            let setup_fn_name = setup_fn_name(constructor);
+            quote! {
+                // Construct s1 and s2
+                let mut s1 = #s1_construct;
+                let mut s2 = #s2_construct;
+                // Precondition both receivers with the setup calls decoded from the input
+                let mut trace = Vec::new();
+                let remain = match #setup_fn_name(&mut s1, &mut s2, remain, &mut trace) {
+                    Setup::Done(remain) => remain,
+                    Setup::Discard => return true,
+                    Setup::Mismatch => {
+                        outputln!("MISMATCH: {}", #fn_name_string);
+                        outputln!("contructor: {:?}", constr_arg_struct);
+                        for call in trace.iter() {
+                            outputln!("{}", call);
+                        }
+                        outputln!("setup call panicked in one implementation");
+                        return false;
+                    }
+                };
+                // Method arguments
+                let method_arg_struct = match #decode_method_args {
+                    Ok(args) => args,
+                    Err(_) => return true,
+                };
+            }
+        };
+
+        quote! {
+            #[inline(always)]
+            fn #test_fn_name(input: &[u8]) -> bool {
+                // Constructor arguments
+                let (constr_arg_struct, remain) = match #decode_constr_args {
+                    Ok((args, remain)) => (args, remain),
+                    Err(_) => return true,
+                };
+                #construct
                 // Precondition check
                 #precondition
+                #guard
                 // Do method call
-                let r1 = #r1_call;
-                let r2 = #r2_call;
+                #r1_call
+                #r2_call
 
                 #retv_check
-                #state_check
+                #(#state_check)*
+                #output_check
                 true
             }
         }
     }
 
     fn additional_code(&self, collection: &FunctionCollection) -> TokenStream {
+        // In stateful mode, methods borrowing their receiver are checked in call sequences, one
+        // per type
+        let (steps, methods): (Vec<_>, Vec<_>) = collection
+            .methods
+            .iter()
+            .partition(|method| self.stateful && borrows_receiver(method));
+        let mut sequences: Vec<(&CommonFunction, Vec<&CommonFunction>)> = Vec::new();
+        for method in steps {
+            let constructor = collection.constructors.get(method.impl_type()).unwrap();
+            match sequences
+                .iter_mut()
+                .find(|(c, _)| c.metadata.name == constructor.metadata.name)
+            {
+                Some((_, steps)) => steps.push(method),
+                None => sequences.push((constructor, vec![method])),
+            }
+        }
+        let sequence_fns = sequences
+            .iter()
+            .map(|(constructor, steps)| {
+                self.sequence_harness(constructor, steps, collection.fixed_width_usize)
+            })
+            .collect::<Vec<_>>();
+        // Setup functions of the types whose methods are compared one call at a time
+        let setup_fns = if self.setup_methods.is_empty() {
+            Vec::new()
+        } else {
+            collection
+                .constructors
+                .iter()
+                .map(|(ty, constructor)| {
+                    let setups = collection
+                        .methods
+                        .iter()
+                        .filter(|method| method.impl_type() == ty && self.is_setup_method(method))
+                        .collect::<Vec<_>>();
+                    self.setup_harness(constructor, &setups, collection)
+                })
+                .collect::<Vec<_>>()
+        };
+        let setup_enum = (!self.setup_methods.is_empty()).then(|| {
+            quote! {
+                // Outcome of the setup calls of a method harness
+                enum Setup<'a> {
+                    // Compare the method on the remaining input
+                    Done(&'a [u8]),
+                    // The input cannot be decoded, or a setup call panicked in both
+                    // implementations
+                    Discard,
+                    // A setup call panicked in one implementation only
+                    Mismatch,
+                }
+            }
+        });
+        let step_enum = (!sequences.is_empty()).then(|| {
+            quote! {
+                // Outcome of a step of a call sequence
+                enum Step<'a> {
+                    // Go on with the remaining input
+                    Next(&'a [u8]),
+                    // The input is exhausted or cannot be decoded
+                    Stop,
+                    // The implementations diverged
+                    Mismatch,
+                }
+            }
+        });
+
         // Generate dispatch function as additional code
         let test_fns = collection
             .functions
             .iter()
+            .chain(methods)
             .map(|func| format!("check_{}", func.metadata.name.to_ident()))
             .chain(
-                collection
-                    .methods
+                sequences
                     .iter()
-                    .map(|method| format!("check_{}", method.metadata.name.to_ident())),
+                    .map(|(constructor, _)| sequence_fn_name(constructor).to_string()),
             )
             .collect::<Vec<_>>();
 
         let fn_count = test_fns.len();
+        let check_fns = test_fns.clone();
+        // Number of leading input bytes used to select the function
+        let mut dispatch_bytes = 1usize;
+        while dispatch_bytes < 7 && 1usize << (8 * dispatch_bytes) < fn_count {
+            dispatch_bytes += 1;
+        }
         let match_arms = test_fns.iter().enumerate().map(|(i, name)| {
             let fn_name = format_ident!("{}", name);
-            let i = i as u8;
             quote! {
-                #i => #fn_name(&input[1..]),
+                #i => #fn_name(input),
             }
         });
         quote! {
+            #step_enum
+            #setup_enum
+            #(#sequence_fns)*
+            #(#setup_fns)*
+
+            const CHECK_FNS: [&str; #fn_count] = [#(#check_fns),*];
+            const DISPATCH_BYTES: usize = #dispatch_bytes;
+            // Select a function from the leading bytes of `input`. Values beyond the largest
+            // multiple of the function count are rejected, so every function is equally likely.
+            fn dispatch(input: &[u8]) -> Option<usize> {
+                if input.len() < DISPATCH_BYTES {
+                    return None;
+                }
+                let value = input[..DISPATCH_BYTES]
+                    .iter()
+                    .fold(0u64, |acc, b| (acc << 8) | *b as u64);
+                let range = 1u64 << (8 * DISPATCH_BYTES);
+                let count = CHECK_FNS.len() as u64;
+                (value < range - range % count).then(|| (value % count) as usize)
+            }
             fn run_harness(input: &[u8]) -> bool {
-                if input.len() == 0 {
+                let Some(fn_id) = dispatch(input) else {
                     return true;
-                }
-                let fn_id = input[0] % #fn_count as u8;
+                };
+                let input = &input[DISPATCH_BYTES..];
                 match fn_id {
                     #(#match_arms)*
                     _ => true,
@@ -316,6 +849,52 @@ impl HarnessBackend for DFHarnessBackend {
             }
         });
 
+        let capture_output = self.capture_output.then(capture_output_helper);
+        let panic_message =
+            (self.catch_panic && self.compare_panic_payload).then(panic_message_helper);
+
+        let main_fn = if self.replay {
+            quote! {
+                fn main() {
+                    #init_log
+                    let path = std::env::args().nth(1).expect("Usage: harness <input_file>");
+                    let data = std::fs::read(&path).expect("Failed to read input file");
+                    let Some(fn_id) = dispatch(&data) else {
+                        println!("Input selects no function, nothing to replay");
+                        return;
+                    };
+                    let check_fn = CHECK_FNS[fn_id];
+                    if run_harness(&data) {
+                        println!("REPLAY OK: {}", check_fn);
+                    } else {
+                        println!("REPLAY MISMATCH: {}", check_fn);
+                        std::process::exit(1);
+                    }
+                }
+            }
+        } else if self.panic_on_mismatch {
+            quote! {
+                fn main() {
+                    #init_log
+                    afl::fuzz_nohook!(|data: &[u8]| {
+                        if !run_harness(data) {
+                            panic!("Harness reported failure for input: {:?}", data);
+                        }
+                    });
+                }
+            }
+        } else {
+            quote! {
+                fn main() {
+                    #init_log
+                    afl::fuzz_nohook!(|data: &[u8]| {
+                        // Mismatches are recorded in the harness log, keep fuzzing
+                        run_harness(data);
+                    });
+                }
+            }
+        };
+
         quote! {
             #![allow(unused)]
             #![allow(non_snake_case)]
@@ -326,16 +905,11 @@ impl HarnessBackend for DFHarnessBackend {
 
             // Harness logging utils
             #log_utils
-            fn main() {
-                #init_log
-                afl::fuzz_nohook!(|data: &[u8]| {
-                    if !run_harness(data) {
-                        panic!("Harness reported failure for input: {:?}", data);
-                    }
-                });
-            }
+            #main_fn
 
             #(#args_structs)*
+            #capture_output
+            #panic_message
             #(#functions)*
             #(#methods)*
             #additional
@@ -343,6 +917,29 @@ impl HarnessBackend for DFHarnessBackend {
     }
 }
 
+/// If `method` takes its receiver by reference, so that it can be called repeatedly on it.
+fn borrows_receiver(method: &CommonFunction) -> bool {
+    method
+        .metadata
+        .signature
+        .0
+        .inputs
+        .iter()
+        .any(|arg| matches!(arg, syn::FnArg::Receiver(rec) if rec.reference.is_some()))
+}
+
+/// Name of the harness function running call sequences on the type built by `constructor`.
+fn sequence_fn_name(constructor: &CommonFunction) -> syn::Ident {
+    let self_ty = constructor.metadata.name.parent().unwrap();
+    format_ident!("check_seq_{}", self_ty.to_ident())
+}
+
+/// Name of the harness function preconditioning receivers of the type built by `constructor`.
+fn setup_fn_name(constructor: &CommonFunction) -> syn::Ident {
+    let self_ty = constructor.metadata.name.parent().unwrap();
+    format_ident!("setup_{}", self_ty.to_ident())
+}
+
 /// Differential fuzzing harness generator.
 type DFHarnessGenerator = HarnessGenerator<DFHarnessBackend>;
 
@@ -382,7 +979,17 @@ impl DifferentialFuzzing {
             DFHarnessBackend {
                 use_preconditions: self.config.use_preconditions,
                 catch_panic: self.config.catch_panic,
-                harness_log: self.config.harness_log,
+                // Replay and non-panicking harnesses report mismatches through the harness log
+                harness_log: self.config.harness_log
+                    || self.config.replay_input.is_some()
+                    || !self.config.panic_on_mismatch,
+                replay: self.config.replay_input.is_some(),
+                format: self.config.serialization,
+                panic_on_mismatch: self.config.panic_on_mismatch,
+                capture_output: self.config.capture_output,
+                stateful: self.config.stateful,
+                compare_panic_payload: self.config.compare_panic_payload,
+                setup_methods: self.config.setup_methods.clone(),
             },
         );
         generator.generate_harness()
@@ -394,7 +1001,8 @@ impl DifferentialFuzzing {
         checker: &Checker,
         harness: TokenStream,
     ) -> anyhow::Result<()> {
-        let toml = r#"
+        let toml = format!(
+            r#"
 [package]
 name = "harness"
 version = "0.1.0"
@@ -402,33 +1010,63 @@ edition = "2024"
 
 [dependencies]
 serde = "*"
-postcard = "*"
+{}
 afl = "*"
-"#;
+{}{}"#,
+            format_dependency(self.config.serialization),
+            if self.config.capture_output {
+                "gag = \"1\"\n"
+            } else {
+                ""
+            },
+            if checker.under_checking_funcs.iter().any(|f| f.is_async()) {
+                "futures = \"0.3\"\n"
+            } else {
+                ""
+            }
+        );
         create_harness_project(
             &self.config.harness_path,
             &checker.src1.content,
             &checker.src2.content,
             &harness.to_string(),
-            toml,
+            &toml,
             false,
         )
     }
 
     /// Prepare initial inputs for the fuzzer.
+    ///
+    /// Inputs are copied from the saved corpus if it is not empty, otherwise generated randomly.
     fn prepare_initial_inputs(&self) -> anyhow::Result<()> {
         let inputs_dir = format!("{}/in", &self.config.harness_path);
         std::fs::create_dir_all(&inputs_dir)
             .map_err(|_| anyhow!("Failed to create inputs directory"))?;
 
+        if let Some(corpus) = &self.config.corpus_path {
+            let copied = copy_dir_files(corpus, &inputs_dir).unwrap_or(0);
+            if copied > 0 {
+                log!(
+                    Normal,
+                    Info,
+                    "Seeded fuzzer with {} inputs from `{}`",
+                    copied,
+                    corpus
+                );
+                return Ok(());
+            }
+        }
+
+        let mut rng = match self.config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
         for i in 0..self.config.initial_inputs {
             let mut file = std::fs::File::create(format!("{}/input{}", inputs_dir, i))
                 .map_err(|_| anyhow!("Failed to create initial input file"))?;
             // Generate random input data
-            let mut buf = Vec::with_capacity(self.config.input_len);
-            for _ in 0..(self.config.input_len) {
-                buf.push(rand::random::<u8>());
-            }
+            let mut buf = vec![0; self.config.input_len];
+            rng.fill_bytes(&mut buf);
             file.write_all(&buf)
                 .map_err(|_| anyhow!("Failed to write initial input file"))?;
         }
@@ -447,33 +1085,96 @@ afl = "*"
         Ok(())
     }
 
+    /// Write an AFL dictionary of boundary values of the argument integer types, and return
+    /// whether a dictionary was written.
+    fn write_dictionary(&self, checker: &Checker) -> anyhow::Result<bool> {
+        if !self.config.dictionary {
+            return Ok(false);
+        }
+        if self.config.serialization != Format::Postcard {
+            log!(
+                Verbose,
+                Info,
+                "Fuzzer dictionary is only generated for postcard inputs"
+            );
+            return Ok(false);
+        }
+
+        let types = checker
+            .under_checking_funcs
+            .iter()
+            .chain(&checker.constructors)
+            .flat_map(|func| func.metadata.signature.0.inputs.iter())
+            .filter_map(|arg| match arg {
+                syn::FnArg::Typed(pat_type) => match &*pat_type.ty {
+                    syn::Type::Path(path) => path.path.get_ident().map(|id| id.to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>();
+        let mut constants = ConstantCollector(BTreeSet::new());
+        for src in [&checker.src1, &checker.src2] {
+            if let Ok(syntax) = syn::parse_file(&src.content) {
+                constants.visit_file(&syntax);
+            }
+        }
+        let entries = dictionary_entries(&types, &constants.0);
+        if entries.is_empty() {
+            return Ok(false);
+        }
+
+        let mut dict = String::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let bytes = entry
+                .iter()
+                .map(|b| format!("\\x{:02x}", b))
+                .collect::<String>();
+            dict.push_str(&format!("entry{}=\"{}\"\n", i, bytes));
+        }
+        std::fs::write(
+            format!("{}/{}", self.config.harness_path, DICTIONARY_FILE),
+            dict,
+        )
+        .map_err(|e| anyhow!("Failed to write fuzzer dictionary: {}", e))?;
+        log!(
+            Verbose,
+            Info,
+            "Fuzzer dictionary of {} entries written",
+            entries.len()
+        );
+        Ok(true)
+    }
+
     /// Run the fuzzer on the harness project.
-    fn run_fuzzer(&self) -> anyhow::Result<()> {
-        let build_status = run_command(
+    fn run_fuzzer(&self, dictionary: bool) -> anyhow::Result<()> {
+        let build_status = run_command_with_retries(
             "cargo",
             &["afl", "build", "--release"],
             None,
             Some(&self.config.harness_path),
+            self.config.retries,
         )?;
         if build_status.code() == Some(101) {
             return Err(anyhow!("Command failed due to compilation error"));
         }
 
-        let fuzz_status = run_command(
+        let executions = self.config.executions.to_string();
+        let mut args = vec!["afl", "fuzz", "-i", "in", "-o", "out", "-E", &executions];
+        if dictionary {
+            args.extend(["-x", DICTIONARY_FILE]);
+        }
+        let seed = self.config.seed.map(|seed| seed.to_string());
+        if let Some(seed) = &seed {
+            args.extend(["-s", seed]);
+        }
+        args.push("target/release/harness");
+        let fuzz_status = run_command_with_retries(
             "cargo",
-            &[
-                "afl",
-                "fuzz",
-                "-i",
-                "in",
-                "-o",
-                "out",
-                "-E",
-                self.config.executions.to_string().as_str(),
-                "target/release/harness",
-            ],
+            &args,
             None,
             Some(&self.config.harness_path),
+            self.config.retries,
         )?;
         if fuzz_status.code() != Some(0) {
             return Err(anyhow!("Fuzzing process failed with status: {}", fuzz_status));
@@ -487,24 +1188,97 @@ afl = "*"
         Ok(())
     }
 
+    /// Minimize the fuzzer queue with `cargo afl cmin` and save it as the corpus.
+    fn minimize_corpus(&self, corpus: &str) -> anyhow::Result<()> {
+        let cmin_dir = format!("{}/cmin", self.config.harness_path);
+        if std::path::Path::new(&cmin_dir).exists() {
+            std::fs::remove_dir_all(&cmin_dir)
+                .map_err(|e| anyhow!("Failed to clean cmin directory: {}", e))?;
+        }
+        let args = cmin_args("out/default/queue", "cmin", "target/release/harness");
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let status = run_command("cargo", &args, None, Some(&self.config.harness_path))?;
+        if !status.success() {
+            return Err(anyhow!(
+                "Corpus minimization failed with status: {}",
+                status
+            ));
+        }
+
+        // Replace the saved corpus with the minimized one
+        if std::path::Path::new(corpus).exists() {
+            std::fs::remove_dir_all(corpus)
+                .map_err(|e| anyhow!("Failed to remove old corpus: {}", e))?;
+        }
+        std::fs::create_dir_all(corpus)
+            .map_err(|e| anyhow!("Failed to create corpus directory: {}", e))?;
+        let count = copy_dir_files(&cmin_dir, corpus)
+            .map_err(|e| anyhow!("Failed to save minimized corpus: {}", e))?;
+        log!(
+            Normal,
+            Info,
+            "Minimized corpus of {} inputs saved to `{}`",
+            count,
+            corpus
+        );
+        Ok(())
+    }
+
+    /// Build the replay harness and run it on the given input file.
+    fn run_replay(&self, input: &str) -> anyhow::Result<()> {
+        let input = std::fs::canonicalize(input)
+            .map_err(|e| anyhow!("Failed to locate replay input `{}`: {}", input, e))?;
+        let build_status = run_command(
+            "cargo",
+            &["build", "--release"],
+            None,
+            Some(&self.config.harness_path),
+        )?;
+        if build_status.code() == Some(101) {
+            return Err(anyhow!("Command failed due to compilation error"));
+        }
+        // A mismatch makes the harness exit with 1, which is reported through the output log.
+        run_command(
+            "target/release/harness",
+            &[&input.to_string_lossy()],
+            None,
+            Some(&self.config.harness_path),
+        )?;
+        std::fs::copy(
+            format!("{}/harness_output.log", self.config.harness_path),
+            &self.config.output_path,
+        )
+        .map_err(|e| anyhow!("Failed to copy harness output log: {}", e))?;
+
+        Ok(())
+    }
+
     /// Analyze the fuzzer output and return the functions that are not checked.
     fn analyze_fuzzer_output(&self, functions: &[Path]) -> CheckResult {
         let mut res = CheckResult {
-            status: Ok(()),
             ok: functions.to_vec(),
-            fail: vec![],
+            ..Default::default()
         };
 
         let re = Regex::new(r"MISMATCH:\s*(\S+)").unwrap();
+        let re_output = Regex::new(r"^OUTPUT MISMATCH:\s*(\S+)").unwrap();
         let file = std::fs::File::open(&self.config.output_path).unwrap();
         let reader = BufReader::new(file);
 
         for line in reader.lines() {
-            if let Some(caps) = re.captures(&line.unwrap()) {
-                let func_name = caps[1].to_string();
-                if let Some(i) = res.ok.iter().position(|f| f.to_string() == func_name) {
+            let line = line.unwrap();
+            if let Some(caps) = re.captures(&line) {
+                let func = Path::from(&caps[1]);
+                if let Some(i) = res.ok.iter().position(|f| *f == func) {
                     res.ok.swap_remove(i);
-                    res.fail.push(Path::from_str(&func_name));
+                    res.fail.push(func);
+                }
+            }
+            // Report the mismatch category of captured output differences
+            if let Some(caps) = re_output.captures(&line) {
+                let func = Path::from(&caps[1]);
+                if !res.counterexamples.iter().any(|(f, _)| *f == func) {
+                    res.counterexamples.push((func, line.clone()));
                 }
             }
         }
@@ -512,6 +1286,26 @@ afl = "*"
         res
     }
 
+    /// Copy the newest input that crashed the harness next to the output file, so that the
+    /// counterexample survives harness cleanup.
+    fn save_crash_input(&self) -> anyhow::Result<()> {
+        let crashes_dir = format!("{}/out/default/crashes", self.config.harness_path);
+        let Some(crash) = newest_crash_file(&crashes_dir) else {
+            log!(Verbose, Info, "No crash input found in `{}`", crashes_dir);
+            return Ok(());
+        };
+        let dest = format!("{}.crash", self.config.output_path);
+        std::fs::copy(&crash, &dest).map_err(|e| anyhow!("Failed to copy crash input: {}", e))?;
+        log!(
+            Brief,
+            Info,
+            "Crash input `{}` saved to `{}`",
+            crash.display(),
+            dest
+        );
+        Ok(())
+    }
+
     /// Remove the harness project.
     fn remove_harness_project(&self) -> anyhow::Result<()> {
         std::fs::remove_dir_all(&self.config.harness_path)
@@ -546,35 +1340,478 @@ impl Component for DifferentialFuzzing {
                 return CheckResult::failed(e);
             }
         }
+        if let Some(dir) = &checker.options().dump_harness
+            && let Err(e) = dump_harness(dir, "difffuzz", &self.config.harness_path)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
         // Note: if using existing harness, the checked functions may be different from
         // generated harness, but we still use the functions from checker for analysis.
         let functions = self.checked_functions(checker);
 
-        let res = self.prepare_initial_inputs();
-        if let Err(e) = res {
-            return CheckResult::failed(e);
+        let mut dictionary = false;
+        if self.config.replay_input.is_none() {
+            let res = self.prepare_initial_inputs();
+            if let Err(e) = res {
+                return CheckResult::failed(e);
+            }
+            match self.write_dictionary(checker) {
+                Ok(written) => dictionary = written,
+                Err(e) => log!(Brief, Warning, "{}", e),
+            }
         }
         let res = self.execute_pre_fuzz_cmd();
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
-        let res = self.run_fuzzer();
+        let res = match &self.config.replay_input {
+            Some(input) => self.run_replay(input),
+            None => self.run_fuzzer(dictionary),
+        };
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
+        if self.config.replay_input.is_none()
+            && let Some(corpus) = &self.config.corpus_path
+            && let Err(e) = self.minimize_corpus(corpus)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
         let check_res = self.analyze_fuzzer_output(&functions);
+        if self.config.replay_input.is_none()
+            && !check_res.fail.is_empty()
+            && let Err(e) = self.save_crash_input()
+        {
+            // The mismatches are reported in the output anyway
+            log!(Brief, Warning, "{}", e);
+        }
 
-        if !self.config.keep_harness {
-            if let Err(e) = self.remove_harness_project() {
-                return CheckResult::failed(e);
-            }
+        // Keep everything for inspection if any function failed
+        let keep_all = self.config.keep_on_failure && !check_res.fail.is_empty();
+        if !self.config.keep_harness
+            && !keep_all
+            && let Err(e) = self.remove_harness_project()
+        {
+            return CheckResult::failed(e);
         }
-        if !self.config.keep_output {
-            if let Err(e) = self.remove_output_file() {
-                return CheckResult::failed(anyhow!("Failed to remove output file: {}", e));
-            }
+        if !self.config.keep_output
+            && !keep_all
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(anyhow!("Failed to remove output file: {}", e));
         }
 
         check_res
     }
 }
+
+/// Find the most recently modified crash input in an AFL crashes directory. AFL also writes a
+/// `README.txt` there, which is skipped.
+fn newest_crash_file(dir: &str) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name() != "README.txt")
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Arguments of `cargo` for minimizing the `queue` directory into `output` with `afl-cmin`.
+fn cmin_args(queue: &str, output: &str, harness: &str) -> Vec<String> {
+    ["afl", "cmin", "-i", queue, "-o", output, "--", harness]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect()
+}
+
+/// Copy all regular files from `src` into `dest`, returning the number of copied files.
+fn copy_dir_files(src: &str, dest: &str) -> std::io::Result<usize> {
+    let mut count = 0;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            std::fs::copy(entry.path(), PathBuf::from(dest).join(entry.file_name()))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Dependency line of the serialization crate in the harness `Cargo.toml`.
+fn format_dependency(format: Format) -> &'static str {
+    match format {
+        Format::Postcard => r#"postcard = "*""#,
+        Format::Bincode => r#"bincode = { version = "2", features = ["serde"] }"#,
+        Format::Cbor => r#"ciborium = "*""#,
+    }
+}
+
+/// File name of the AFL dictionary in the harness project.
+const DICTIONARY_FILE: &str = "dict.txt";
+
+/// Postcard encodings of boundary values of the integer types named `types`, and of the integer
+/// `constants` (and their predecessors) that fit in them.
+///
+/// Boundary values are 0, 1, MIN, MAX and their neighbours, and every power of two (including
+/// negated ones for signed types).
+fn dictionary_entries(types: &BTreeSet<String>, constants: &BTreeSet<u128>) -> Vec<Vec<u8>> {
+    let mut entries = BTreeSet::new();
+    for ty in types {
+        let (bits, signed) = match ty.as_str() {
+            "u8" => (8, false),
+            "u16" => (16, false),
+            "u32" => (32, false),
+            "u64" | "usize" => (64, false),
+            "u128" => (128, false),
+            "i8" => (8, true),
+            "i16" => (16, true),
+            "i32" => (32, true),
+            "i64" | "isize" => (64, true),
+            "i128" => (128, true),
+            _ => continue,
+        };
+        let (min, max) = if signed {
+            (-1i128 << (bits - 1), (1u128 << (bits - 1)) - 1)
+        } else {
+            (0, u128::MAX >> (128 - bits))
+        };
+
+        let mut values = vec![0i128, 1];
+        let max_values = [max, max - 1]
+            .into_iter()
+            .chain((0..bits - signed as u32).map(|k| 1u128 << k))
+            .chain(
+                constants
+                    .iter()
+                    .flat_map(|&c| [c, c.saturating_sub(1)])
+                    .filter(|&c| c <= max),
+            );
+        for value in max_values {
+            // `u128` values above `i128::MAX` are only encoded as unsigned
+            if let Ok(value) = i128::try_from(value) {
+                values.push(value);
+                if signed {
+                    values.push(-value);
+                }
+            } else {
+                entries.insert(varint(value));
+            }
+        }
+        if signed {
+            values.extend([min, min + 1]);
+        }
+
+        for value in values {
+            let entry = match (bits, signed) {
+                (8, _) => vec![value as u8],
+                (_, false) => varint(value as u128),
+                // Zigzag encoding of signed integers
+                (_, true) => varint(((value << 1) ^ (value >> 127)) as u128),
+            };
+            entries.insert(entry);
+        }
+    }
+    entries.into_iter().collect()
+}
+
+/// Postcard (LEB128) varint encoding of an unsigned integer.
+fn varint(mut value: u128) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Visitor that collects integer literal values of `const` items, such as an allocator `CAP`.
+struct ConstantCollector(BTreeSet<u128>);
+
+impl ConstantCollector {
+    fn insert(&mut self, expr: &syn::Expr) {
+        if let syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(lit),
+            ..
+        }) = expr
+            && let Ok(value) = lit.base10_parse::<u128>()
+        {
+            self.0.insert(value);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ConstantCollector {
+    fn visit_item_const(&mut self, i: &'ast syn::ItemConst) {
+        self.insert(&i.expr);
+    }
+
+    fn visit_impl_item_const(&mut self, i: &'ast syn::ImplItemConst) {
+        self.insert(&i.expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check::test_checker, log::init_test_logger};
+
+    #[test]
+    fn setup_panic_in_one_implementation_is_a_mismatch() {
+        init_test_logger();
+        let src = "pub struct Pool(u8);
+            impl Pool {
+                pub fn verieasy_new(n: u8) -> Self { Pool(n) }
+                pub fn remove(&mut self) { self.0 -= 1; }
+                pub fn len(&self) -> u8 { self.0 }
+            }";
+        let checker = test_checker(src, src, vec![]);
+        let df = DifferentialFuzzing::new(DiffFuzzConfig {
+            setup_methods: vec!["remove".to_owned()],
+            ..Default::default()
+        });
+        let harness = df.generate_harness(&checker).to_string();
+
+        // Both setup calls are caught, then compared
+        assert!(harness.contains("(false , false) => return Setup :: Discard"));
+        assert!(harness.contains("_ => return Setup :: Mismatch"));
+        assert!(harness.contains("Setup :: Mismatch => {"));
+        assert!(harness.contains("setup call panicked in one implementation"));
+        assert!(!harness.contains(". ok () ?"));
+    }
+
+    #[test]
+    fn replay_dispatches_on_leading_byte() {
+        init_test_logger();
+        let src = "pub fn f(x: u8) -> u8 { x }
+            pub fn g(x: u8) -> u8 { x + 1 }";
+        let checker = test_checker(src, src, vec![]);
+        let df = DifferentialFuzzing::new(DiffFuzzConfig {
+            replay_input: Some("crash".to_owned()),
+            ..Default::default()
+        });
+        let harness = df.generate_harness(&checker).to_string();
+
+        // The reported function and the executed one are selected by the same id
+        assert!(harness.contains("CHECK_FNS : [& str ; 2usize] = [\"check_f\" , \"check_g\"]"));
+        assert!(harness.contains("0usize => check_f (input)"));
+        assert!(harness.contains("1usize => check_g (input)"));
+        assert!(harness.contains("let check_fn = CHECK_FNS [fn_id]"));
+        assert!(!harness.contains("afl :: fuzz_nohook"));
+    }
+
+    #[test]
+    fn cbor_inputs_are_decoded_with_ciborium() {
+        init_test_logger();
+        let src = "pub fn f(x: u8) -> u8 { x }";
+        let checker = test_checker(src, src, vec![]);
+        let df = DifferentialFuzzing::new(DiffFuzzConfig {
+            serialization: Format::Cbor,
+            ..Default::default()
+        });
+        let harness = df.generate_harness(&checker).to_string();
+
+        assert!(harness.contains("# [derive (Debug , serde :: Deserialize)] pub struct Argsf"));
+        assert!(harness.contains("ciborium :: from_reader :: < Argsf , _ > (& input [..])"));
+        assert!(!harness.contains("postcard"));
+        assert_eq!(format_dependency(Format::Cbor), r#"ciborium = "*""#);
+    }
+
+    #[test]
+    fn dispatch_is_uniform_over_seven_functions() {
+        init_test_logger();
+        let src = (0..7)
+            .map(|i| format!("pub fn f{}(x: u8) -> u8 {{ x }}", i))
+            .collect::<String>();
+        let checker = test_checker(&src, &src, vec![]);
+        let df = DifferentialFuzzing::new(DiffFuzzConfig::default());
+        let harness = df.generate_harness(&checker).to_string();
+        assert!(harness.contains("const CHECK_FNS : [& str ; 7usize]"));
+        assert!(harness.contains("const DISPATCH_BYTES : usize = 1usize ;"));
+        assert!(harness.contains("(value < range - range % count)"));
+
+        // Same selection as the generated `dispatch`, over every leading byte
+        let (range, count) = (256u64, 7u64);
+        let mut hits = [0; 7];
+        for value in (0..range).filter(|value| *value < range - range % count) {
+            hits[(value % count) as usize] += 1;
+        }
+        assert_eq!(hits, [36; 7]);
+    }
+
+    #[test]
+    fn panics_in_both_implementations_are_equivalent() {
+        init_test_logger();
+        let src = "pub fn f(x: u8) -> u8 { 100 / x }";
+        let checker = test_checker(src, src, vec![]);
+        let df = DifferentialFuzzing::new(DiffFuzzConfig::default());
+        let harness = df.generate_harness(&checker).to_string();
+
+        // Any panic is mapped to `Err(())`, so panics on both sides compare equal
+        let call = |mod_: &str| {
+            format!(
+                "std :: panic :: catch_unwind (std :: panic :: AssertUnwindSafe (|| {{ {} :: f \
+                 (function_arg_struct . x . clone ()) }})) . map_err (| _ | ())",
+                mod_
+            )
+        };
+        assert!(harness.contains(&format!("let r1 = {} ;", call("mod1"))));
+        assert!(harness.contains(&format!("let r2 = {} ;", call("mod2"))));
+        assert!(harness.contains("if r1 != r2 {"));
+    }
+
+    #[test]
+    fn mismatch_is_only_logged_without_panicking() {
+        init_test_logger();
+        let src = "pub fn f(x: u8) -> u8 { x }";
+        let checker = test_checker(src, src, vec![]);
+        let df = DifferentialFuzzing::new(DiffFuzzConfig {
+            harness_log: false,
+            panic_on_mismatch: false,
+            ..Default::default()
+        });
+        let harness = df.generate_harness(&checker).to_string();
+
+        // The log is enabled anyway, and the fuzzer keeps going after a mismatch
+        assert!(harness.contains("init_harness_output () ;"));
+        assert!(harness.contains("outputln ! (\"MISMATCH: {}\" , \"f\")"));
+        let fuzz_loop = "afl :: fuzz_nohook ! (| data : & [u8] | { run_harness (data) ; })";
+        assert!(harness.contains(fuzz_loop));
+        assert!(!harness.contains("Harness reported failure"));
+    }
+
+    #[test]
+    fn cmin_runs_the_harness_on_the_queue() {
+        let args = cmin_args("out/default/queue", "cmin", "target/release/harness");
+        assert_eq!(
+            args,
+            [
+                "afl",
+                "cmin",
+                "-i",
+                "out/default/queue",
+                "-o",
+                "cmin",
+                "--",
+                "target/release/harness"
+            ]
+        );
+    }
+
+    #[test]
+    fn newest_crash_file_skips_readme() {
+        let dir = std::env::temp_dir().join(format!("veri-easy-crashes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let now = std::time::SystemTime::now();
+        for (name, age) in [("id:000000", 20), ("id:000001", 10), ("README.txt", 0)] {
+            let file = std::fs::File::create(dir.join(name)).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age))
+                .unwrap();
+        }
+        let newest = newest_crash_file(&dir.to_string_lossy());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(newest, Some(dir.join("id:000001")));
+        assert_eq!(newest_crash_file(&dir.to_string_lossy()), None);
+    }
+
+    #[test]
+    fn usize_dictionary_has_boundary_values() {
+        let types = BTreeSet::from(["usize".to_owned()]);
+        let entries = dictionary_entries(&types, &BTreeSet::from([4096]));
+        let mut max = vec![0xff; 9];
+        max.push(0x01);
+        let mut expected = vec![vec![0], vec![1], vec![0x80, 0x20], vec![0xff, 0x1f], max];
+        // 2^63, the highest power of two
+        let mut top = vec![0x80; 9];
+        top.push(0x01);
+        expected.push(top);
+        for entry in &expected {
+            assert!(entries.contains(entry), "missing {:02x?}", entry);
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_same_initial_inputs() {
+        let inputs = |run: &str, seed: u64| {
+            let dir = format!("veri-easy-seed-{}-{}", run, std::process::id());
+            let dir = std::env::temp_dir().join(dir);
+            let df = DifferentialFuzzing::new(DiffFuzzConfig {
+                harness_path: dir.to_str().unwrap().to_owned(),
+                seed: Some(seed),
+                ..Default::default()
+            });
+            df.prepare_initial_inputs().unwrap();
+            let inputs = (0..df.config.initial_inputs)
+                .map(|i| std::fs::read(dir.join(format!("in/input{}", i))).unwrap())
+                .collect::<Vec<_>>();
+            std::fs::remove_dir_all(&dir).unwrap();
+            inputs
+        };
+        let first = inputs("first", 7);
+        assert!(!first.is_empty());
+        assert_eq!(first, inputs("second", 7));
+        assert_ne!(first, inputs("other", 8));
+    }
+
+    #[test]
+    fn stateful_harness_checks_getters_after_each_step() {
+        init_test_logger();
+        let src = "pub struct Counter(u8);
+            impl Counter {
+                pub fn verieasy_new(a: u8) -> Self { Counter(a) }
+                #[verieasy_getter]
+                pub fn value(&self) -> u8 { self.0 }
+                pub fn bump(&mut self, by: u8) { self.0 = self.0.wrapping_add(by); }
+            }";
+        let checker = test_checker(src, src, vec![]);
+        let df = DifferentialFuzzing::new(DiffFuzzConfig {
+            stateful: true,
+            ..Default::default()
+        });
+        let harness = df.generate_harness(&checker).to_string();
+
+        // Steps are applied in a loop, and the getter is compared after each one
+        assert!(harness.contains("while let Some ((selector , remain)) = input . split_first ()"));
+        let step = "0usize => step_Counter___bump (& mut s1 , & mut s2 , remain , & mut trace)";
+        assert!(harness.contains(step));
+        assert!(harness.contains("if s1 . value () != s2 . value ()"));
+        assert!(harness.contains("[\"check_seq_Counter\"]"));
+    }
+
+    #[test]
+    fn setup_calls_are_decoded_from_the_input() {
+        init_test_logger();
+        let src = "pub struct Pool(u8);
+            impl Pool {
+                pub fn verieasy_new(n: u8) -> Self { Pool(n) }
+                pub fn remove(&mut self, k: u8) { self.0 -= k; }
+                pub fn insert(&mut self, k: u8) { self.0 += k; }
+                pub fn len(&self) -> u8 { self.0 }
+            }";
+        let checker = test_checker(src, src, vec![]);
+        let df = DifferentialFuzzing::new(DiffFuzzConfig {
+            setup_methods: vec!["remove".to_owned()],
+            ..Default::default()
+        });
+        let harness = df.generate_harness(&checker).to_string();
+
+        // Every method harness preconditions its receivers before the compared call
+        let setup = "match setup_Pool (& mut s1 , & mut s2 , remain , & mut trace)";
+        assert_eq!(harness.matches(setup).count(), 3);
+        // A count byte, then a selector byte and the arguments of each `remove` call
+        let count = "let Some ((count , mut input)) = input . split_first () else";
+        assert!(harness.contains(count));
+        assert!(harness.contains("for _ in 0 .. * count"));
+        assert!(harness.contains("input = match * selector as usize % 1usize"));
+        let decode = "postcard :: take_from_bytes :: < ArgsPool___remove > (remain)";
+        assert!(harness.contains(decode));
+    }
+}
@@ -0,0 +1,478 @@
+//! Check function equivalence by enumerating every input of small domains.
+
+use anyhow::anyhow;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use regex::Regex;
+use std::io::{BufRead, BufReader};
+
+use crate::{
+    check::{CheckResult, Checker, Component},
+    config::ExhaustiveConfig,
+    defs::{CommonFunction, Path, Precondition},
+    generate::{
+        FunctionCallArgs, HarnessBackend, HarnessGenerator, MethodHarness, arg_ident,
+        collect_if_iterator, return_mismatch,
+    },
+    log,
+    utils::{create_harness_project, dump_harness, run_command_with_retries},
+};
+
+/// Values of an argument type with a small domain, together with the domain size.
+fn domain(ty: &syn::Type) -> Option<(TokenStream, u64)> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    if path.qself.is_some() {
+        return None;
+    }
+    let ident = path.path.get_ident()?;
+    match ident.to_string().as_str() {
+        "bool" => Some((quote! { [false, true] }, 2)),
+        "u8" | "i8" => Some((quote! { #ident::MIN..=#ident::MAX }, 1 << 8)),
+        "u16" | "i16" => Some((quote! { #ident::MIN..=#ident::MAX }, 1 << 16)),
+        _ => None,
+    }
+}
+
+/// Exhaustive harness generator backend.
+struct ExhaustiveHarnessBackend {
+    /// Maximum number of inputs of a function.
+    max_cases: u64,
+    /// Use preconditions.
+    use_preconditions: bool,
+}
+
+impl HarnessBackend for ExhaustiveHarnessBackend {
+    fn arg_struct_attrs(&self) -> TokenStream {
+        quote! {
+            #[derive(Debug)]
+        }
+    }
+
+    fn make_harness_for_function(
+        &self,
+        function: &CommonFunction,
+        call_args: &FunctionCallArgs,
+        precondition: Option<&Precondition>,
+    ) -> TokenStream {
+        let fn_name = &function.metadata.name;
+        let fn_name_string = fn_name.to_string();
+        // Async functions are left to the testing components, and so are functions over
+        // different integer widths, whose domains are those of the narrower types
+        if function.is_async() || function.signature2.is_some() {
+            return quote! {};
+        }
+
+        // Every argument must have a small domain, and so must their product
+        let mut fields = Vec::new();
+        let mut domains = Vec::new();
+        let mut cases: Option<u64> = Some(1);
+        for (index, arg) in function.metadata.signature.0.inputs.iter().enumerate() {
+            let syn::FnArg::Typed(pat_type) = arg else {
+                return quote! {};
+            };
+            let Some((values, size)) = domain(&pat_type.ty) else {
+                return quote! {};
+            };
+            fields.push(arg_ident(index, pat_type));
+            domains.push(values);
+            cases = cases.and_then(|cases| cases.checked_mul(size));
+        }
+        match cases {
+            Some(cases) if cases <= self.max_cases => {
+                log!(
+                    Verbose,
+                    Info,
+                    "Enumerate {} inputs of `{}`",
+                    cases,
+                    fn_name_string
+                );
+            }
+            _ => {
+                log!(
+                    Verbose,
+                    Info,
+                    "Input domain of `{}` is too large to enumerate",
+                    fn_name_string
+                );
+                return quote! {};
+            }
+        }
+
+        // Function argument struct name
+        let function_arg_struct = format_ident!("Args{}", fn_name.to_ident());
+
+        // If a precondition is provided, skip inputs that violate it
+        let precondition = self
+            .use_preconditions
+            .then(|| {
+                precondition.map(|pre| {
+                    let check_fn_name = pre.checker_name();
+                    let args = &call_args.precondition;
+                    quote! {
+                        if !#check_fn_name(#(#args),*) {
+                            continue;
+                        }
+                    }
+                })
+            })
+            .flatten();
+        let mismatch = return_mismatch(function, true);
+        let (mod1_args, mod2_args) = (&call_args.mod1, &call_args.mod2);
+        let mod1_call = collect_if_iterator(quote! { mod1::#fn_name(#(#mod1_args),*) }, function);
+        let mod2_call = collect_if_iterator(quote! { mod2::#fn_name(#(#mod2_args),*) }, function);
+
+        // Innermost loop body, checking one input
+        let mut body = quote! {
+            let function_arg_struct = #function_arg_struct { #(#fields),* };
+            #precondition
+
+            let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                #mod1_call
+            }))
+            .map_err(|_| ());
+            let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                #mod2_call
+            }))
+            .map_err(|_| ());
+
+            if #mismatch {
+                println!("MISMATCH {}: {:?}", #fn_name_string, function_arg_struct);
+                break 'check;
+            }
+        };
+        for (field, values) in fields.iter().zip(domains).rev() {
+            body = quote! {
+                for #field in #values {
+                    #body
+                }
+            };
+        }
+
+        quote! {
+            'check: {
+                #body
+                println!("OK {}", #fn_name_string);
+            }
+        }
+    }
+
+    fn make_harness_for_method(&self, _harness: MethodHarness) -> TokenStream {
+        // The receiver state is not enumerable
+        quote! {}
+    }
+
+    fn finalize(
+        &self,
+        imports: Vec<TokenStream>,
+        args_structs: Vec<TokenStream>,
+        functions: Vec<TokenStream>,
+        _methods: Vec<TokenStream>,
+        _additional: TokenStream,
+    ) -> TokenStream {
+        quote! {
+            #![allow(unused)]
+            #![allow(non_snake_case)]
+            #![allow(non_camel_case_types)]
+            mod mod1;
+            mod mod2;
+
+            #(#imports)*
+            #(#args_structs)*
+
+            fn main() {
+                // Panics are compared as results, do not report each of them
+                std::panic::set_hook(Box::new(|_| {}));
+                #(#functions)*
+            }
+        }
+    }
+}
+
+/// Exhaustive harness generator.
+type ExhaustiveHarnessGenerator = HarnessGenerator<ExhaustiveHarnessBackend>;
+
+/// Exhaustive step: compare functions on every input if their input domain is small enough.
+///
+/// Functions whose arguments are all `bool`, `u8`, `i8`, `u16` or `i16` are called on every
+/// combination of argument values, which proves equivalence rather than sampling it. A mismatching
+/// input refutes the function, so it fails definitely. Other functions and methods are left
+/// undetermined.
+pub struct Exhaustive {
+    config: ExhaustiveConfig,
+}
+
+impl Exhaustive {
+    /// Create a new Exhaustive component with the given configuration.
+    pub fn new(config: ExhaustiveConfig) -> Self {
+        Self { config }
+    }
+
+    /// Generate the exhaustive harness.
+    fn generate_harness(&self, checker: &Checker) -> TokenStream {
+        let generator = ExhaustiveHarnessGenerator::new(
+            checker,
+            ExhaustiveHarnessBackend {
+                max_cases: self.config.max_cases,
+                use_preconditions: self.config.use_preconditions,
+            },
+        );
+        generator.generate_harness()
+    }
+
+    /// Create a cargo project for the exhaustive harness.
+    fn create_harness_project(
+        &self,
+        checker: &Checker,
+        harness: TokenStream,
+    ) -> anyhow::Result<()> {
+        // Keep overflow checks, so that overflows panic like in the other harnesses
+        let toml = r#"
+[package]
+name = "harness"
+version = "0.1.0"
+edition = "2024"
+
+[profile.release]
+overflow-checks = true
+"#;
+        create_harness_project(
+            &self.config.harness_path,
+            &checker.src1.content,
+            &checker.src2.content,
+            &harness.to_string(),
+            toml,
+            false,
+        )
+    }
+
+    /// Run the harness and save the output.
+    fn run_harness(&self) -> anyhow::Result<()> {
+        let status = run_command_with_retries(
+            "cargo",
+            &["run", "--release"],
+            Some(&self.config.output_path),
+            Some(&self.config.harness_path),
+            self.config.retries,
+        )?;
+        if status.code() == Some(101) {
+            return Err(anyhow!("Command failed due to compilation error"));
+        }
+        Ok(())
+    }
+
+    /// Analyze the harness output.
+    fn analyze_output(&self) -> CheckResult {
+        let mut res = CheckResult::default();
+
+        let re_ok = Regex::new(r"^OK (\S+)$").unwrap();
+        let re_fail = Regex::new(r"^MISMATCH (\S+): (.*)$").unwrap();
+
+        let file = std::fs::File::open(&self.config.output_path).unwrap();
+        let reader = BufReader::new(file);
+        for line in reader.lines() {
+            let line = line.unwrap();
+            if let Some(caps) = re_ok.captures(&line) {
+                res.ok.push(Path::from(&caps[1]));
+            }
+            if let Some(caps) = re_fail.captures(&line) {
+                let func = Path::from(&caps[1]);
+                // A mismatching input is a concrete counterexample, not an undetermined result
+                res.fail.push(func.clone());
+                res.refuted.push(func.clone());
+                res.counterexamples.push((func, caps[2].to_string()));
+            }
+        }
+
+        res
+    }
+
+    /// Remove the harness project.
+    fn remove_harness_project(&self) -> anyhow::Result<()> {
+        std::fs::remove_dir_all(&self.config.harness_path)
+            .map_err(|_| anyhow!("Failed to remove harness file"))
+    }
+
+    /// Remove the output file.
+    fn remove_output_file(&self) -> anyhow::Result<()> {
+        std::fs::remove_file(&self.config.output_path)
+            .map_err(|_| anyhow!("Failed to remove output file"))
+    }
+}
+
+impl Component for Exhaustive {
+    fn name(&self) -> &str {
+        "Exhaustive"
+    }
+
+    fn is_formal(&self) -> bool {
+        true
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Compares functions on every input of small domains.")
+    }
+
+    fn cache_key(&self) -> String {
+        format!("preconditions={}", self.config.use_preconditions)
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        if self.config.gen_harness {
+            let harness = self.generate_harness(checker);
+            let res = self.create_harness_project(checker, harness);
+            if let Err(e) = res {
+                return CheckResult::failed(e);
+            }
+        }
+        if let Some(dir) = &checker.options().dump_harness
+            && let Err(e) = dump_harness(dir, "exhaustive", &self.config.harness_path)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
+
+        let res = self.run_harness();
+        if let Err(e) = res {
+            return CheckResult::failed(e);
+        }
+        let check_res = self.analyze_output();
+
+        // Keep everything for inspection if any function failed
+        let keep_all = self.config.keep_on_failure && !check_res.fail.is_empty();
+        if !self.config.keep_harness
+            && !keep_all
+            && let Err(e) = self.remove_harness_project()
+        {
+            return CheckResult::failed(e);
+        }
+        if !self.config.keep_output
+            && !keep_all
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckerOptions, Source, check::test_checker, log::init_test_logger};
+
+    #[test]
+    fn u8_functions_are_checked_exhaustively() {
+        init_test_logger();
+        let src1 = "pub fn half(x: u8) -> u8 { x / 2 }
+            pub fn low(x: u8) -> u8 { x & 0x7f }";
+        let src2 = "pub fn half(x: u8) -> u8 { x >> 1 }
+            pub fn low(x: u8) -> u8 { x % 127 }";
+        let dir = std::env::temp_dir().join(format!("veri-easy-exhaustive-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = ExhaustiveConfig {
+            harness_path: dir.join("harness").to_string_lossy().into_owned(),
+            output_path: dir.join("exhaustive.tmp").to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let mut checker = test_checker(src1, src2, vec![Box::new(Exhaustive::new(config))]);
+        checker.run_all();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let names = |funcs: &[CommonFunction]| -> Vec<String> {
+            funcs.iter().map(|f| f.metadata.name.to_string()).collect()
+        };
+        assert_eq!(names(&checker.verified_funcs), ["half"]);
+        // `low` differs on 127, which is a counterexample rather than an undetermined result
+        assert_eq!(names(&checker.failed_funcs), ["low"]);
+        assert!(checker.under_checking_funcs.is_empty());
+    }
+
+    #[test]
+    fn keyword_parameter_harness_compiles() {
+        init_test_logger();
+        let src = "pub fn kind(r#type: u8) -> u8 { r#type / 2 }";
+        let dir = std::env::temp_dir().join(format!("veri-easy-keyword-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = ExhaustiveConfig {
+            harness_path: dir.join("harness").to_string_lossy().into_owned(),
+            output_path: dir.join("exhaustive.tmp").to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let checker = |components| test_checker(src, src, components);
+        let exhaustive = Exhaustive::new(config.clone());
+        let harness = exhaustive.generate_harness(&checker(vec![])).to_string();
+        assert!(harness.contains("pub arg_type : u8"));
+
+        // The harness builds and runs
+        let mut checker = checker(vec![Box::new(exhaustive)]);
+        checker.run_all();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(checker.verified_funcs.len(), 1);
+    }
+
+    #[test]
+    fn pattern_parameter_harness_compiles() {
+        init_test_logger();
+        let src = "pub fn f(_: u8, _: bool) -> u8 { 0 }
+            pub fn g(_: u8, (a, b): (u8, u8)) -> u8 { a.wrapping_add(b) }";
+        let dir = std::env::temp_dir().join(format!("veri-easy-pattern-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = ExhaustiveConfig {
+            harness_path: dir.join("harness").to_string_lossy().into_owned(),
+            output_path: dir.join("exhaustive.tmp").to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let checker = |components| test_checker(src, src, components);
+        let exhaustive = Exhaustive::new(config.clone());
+        let harness = exhaustive.generate_harness(&checker(vec![])).to_string();
+        assert!(harness.contains("pub struct Argsf { pub arg0 : u8 , pub arg1 : bool }"));
+        assert!(harness.contains("pub struct Argsg { pub arg0 : u8 , pub arg1 : (u8 , u8) }"));
+
+        // The harness, including the struct of `g`, builds and runs. Tuples have no enumerated
+        // domain, so `g` is left to other components.
+        let mut checker = checker(vec![Box::new(exhaustive)]);
+        checker.run_all();
+        let _ = std::fs::remove_dir_all(&dir);
+        let verified = checker.verified_funcs.iter();
+        let verified = verified.map(|f| f.metadata.name.to_string());
+        assert_eq!(verified.collect::<Vec<_>>(), ["f"]);
+    }
+
+    #[test]
+    fn reference_const_harness_compiles() {
+        init_test_logger();
+        let src = "pub struct Point(u8);
+            pub const NAME: &str = \"veri\";
+            pub const BYTES: &[u8] = b\"ok\";
+            pub const ORIGIN: Point = Point(0);
+            pub fn half(x: u8) -> u8 { x / 2 }";
+        let dir = std::env::temp_dir().join(format!("veri-easy-const-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = ExhaustiveConfig {
+            harness_path: dir.join("harness").to_string_lossy().into_owned(),
+            output_path: dir.join("exhaustive.tmp").to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let source = Source::from_string("a.rs", src).unwrap();
+        assert!(source.content.contains("-> & 'static str"));
+        // `Point` does not derive `PartialEq` and `Debug`
+        assert!(!source.content.contains("verieasy_const_ORIGIN"));
+
+        // The sources, including the wrappers, build with the harness
+        let mut checker = Checker::new(
+            source,
+            Source::from_string("b.rs", src).unwrap(),
+            vec![Box::new(Exhaustive::new(config))],
+            Vec::new(),
+            CheckerOptions::default(),
+        );
+        checker.run_all();
+        let _ = std::fs::remove_dir_all(&dir);
+        let verified = checker.verified_funcs.iter();
+        let verified = verified.map(|f| f.metadata.name.to_string());
+        let expected = ["half", "verieasy_const_NAME", "verieasy_const_BYTES"];
+        assert_eq!(verified.collect::<Vec<_>>(), expected);
+    }
+}
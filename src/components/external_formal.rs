@@ -0,0 +1,160 @@
+//! Use a user-configured external verifier to check function equivalence.
+
+use anyhow::anyhow;
+use regex::Regex;
+use std::io::BufRead;
+
+use crate::{
+    check::{CheckResult, Checker, Component},
+    config::ExternalFormalConfig,
+    defs::Path,
+    utils::run_command_with_retries,
+};
+
+/// External formal step: shell out to an external verifier and parse its per-function verdicts.
+pub struct ExternalFormal {
+    config: ExternalFormalConfig,
+}
+
+impl ExternalFormal {
+    /// Create a new external formal component with the given configuration.
+    pub fn new(config: ExternalFormalConfig) -> Self {
+        Self { config }
+    }
+
+    /// Run the external verifier on the two sources and save the output.
+    fn run_verifier(&self, checker: &Checker) -> anyhow::Result<()> {
+        let args: Vec<String> = self
+            .config
+            .args
+            .iter()
+            .map(|arg| {
+                arg.replace("{file1}", &checker.src1.path)
+                    .replace("{file2}", &checker.src2.path)
+            })
+            .collect();
+        let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+        run_command_with_retries(
+            &self.config.verifier_path,
+            &args,
+            Some(&self.config.output_path),
+            None,
+            self.config.retries,
+        )?;
+        Ok(())
+    }
+
+    /// Analyze the verifier output with the configured success and failure regexes.
+    fn analyze_output(&self) -> CheckResult {
+        let success_re = match Regex::new(&self.config.success_regex) {
+            Ok(re) => re,
+            Err(e) => return CheckResult::failed(anyhow!("Invalid success regex: {}", e)),
+        };
+        let fail_re = match Regex::new(&self.config.fail_regex) {
+            Ok(re) => re,
+            Err(e) => return CheckResult::failed(anyhow!("Invalid fail regex: {}", e)),
+        };
+        let file = match std::fs::File::open(&self.config.output_path) {
+            Ok(file) => file,
+            Err(_) => return CheckResult::failed(anyhow!("Failed to open verifier output")),
+        };
+        let lines = std::io::BufReader::new(file).lines().map_while(Result::ok);
+        parse_verifier_output(lines, &success_re, &fail_re)
+    }
+
+    /// Remove the output file.
+    fn remove_output_file(&self) -> anyhow::Result<()> {
+        std::fs::remove_file(&self.config.output_path)
+            .map_err(|_| anyhow!("Failed to remove output file"))
+    }
+}
+
+impl Component for ExternalFormal {
+    fn name(&self) -> &str {
+        "External"
+    }
+
+    fn is_formal(&self) -> bool {
+        true
+    }
+
+    fn note(&self) -> Option<&str> {
+        Some("Use an external verifier to check function consistency")
+    }
+
+    fn cache_key(&self) -> String {
+        format!("{} {:?}", self.config.verifier_path, self.config.args)
+    }
+
+    fn run(&self, checker: &Checker) -> CheckResult {
+        if let Err(e) = self.run_verifier(checker) {
+            return CheckResult::failed(e);
+        }
+        let check_res = self.analyze_output();
+        if !self.config.keep_output
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
+        }
+
+        check_res
+    }
+}
+
+/// Classify functions from verifier output lines. The first capture group of each regex is the
+/// function name, either `::`-separated or mangled with `___`; lines matched without it are
+/// skipped.
+fn parse_verifier_output(
+    lines: impl Iterator<Item = String>,
+    success_re: &Regex,
+    fail_re: &Regex,
+) -> CheckResult {
+    let mut res = CheckResult::default();
+    let name = |caps: regex::Captures| {
+        let name = caps.get(1)?.as_str().replace("___", "::");
+        Some(Path::from(name.as_str()))
+    };
+    for line in lines {
+        if let Some(caps) = success_re.captures(&line) {
+            res.ok.extend(name(caps));
+        } else if let Some(caps) = fail_re.captures(&line) {
+            res.fail.extend(name(caps));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifier_output_is_classified() {
+        let success_re = Regex::new(r"^verified (\S+)$").unwrap();
+        let fail_re = Regex::new(r"^error in (\S+):").unwrap();
+        let output = [
+            "checking 3 functions",
+            "verified add",
+            "error in Stack::pop: postcondition not satisfied",
+            "verified Stack___push",
+            "done",
+        ];
+        let res =
+            parse_verifier_output(output.into_iter().map(str::to_owned), &success_re, &fail_re);
+
+        assert_eq!(res.ok, [Path::from("add"), Path::from("Stack::push")]);
+        assert_eq!(res.fail, [Path::from("Stack::pop")]);
+    }
+
+    #[test]
+    fn lines_without_function_names_are_skipped() {
+        let success_re = Regex::new(r"^verified").unwrap();
+        let fail_re = Regex::new(r"^error in (\S+):").unwrap();
+        let output = ["verified add", "error in sub: postcondition not satisfied"];
+        let res =
+            parse_verifier_output(output.into_iter().map(str::to_owned), &success_re, &fail_re);
+
+        assert!(res.ok.is_empty());
+        assert_eq!(res.fail, [Path::from("sub")]);
+    }
+}
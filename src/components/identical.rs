@@ -1,4 +1,7 @@
-use crate::check::{CheckResult, Checker, Component};
+use crate::{
+    check::{CheckResult, Checker, Component},
+    log,
+};
 
 /// Identical step: if bodies are identical -> ok; if same name but different body -> undetermined.
 pub struct Identical;
@@ -17,19 +20,131 @@ impl Component for Identical {
     }
 
     fn run(&self, checker: &Checker) -> CheckResult {
-        let mut res = CheckResult {
-            status: Ok(()),
-            ok: vec![],
-            fail: vec![],
-        };
+        let mut res = CheckResult::default();
 
         // only consider functions present in both srcs (unchecked sets already contain intersection)
         for func in &checker.under_checking_funcs {
-            if func.body1 == func.body2 {
+            // The same body over different integer widths may overflow differently
+            if func.signature2.is_some() {
+                continue;
+            }
+            let body1 = normalize_body(&func.body1);
+            let body2 = normalize_body(&func.body2);
+            if body1 == body2 {
                 res.ok.push(func.metadata.name.clone());
+            } else {
+                log!(Verbose, Info, "Bodies of `{:?}` differ:", func.metadata.name);
+                for line in body_diff(&body1, &body2) {
+                    log!(Verbose, Simple, "{}", line);
+                }
             }
         }
 
         res
     }
 }
+
+/// Normalize a stringified function body by reparsing it with `syn` and re-emitting it with
+/// `prettyplease`, so that formatting noise does not affect comparison. Falls back to
+/// whitespace-normalized text if the body cannot be parsed.
+fn normalize_body(body: &str) -> String {
+    match syn::parse_str::<syn::File>(&format!("fn f() {}", body)) {
+        Ok(file) => prettyplease::unparse(&file),
+        Err(_) => body.split_whitespace().collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// Line-based diff of two normalized function bodies using the longest common subsequence.
+///
+/// Each output line is prefixed with `- ` (only in body1), `+ ` (only in body2) or `  ` (both).
+fn body_diff(body1: &str, body2: &str) -> Vec<String> {
+    let a: Vec<&str> = body1.lines().collect();
+    let b: Vec<&str> = body2.lines().collect();
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            diff.push(format!("  {}", a[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            diff.push(format!("- {}", a[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", b[j]));
+            j += 1;
+        }
+    }
+    diff.extend(a[i..].iter().map(|line| format!("- {}", line)));
+    diff.extend(b[j..].iter().map(|line| format!("+ {}", line)));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check::test_checker, collect::FunctionCollector, log::init_test_logger};
+
+    #[test]
+    fn diff_contains_changed_line() {
+        let body1 = normalize_body("{ let y = x + 1; y * 2 }");
+        let body2 = normalize_body("{ let y = x + 2; y * 2 }");
+        let diff = body_diff(&body1, &body2);
+
+        assert!(diff.contains(&"-     let y = x + 1;".to_owned()));
+        assert!(diff.contains(&"+     let y = x + 2;".to_owned()));
+        assert!(diff.contains(&"      y * 2".to_owned()));
+        let changed = diff.iter().filter(|line| !line.starts_with("  "));
+        assert_eq!(changed.count(), 2);
+    }
+
+    #[test]
+    fn formatting_does_not_affect_bodies() {
+        let compact = "{ let y = x+1; if y>2 { y } else { 0 } }";
+        let spread = "{
+            let y =
+                x + 1;
+            if y > 2 {
+                y
+            } else { 0 }
+        }";
+        assert_eq!(normalize_body(compact), normalize_body(spread));
+        let other = "{ let y = x + 1; y }";
+        assert_ne!(normalize_body(compact), normalize_body(other));
+    }
+
+    #[test]
+    fn const_fn_is_checked() {
+        init_test_logger();
+        let src = "pub const fn size(n: usize) -> usize { n * 8 }
+            pub struct S;
+            impl S {
+                pub const fn verieasy_new() -> Self { S }
+                pub const fn bits(&self) -> u32 { 8 }
+            }";
+        let syntax = syn::parse_file(src).unwrap();
+        let collected = FunctionCollector::new().collect(&syntax);
+        let names = collected.iter().map(|f| f.metadata.name.to_string());
+        let names = names.collect::<Vec<_>>();
+        assert_eq!(names, ["size", "S::verieasy_new", "S::bits"]);
+
+        let mut checker = test_checker(src, src, vec![Box::new(Identical)]);
+        checker.run_all();
+        let verified = checker.verified_funcs.iter();
+        let verified = verified.map(|f| f.metadata.name.to_string());
+        assert_eq!(verified.collect::<Vec<_>>(), ["size", "S::bits"]);
+    }
+}
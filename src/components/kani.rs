@@ -4,14 +4,26 @@ use anyhow::anyhow;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use regex::Regex;
-use std::{io::BufRead, str::FromStr};
+use std::{
+    io::{BufRead, Write},
+    str::FromStr,
+    sync::{
+        Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
 
 use crate::{
     check::{CheckResult, Checker, Component},
     config::KaniConfig,
     defs::{CommonFunction, Path, Precondition},
-    generate::{HarnessBackend, HarnessGenerator},
-    utils::{create_harness_project, run_command},
+    generate::{
+        FunctionCallArgs, HarnessBackend, HarnessGenerator, MethodHarness, collect_if_iterator,
+        return_mismatch,
+    },
+    log,
+    utils::{create_harness_project, dump_harness, run_command_with_retries},
 };
 
 /// Kani harness generator backend.
@@ -20,6 +32,25 @@ struct KaniHarnessBackend {
     use_preconditions: bool,
     /// Loop unwind limit.
     loop_unwind: Option<u32>,
+    /// Wrap calls in `catch_unwind` so that panics are compared like in DF and PBT harnesses.
+    catch_panic: bool,
+    /// `#[kani::stub(..)]` attributes added to every proof harness.
+    stub_attrs: TokenStream,
+    /// Stub definitions included in the harness.
+    stub_defs: TokenStream,
+}
+
+impl KaniHarnessBackend {
+    /// Wrap a call expression in `catch_unwind` if panic catching is enabled.
+    fn wrap_call(&self, call: TokenStream) -> TokenStream {
+        if self.catch_panic {
+            quote! {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| #call)).map_err(|_| ())
+            }
+        } else {
+            call
+        }
+    }
 }
 
 impl HarnessBackend for KaniHarnessBackend {
@@ -32,9 +63,13 @@ impl HarnessBackend for KaniHarnessBackend {
     fn make_harness_for_function(
         &self,
         function: &CommonFunction,
-        function_args: &[TokenStream],
+        call_args: &FunctionCallArgs,
         precondition: Option<&Precondition>,
     ) -> TokenStream {
+        // Async functions are left to the testing components
+        if function.is_async() {
+            return quote! {};
+        }
         let fn_name = &function.metadata.name;
 
         // Test function name
@@ -48,8 +83,9 @@ impl HarnessBackend for KaniHarnessBackend {
             .then(|| {
                 precondition.map(|pre| {
                     let check_fn_name = pre.checker_name();
+                    let args = &call_args.precondition;
                     quote! {
-                        kani::assume(#check_fn_name(#(function_arg_struct.#function_args),*));
+                        kani::assume(#check_fn_name(#(#args),*));
                     }
                 })
             })
@@ -61,34 +97,67 @@ impl HarnessBackend for KaniHarnessBackend {
                 #[kani::unwind(#unwind)]
             }
         });
+        // Return value check code
+        let retv_check = if function.metadata.returns_reference() {
+            let mismatch = return_mismatch(function, self.catch_panic);
+            quote! { assert!(!(#mismatch)); }
+        } else {
+            quote! { assert!(r1 == r2); }
+        };
+        // Mutated `&mut` argument check code
+        let mut_arg_check = call_args
+            .mut_mismatch
+            .as_ref()
+            .map(|mismatch| quote! { assert!(!(#mismatch)); });
+        let (mod1_args, mod2_args) = (&call_args.mod1, &call_args.mod2);
+        let stub_attrs = &self.stub_attrs;
+        let r1_call = self.wrap_call(collect_if_iterator(
+            call_args.widen_result(1, quote! { mod1::#fn_name(#(#mod1_args),*) }),
+            function,
+        ));
+        let r2_call = self.wrap_call(collect_if_iterator(
+            call_args.widen_result(2, quote! { mod2::#fn_name(#(#mod2_args),*) }),
+            function,
+        ));
+        let setup = &call_args.setup;
 
         quote! {
             #[cfg(kani)]
             #[kani::proof]
             #[allow(non_snake_case)]
             #unwind_attr
+            #stub_attrs
             pub fn #test_fn_name() {
                 let function_arg_struct = kani::any::<#function_arg_struct>();
                 // Precondition assume
                 #precondition
                 // Function call
-                let r1 = mod1::#fn_name(#(function_arg_struct.#function_args),*);
-                let r2 = mod2::#fn_name(#(function_arg_struct.#function_args),*);
-                assert!(r1 == r2);
+                #setup
+                let r1 = #r1_call;
+                let r2 = #r2_call;
+                #retv_check
+                #mut_arg_check
             }
         }
     }
 
-    fn make_harness_for_method(
-        &self,
-        method: &CommonFunction,
-        constructor: &CommonFunction,
-        getter: Option<&CommonFunction>,
-        method_args: &[TokenStream],
-        constructor_args: &[TokenStream],
-        receiver_prefix: TokenStream,
-        precondition: Option<&Precondition>,
-    ) -> TokenStream {
+    fn make_harness_for_method(&self, harness: MethodHarness) -> TokenStream {
+        let MethodHarness {
+            method,
+            constructor,
+            getters,
+            // Formatting the whole state is too expensive to model-check
+            compare_state: _,
+            ref method_args,
+            ref constructor_args,
+            receiver_prefix,
+            precondition,
+            guard,
+        } = harness;
+        // Async methods are left to the testing components
+        if method.is_async() {
+            return quote! {};
+        }
         let fn_name = &method.metadata.name;
         let constr_name = &constructor.metadata.name;
 
@@ -99,11 +168,12 @@ impl HarnessBackend for KaniHarnessBackend {
         // Constructor argument struct name
         let constructor_arg_struct = format_ident!("Args{}", constr_name.to_ident());
 
-        // If a getter is provided, generate state check code after method call
-        let state_check = getter.map(|getter| {
+        // Compare the state through every getter after the method call
+        let state_check = getters.iter().map(|getter| {
             let getter = &getter.metadata.signature.0.ident;
+            let message = format!("state differs: {}", getter);
             quote! {
-                assert!(s1.#getter() == s2.#getter());
+                assert!(s1.#getter() == s2.#getter(), #message);
             }
         });
 
@@ -119,6 +189,8 @@ impl HarnessBackend for KaniHarnessBackend {
                 })
             })
             .flatten();
+        // Method guard assume
+        let guard = guard.map(|guard| quote! { kani::assume(#guard); });
         // If loop unwind is specified, add unwind attribute
         let unwind_attr = self.loop_unwind.map(|unwind| {
             let unwind = TokenStream::from_str(&unwind.to_string()).unwrap();
@@ -126,12 +198,33 @@ impl HarnessBackend for KaniHarnessBackend {
                 #[kani::unwind(#unwind)]
             }
         });
+        // Return value check code
+        let retv_check = if method.metadata.returns_reference() {
+            let mismatch = return_mismatch(method, self.catch_panic);
+            quote! { assert!(!(#mismatch)); }
+        } else {
+            quote! { assert!(r1 == r2); }
+        };
+        let stub_attrs = &self.stub_attrs;
+        let r1_call = self.wrap_call(collect_if_iterator(
+            quote! {
+                mod1::#fn_name(#receiver_prefix s1, #(method_arg_struct.#method_args),*)
+            },
+            method,
+        ));
+        let r2_call = self.wrap_call(collect_if_iterator(
+            quote! {
+                mod2::#fn_name(#receiver_prefix s2, #(method_arg_struct.#method_args),*)
+            },
+            method,
+        ));
 
         quote! {
             #[cfg(kani)]
             #[kani::proof]
             #[allow(non_snake_case)]
             #unwind_attr
+            #stub_attrs
             pub fn #test_fn_name() {
                 let constr_arg_struct = kani::any::<#constructor_arg_struct>();
                 // Construct s1 and s2
@@ -141,12 +234,13 @@ impl HarnessBackend for KaniHarnessBackend {
                 let method_arg_struct = kani::any::<#method_arg_struct>();
                 // Precondition assume
                 #precondition
+                #guard
                 // Do method call
-                let r1 = mod1::#fn_name(#receiver_prefix s1, #(method_arg_struct.#method_args),*);
-                let r2 = mod2::#fn_name(#receiver_prefix s2, #(method_arg_struct.#method_args),*);
+                let r1 = #r1_call;
+                let r2 = #r2_call;
 
-                assert!(r1 == r2);
-                #state_check
+                #retv_check
+                #(#state_check)*
             }
         }
     }
@@ -159,6 +253,7 @@ impl HarnessBackend for KaniHarnessBackend {
         methods: Vec<TokenStream>,
         _additional: TokenStream,
     ) -> TokenStream {
+        let stub_defs = &self.stub_defs;
         quote! {
             #![allow(unused)]
             #![allow(non_snake_case)]
@@ -170,6 +265,7 @@ impl HarnessBackend for KaniHarnessBackend {
             #(#args_structs)*
             #(#functions)*
             #(#methods)*
+            #stub_defs
 
             fn main() {}
         }
@@ -191,15 +287,34 @@ impl Kani {
     }
 
     /// Generate harness code for Kani.
-    fn generate_harness(&self, checker: &Checker) -> TokenStream {
+    fn generate_harness(&self, checker: &Checker) -> anyhow::Result<TokenStream> {
+        let mut stub_attrs = TokenStream::new();
+        for (original, stub) in &self.config.stubs {
+            let original = syn::parse_str::<syn::Path>(original)
+                .map_err(|_| anyhow!("Invalid stubbed function path `{}`", original))?;
+            let stub = syn::parse_str::<syn::Path>(stub)
+                .map_err(|_| anyhow!("Invalid stub function path `{}`", stub))?;
+            stub_attrs.extend(quote! { #[kani::stub(#original, #stub)] });
+        }
+        let stub_defs = match &self.config.stub_file {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read stub file `{}`: {}", path, e))?
+                .parse::<TokenStream>()
+                .map_err(|e| anyhow!("Failed to parse stub file `{}`: {}", path, e))?,
+            None => TokenStream::new(),
+        };
+
         let generator = KaniHarnessGenerator::new(
             checker,
             KaniHarnessBackend {
                 use_preconditions: self.config.use_preconditions,
                 loop_unwind: self.config.loop_unwind,
+                catch_panic: self.config.catch_panic,
+                stub_attrs,
+                stub_defs,
             },
         );
-        generator.generate_harness()
+        Ok(generator.generate_harness())
     }
 
     /// Create a cargo project for Kani harness.
@@ -227,20 +342,38 @@ kani = "*"
         )
     }
 
+    /// Arguments of `cargo` for running Kani with the configured timeout, enabling stubbing if
+    /// stubs are configured.
+    fn kani_args(&self) -> Vec<String> {
+        let mut args = [
+            "kani",
+            "-Z",
+            "unstable-options",
+            "--harness-timeout",
+            &format!("{}s", self.config.timeout_secs),
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect::<Vec<_>>();
+        if !self.config.stubs.is_empty() {
+            args.extend(["-Z".to_owned(), "stubbing".to_owned()]);
+        }
+        args
+    }
+
     /// Run Kani and save the output.
     fn run_kani(&self) -> anyhow::Result<()> {
-        let timeout_secs = self.config.timeout_secs;
-        let status = run_command(
+        if self.config.jobs > 1 {
+            return self.run_kani_parallel();
+        }
+        let args = self.kani_args();
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let status = run_command_with_retries(
             "cargo",
-            &[
-                "kani",
-                "-Z",
-                "unstable-options",
-                "--harness-timeout",
-                &format!("{}s", timeout_secs),
-            ],
+            &args,
             Some(&self.config.output_path),
             Some(&self.config.harness_path),
+            self.config.retries,
         )?;
 
         if status.code() == Some(101) {
@@ -249,18 +382,170 @@ kani = "*"
         Ok(())
     }
 
+    /// Names of the proof harnesses in the harness project.
+    fn harness_names(&self) -> anyhow::Result<Vec<String>> {
+        let file = format!("{}/src/main.rs", self.config.harness_path);
+        let content = std::fs::read_to_string(&file)
+            .map_err(|e| anyhow!("Failed to read harness file: {}", e))?;
+        let syntax = syn::parse_file(&content)
+            .map_err(|e| anyhow!("Failed to parse harness file: {}", e))?;
+        Ok(proof_harnesses(&syntax))
+    }
+
+    /// Absolute path of the harness project manifest.
+    fn harness_manifest(&self) -> anyhow::Result<String> {
+        std::fs::canonicalize(format!("{}/Cargo.toml", self.config.harness_path))
+            .map(|path| path.to_string_lossy().into_owned())
+            .map_err(|e| anyhow!("Failed to locate harness manifest: {}", e))
+    }
+
+    /// Run Kani on a single harness, optionally overriding its loop unwind bound, and save the
+    /// output to `output_path`.
+    ///
+    /// The harness project is selected with `--manifest-path`, so the output path stays relative
+    /// to the current directory.
+    fn run_kani_harness(
+        &self,
+        manifest: &str,
+        harness: &str,
+        unwind: Option<u32>,
+        output_path: &str,
+    ) -> anyhow::Result<()> {
+        let mut args = self.kani_args();
+        args.extend([
+            "--manifest-path".to_owned(),
+            manifest.to_owned(),
+            "--harness".to_owned(),
+            harness.to_owned(),
+            "--exact".to_owned(),
+        ]);
+        if let Some(unwind) = unwind {
+            args.extend(["--unwind".to_owned(), unwind.to_string()]);
+        }
+        let args = args.iter().map(String::as_str).collect::<Vec<_>>();
+        let status =
+            run_command_with_retries("cargo", &args, Some(output_path), None, self.config.retries)?;
+        if status.code() == Some(101) {
+            return Err(anyhow!("Command failed due to compilation error"));
+        }
+        Ok(())
+    }
+
+    /// Run one Kani invocation per harness, `jobs` at a time, and save their outputs in harness
+    /// order.
+    fn run_kani_parallel(&self) -> anyhow::Result<()> {
+        let harnesses = self.harness_names()?;
+        let manifest = self.harness_manifest()?;
+        log!(
+            Normal,
+            Info,
+            "Verifying {} harnesses with {} jobs",
+            harnesses.len(),
+            self.config.jobs
+        );
+
+        let outputs = (0..harnesses.len())
+            .map(|i| format!("{}.{}", self.config.output_path, i))
+            .collect::<Vec<_>>();
+        let next = AtomicUsize::new(0);
+        let errors = Mutex::new(Vec::new());
+        std::thread::scope(|scope| {
+            for _ in 0..self.config.jobs.min(harnesses.len()) {
+                scope.spawn(|| {
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(harness) = harnesses.get(index) else {
+                            break;
+                        };
+                        let res = self.run_kani_harness(&manifest, harness, None, &outputs[index]);
+                        if let Err(e) = res {
+                            errors.lock().unwrap().push(e);
+                        }
+                    }
+                });
+            }
+        });
+
+        // Concatenate the outputs, so that they are analyzed like a single Kani run
+        let mut output = std::fs::File::create(&self.config.output_path)
+            .map_err(|e| anyhow!("Failed to create output file: {}", e))?;
+        for path in &outputs {
+            if let Ok(content) = std::fs::read(path) {
+                output
+                    .write_all(&content)
+                    .map_err(|e| anyhow!("Failed to write output file: {}", e))?;
+            }
+            let _ = std::fs::remove_file(path);
+        }
+        match errors.into_inner().unwrap().into_iter().next() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Re-verify harnesses that failed only on unwinding assertions with doubled loop unwind
+    /// bounds, up to `max_unwind`, replacing their output with the output of the last retry.
+    fn escalate_unwind(&self) -> anyhow::Result<()> {
+        let (Some(unwind), Some(max_unwind)) = (self.config.loop_unwind, self.config.max_unwind)
+        else {
+            return Ok(());
+        };
+        let output = std::fs::read_to_string(&self.config.output_path)
+            .map_err(|e| anyhow!("Failed to read output file: {}", e))?;
+        let mut sections = harness_sections(&output);
+        if !sections.iter().any(|(_, text)| unwinding_failure(text)) {
+            return Ok(());
+        }
+
+        let manifest = self.harness_manifest()?;
+        let retry_output = format!("{}.unwind", self.config.output_path);
+        for (harness, text) in sections.iter_mut() {
+            let Some(harness) = harness else {
+                continue;
+            };
+            let mut bound = unwind;
+            while unwinding_failure(text) && bound < max_unwind {
+                bound = next_unwind(bound, max_unwind);
+                log!(
+                    Normal,
+                    Info,
+                    "Retrying `{}` with loop unwind {}",
+                    harness,
+                    bound
+                );
+                self.run_kani_harness(&manifest, harness, Some(bound), &retry_output)?;
+                let retry = std::fs::read_to_string(&retry_output)
+                    .map_err(|e| anyhow!("Failed to read output file: {}", e))?;
+                // Keep the section of the harness, dropping the compilation output
+                if let Some((_, retry_text)) = harness_sections(&retry)
+                    .into_iter()
+                    .find(|(name, _)| name.as_ref() == Some(harness))
+                {
+                    *text = retry_text;
+                }
+            }
+        }
+        let _ = std::fs::remove_file(&retry_output);
+
+        let output = sections
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect::<String>();
+        std::fs::write(&self.config.output_path, output)
+            .map_err(|e| anyhow!("Failed to write output file: {}", e))
+    }
+
     /// Analyze Kani output from "kani.tmp".
     fn analyze_kani_output(&self) -> CheckResult {
-        let mut res = CheckResult {
-            status: Ok(()),
-            ok: vec![],
-            fail: vec![],
-        };
+        let mut res = CheckResult::default();
 
         let re = Regex::new(r"Checking harness check_([0-9a-zA-Z_]+)\.").unwrap();
+        let time_re = Regex::new(r"Verification Time: ([0-9.]+)s").unwrap();
         let file = std::fs::File::open(&self.config.output_path).unwrap();
         let reader = std::io::BufReader::new(file);
         let mut func_name: Option<String> = None;
+        // Kani prints the verification time after the verdict
+        let mut last_checked: Option<String> = None;
 
         for line in reader.lines() {
             let line = line.unwrap();
@@ -268,9 +553,17 @@ kani = "*"
                 func_name = Some(caps[1].replace("___", "::"));
             }
             if line.contains("VERIFICATION:- SUCCESSFUL") && func_name.is_some() {
-                res.ok.push(Path::from_str(&func_name.take().unwrap()));
+                last_checked = func_name.take();
+                res.ok.push(Path::from(last_checked.as_deref().unwrap()));
             } else if line.contains("VERIFICATION:- FAILED") && func_name.is_some() {
-                res.fail.push(Path::from_str(&func_name.take().unwrap()));
+                last_checked = func_name.take();
+                res.fail.push(Path::from(last_checked.as_deref().unwrap()));
+            } else if let Some(caps) = time_re.captures(&line)
+                && let Some(name) = last_checked.take()
+                && let Ok(secs) = caps[1].parse::<f64>()
+            {
+                res.timings
+                    .push((Path::from(name.as_str()), Duration::from_secs_f64(secs)));
             }
         }
 
@@ -303,30 +596,198 @@ impl Component for Kani {
         Some("Use Kani model-checker to check function consistency")
     }
 
+    fn cache_key(&self) -> String {
+        // Stubs replace functions, so their definitions are part of what is proven
+        let stub_code = self
+            .config
+            .stub_file
+            .as_ref()
+            .and_then(|path| std::fs::read_to_string(path).ok());
+        format!(
+            "preconditions={} unwind={:?} max_unwind={:?} catch_panic={} stubs={:?} {:?}",
+            self.config.use_preconditions,
+            self.config.loop_unwind,
+            self.config.max_unwind,
+            self.config.catch_panic,
+            self.config.stubs,
+            stub_code
+        )
+    }
+
     fn run(&self, checker: &Checker) -> CheckResult {
         if self.config.gen_harness {
-            let harness = self.generate_harness(checker);
-            let res = self.create_harness_project(checker, harness);
+            let res = self
+                .generate_harness(checker)
+                .and_then(|harness| self.create_harness_project(checker, harness));
             if let Err(e) = res {
                 return CheckResult::failed(e);
             }
         }
-        let res = self.run_kani();
+        if let Some(dir) = &checker.options().dump_harness
+            && let Err(e) = dump_harness(dir, "kani", &self.config.harness_path)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
+        let res = self.run_kani().and_then(|_| self.escalate_unwind());
         if let Err(e) = res {
             return CheckResult::failed(e);
         }
         let check_res = self.analyze_kani_output();
-        if !self.config.keep_harness {
-            if let Err(e) = self.remove_harness_project() {
-                return CheckResult::failed(e);
-            }
+        // Keep everything for inspection if any function failed
+        let keep_all = self.config.keep_on_failure && !check_res.fail.is_empty();
+        if !self.config.keep_harness
+            && !keep_all
+            && let Err(e) = self.remove_harness_project()
+        {
+            return CheckResult::failed(e);
         }
-        if !self.config.keep_output {
-            if let Err(e) = self.remove_output_file() {
-                return CheckResult::failed(e);
-            }
+        if !self.config.keep_output
+            && !keep_all
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
         }
 
         check_res
     }
 }
+
+/// Names of the `#[kani::proof]` functions of a harness file.
+fn proof_harnesses(syntax: &syn::File) -> Vec<String> {
+    syntax
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Fn(func)
+                if func.attrs.iter().any(|attr| {
+                    let segments = attr.path().segments.iter();
+                    segments
+                        .map(|seg| seg.ident.to_string())
+                        .eq(["kani", "proof"])
+                }) =>
+            {
+                Some(func.sig.ident.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Split Kani output into the sections of each harness, starting at `Checking harness <name>...`.
+/// Output before the first harness forms a section without name.
+fn harness_sections(output: &str) -> Vec<(Option<String>, String)> {
+    let re = Regex::new(r"^Checking harness ([0-9a-zA-Z_]+)\.").unwrap();
+    let mut sections: Vec<(Option<String>, String)> = vec![(None, String::new())];
+    for line in output.lines() {
+        if let Some(caps) = re.captures(line) {
+            sections.push((Some(caps[1].to_owned()), String::new()));
+        }
+        let text = &mut sections.last_mut().unwrap().1;
+        text.push_str(line);
+        text.push('\n');
+    }
+    sections
+}
+
+/// Whether a harness section failed, and every failed check is an unwinding assertion.
+fn unwinding_failure(section: &str) -> bool {
+    let mut failed_checks = section
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("Failed Checks:"))
+        .peekable();
+    section.contains("VERIFICATION:- FAILED")
+        && failed_checks.peek().is_some()
+        && failed_checks.all(|check| check.trim().starts_with("unwinding assertion"))
+}
+
+/// Next loop unwind bound to try after an unwinding assertion failure.
+fn next_unwind(bound: u32, max_unwind: u32) -> u32 {
+    bound.saturating_mul(2).min(max_unwind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::test_checker;
+
+    /// Checker over `src` in both sources.
+    fn checker(src: &str) -> Checker {
+        test_checker(src, src, vec![])
+    }
+
+    #[test]
+    fn harness_names_are_the_check_functions() {
+        let checker = checker(
+            "pub fn f(x: u8) -> u8 { x }
+            pub mod m {
+                pub fn g(x: u8) -> u8 { x }
+            }",
+        );
+        let harness = Kani::new(KaniConfig::default())
+            .generate_harness(&checker)
+            .unwrap();
+        let mut names = proof_harnesses(&syn::parse2(harness).unwrap());
+        names.sort();
+        assert_eq!(names, ["check_f", "check_m___g"]);
+    }
+
+    #[test]
+    fn calls_are_caught_with_catch_panic() {
+        let checker = checker("pub fn f(x: u8) -> u8 { 100 / x }");
+        let harness = |catch_panic| {
+            let config = KaniConfig {
+                catch_panic,
+                ..Default::default()
+            };
+            let kani = Kani::new(config);
+            kani.generate_harness(&checker).unwrap().to_string()
+        };
+        let caught = "let r1 = std :: panic :: catch_unwind (std :: panic :: AssertUnwindSafe (|| \
+                      mod1 :: f (function_arg_struct . x . clone ()))) . map_err (| _ | ()) ;";
+        assert!(harness(true).contains(caught));
+        assert!(harness(true).contains("assert ! (r1 == r2)"));
+        let uncaught = "let r1 = mod1 :: f (function_arg_struct . x . clone ()) ;";
+        assert!(harness(false).contains(uncaught));
+        assert!(!harness(false).contains("catch_unwind"));
+    }
+
+    #[test]
+    fn stubs_are_attached_to_every_harness() {
+        let checker = checker(
+            "pub fn f(x: u8) -> u8 { x }
+            pub fn g(x: u8) -> u8 { x }",
+        );
+        let config = KaniConfig {
+            stubs: vec![("mod1::f".to_owned(), "stub_f".to_owned())],
+            ..Default::default()
+        };
+        let harness = Kani::new(config).generate_harness(&checker).unwrap();
+        let harness = harness.to_string();
+        let stub = "# [kani :: stub (mod1 :: f , stub_f)]";
+        assert_eq!(harness.matches(stub).count(), 2);
+    }
+
+    #[test]
+    fn unwinding_failures_escalate_the_bound() {
+        let output = "Compiling harness
+Checking harness check_f...
+Failed Checks: unwinding assertion loop 0
+VERIFICATION:- FAILED
+Checking harness check_g...
+Failed Checks: assertion failed: r1 == r2
+Failed Checks: unwinding assertion loop 0
+VERIFICATION:- FAILED
+";
+        let sections = harness_sections(output);
+        let names = sections.iter().map(|(name, _)| name.as_deref());
+        assert!(names.eq([None, Some("check_f"), Some("check_g")]));
+        // Only a harness failing on unwinding assertions alone is retried
+        assert!(unwinding_failure(&sections[1].1));
+        assert!(!unwinding_failure(&sections[2].1));
+
+        let bounds = std::iter::successors(Some(10), |&bound| {
+            (bound < 64).then(|| next_unwind(bound, 64))
+        });
+        assert!(bounds.eq([10, 20, 40, 64]));
+    }
+}
@@ -2,12 +2,16 @@
 
 mod alive2;
 mod df;
+mod exhaustive;
+mod external_formal;
 mod identical;
 mod kani;
 mod pbt;
 
 pub use alive2::Alive2;
 pub use df::DifferentialFuzzing;
+pub use exhaustive::Exhaustive;
+pub use external_formal::ExternalFormal;
 pub use identical::Identical;
 pub use kani::Kani;
 pub use pbt::PropertyBasedTesting;
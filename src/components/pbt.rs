@@ -12,9 +12,14 @@ use std::{
 use crate::{
     check::{CheckResult, Checker, Component},
     config::PBTConfig,
-    defs::{CommonFunction, Path, Precondition},
-    generate::{HarnessBackend, HarnessGenerator},
-    utils::{create_harness_project, run_command},
+    defs::{ArgBound, CommonFunction, Path, Precondition},
+    generate::{
+        FunctionCallArgs, HarnessBackend, HarnessGenerator, MethodHarness, bind_call,
+        block_on_if_async, capture_output_helper, collect_if_iterator, map_panic_payload,
+        panic_message_helper, return_mismatch, state_mismatches,
+    },
+    log,
+    utils::{create_harness_project, dump_harness, run_command_with_retries},
 };
 
 /// PBT harness generator backend.
@@ -25,6 +30,53 @@ struct PBTHarnessBackend {
     timeout_secs: u64,
     /// Use preconditions.
     use_preconditions: bool,
+    /// Absolute path of the proptest regression file, if persistence is enabled.
+    regressions_path: Option<String>,
+    /// Capture and compare stdout and stderr of each call.
+    capture_output: bool,
+    /// Fixed RNG seed.
+    seed: Option<u64>,
+    /// Compare the messages of caught panics.
+    compare_panic_payload: bool,
+    /// Count inputs accepted and rejected by preconditions and guards.
+    report_filter_rate: bool,
+    /// Generate arguments within the ranges implied by preconditions.
+    precondition_strategies: bool,
+}
+
+impl PBTHarnessBackend {
+    /// Declaration of the `FILTER` counter of a test, with the statements recording an accepted
+    /// and a rejected input (see `filter_counter_helper`). `rejects_complete` is set if rejected
+    /// inputs still count as proptest cases, i.e. are skipped by an early return rather than by
+    /// `prop_assume!`. `None` unless filter rates are reported.
+    fn filter_counter(
+        &self,
+        fn_name: &str,
+        rejects_complete: bool,
+    ) -> Option<(TokenStream, TokenStream, TokenStream)> {
+        if !self.report_filter_rate {
+            return None;
+        }
+        let cases = self.cases as u64;
+        Some((
+            quote! { static FILTER: FilterCounter = FilterCounter::new(); },
+            quote! { FILTER.record(#fn_name, true, true, #cases); },
+            quote! { FILTER.record(#fn_name, false, #rejects_complete, #cases); },
+        ))
+    }
+
+    /// Output check code reporting an `OUTPUT MISMATCH` if the captured output differs.
+    fn output_check(&self, fn_name: &str, arg_report: TokenStream) -> Option<TokenStream> {
+        self.capture_output.then(|| {
+            quote! {
+                if out1 != out2 {
+                    println!("OUTPUT MISMATCH {}", #fn_name);
+                    #arg_report
+                    assert!(false);
+                }
+            }
+        })
+    }
 }
 
 impl HarnessBackend for PBTHarnessBackend {
@@ -35,10 +87,35 @@ impl HarnessBackend for PBTHarnessBackend {
         }
     }
 
+    fn bounded_field_attrs(&self, ty: &TokenStream, bound: &ArgBound) -> TokenStream {
+        if !self.use_preconditions || !self.precondition_strategies {
+            return quote! {};
+        }
+        let ty = ty.to_string();
+        let Some((ty_min, ty_max)) = int_range(&ty) else {
+            return quote! {};
+        };
+        let min = bound.min.map_or(ty_min, |min| min.max(ty_min));
+        let max = bound.max.map_or(ty_max, |max| max.min(ty_max));
+        if min > max {
+            // Unsatisfiable, leave it to the precondition check
+            return quote! {};
+        }
+        // The precondition is still checked, the range only makes most inputs pass it
+        let strategy = if max < ty_max {
+            format!("{}{}..{}{}", min, ty, max + 1, ty)
+        } else {
+            format!("{}{}..={}{}", min, ty, max, ty)
+        };
+        quote! {
+            #[cfg_attr(test, proptest(strategy = #strategy))]
+        }
+    }
+
     fn make_harness_for_function(
         &self,
         function: &CommonFunction,
-        function_args: &[TokenStream],
+        call_args: &FunctionCallArgs,
         precondition: Option<&Precondition>,
     ) -> TokenStream {
         let fn_name = &function.metadata.name;
@@ -55,8 +132,19 @@ impl HarnessBackend for PBTHarnessBackend {
             .then(|| {
                 precondition.map(|pre| {
                     let check_fn_name = pre.checker_name();
-                    quote! {
-                        prop_assume!(#check_fn_name(#(function_arg_struct.#function_args),*));
+                    let args = &call_args.precondition;
+                    match self.filter_counter(&fn_name_string, false) {
+                        Some((counter, accept, reject)) => quote! {
+                            #counter
+                            if !#check_fn_name(#(#args),*) {
+                                #reject
+                                prop_assume!(false);
+                            }
+                            #accept
+                        },
+                        None => quote! {
+                            prop_assume!(#check_fn_name(#(#args),*));
+                        },
                     }
                 })
             })
@@ -67,12 +155,62 @@ impl HarnessBackend for PBTHarnessBackend {
             println!("function: {:?}", function_arg_struct);
         };
         // Return value check code
+        let mismatch = return_mismatch(function, true);
         let retv_check = quote! {
-            if r1 != r2 {
+            if #mismatch {
                 #err_report
                 assert!(false);
             }
         };
+        // Mutated `&mut` argument check code
+        let mut_arg_check = call_args.mut_mismatch.as_ref().map(|mismatch| {
+            quote! {
+                if #mismatch {
+                    #err_report
+                    assert!(false);
+                }
+            }
+        });
+        let output_check = self.output_check(
+            &fn_name_string,
+            quote! { println!("function: {:?}", function_arg_struct); },
+        );
+        let (setup, mod1_args, mod2_args) = (&call_args.setup, &call_args.mod1, &call_args.mod2);
+        let mod1_call = collect_if_iterator(
+            block_on_if_async(
+                call_args.widen_result(1, quote! { mod1::#fn_name(#(#mod1_args),*) }),
+                function.asyncness.0,
+            ),
+            function,
+        );
+        let mod2_call = collect_if_iterator(
+            block_on_if_async(
+                call_args.widen_result(2, quote! { mod2::#fn_name(#(#mod2_args),*) }),
+                function.asyncness.1,
+            ),
+            function,
+        );
+        let map_err = map_panic_payload(self.compare_panic_payload);
+        let r1_call = bind_call(
+            1,
+            quote! {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    #mod1_call
+                }))
+                #map_err
+            },
+            self.capture_output,
+        );
+        let r2_call = bind_call(
+            2,
+            quote! {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    #mod2_call
+                }))
+                #map_err
+            },
+            self.capture_output,
+        );
 
         quote! {
             #[test]
@@ -81,30 +219,29 @@ impl HarnessBackend for PBTHarnessBackend {
                 #precondition
 
                 // Function call
-                let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    mod1::#fn_name(#(function_arg_struct.#function_args),*)
-                }))
-                .map_err(|_| ());
-                let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    mod2::#fn_name(#(function_arg_struct.#function_args),*)
-                }))
-                .map_err(|_| ());
+                #setup
+                #r1_call
+                #r2_call
 
                 #retv_check
+                #mut_arg_check
+                #output_check
             }
         }
     }
 
-    fn make_harness_for_method(
-        &self,
-        method: &CommonFunction,
-        constructor: &CommonFunction,
-        getter: Option<&CommonFunction>,
-        method_args: &[TokenStream],
-        constructor_args: &[TokenStream],
-        receiver_prefix: TokenStream,
-        precondition: Option<&Precondition>,
-    ) -> TokenStream {
+    fn make_harness_for_method(&self, harness: MethodHarness) -> TokenStream {
+        let MethodHarness {
+            method,
+            constructor,
+            getters,
+            compare_state,
+            ref method_args,
+            ref constructor_args,
+            receiver_prefix,
+            precondition,
+            guard,
+        } = harness;
         let fn_name = &method.metadata.name;
         let constr_name = &constructor.metadata.name;
         let fn_name_string = fn_name.to_string();
@@ -116,16 +253,38 @@ impl HarnessBackend for PBTHarnessBackend {
         // Constructor argument struct name
         let constructor_arg_struct = format_ident!("Args{}", constr_name.to_ident());
 
+        // Count inputs skipped by the precondition or the guard
+        let precondition = precondition.filter(|_| self.use_preconditions);
+        let counter = (precondition.is_some() || guard.is_some())
+            .then(|| self.filter_counter(&fn_name_string, true))
+            .flatten();
+        let (counter, accept, reject) = match counter {
+            Some((counter, accept, reject)) => (Some(counter), Some(accept), Some(reject)),
+            None => (None, None, None),
+        };
+
         // If a precondition is provided, add assume statements before method call
-        let precondition = self.use_preconditions.then(|| {
-            precondition.map(|pre| {
-                let check_fn_name = pre.checker_name();
-                quote! {
-                    if !s2.#check_fn_name(#(method_arg_struct.#method_args),*) {
-                        return Ok(());
-                    }
+        let precondition = precondition.map(|pre| {
+            let check_fn_name = pre.checker_name();
+            quote! {
+                if !s2.#check_fn_name(#(method_arg_struct.#method_args),*) {
+                    #reject
+                    return Ok(());
                 }
-            })
+            }
+        });
+
+        // Method guard check. A panicking guard lets the call proceed, so that the panic is
+        // compared by the call itself.
+        let guard = guard.map(|guard| {
+            quote! {
+                if !std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| #guard))
+                    .unwrap_or(true)
+                {
+                    #reject
+                    return Ok(());
+                }
+            }
         });
 
         // Error report message
@@ -134,22 +293,70 @@ impl HarnessBackend for PBTHarnessBackend {
             println!("method: {:?}", method_arg_struct);
         };
         // Return value check code
+        let mismatch = return_mismatch(method, true);
         let retv_check = quote! {
-            if r1 != r2 {
+            if #mismatch {
                 #err_report
                 assert!(false);
             }
         };
-        // If a getter is provided, generate state check code after method call
-        let state_check = getter.map(|getter| {
-            let getter = &getter.metadata.signature.0.ident;
-            quote! {
-                if s1.#getter() != s2.#getter() {
-                    #err_report
-                    assert!(false);
+        // If the state can be compared, check it after the method call, one getter at a time
+        let state_check = state_mismatches(getters, compare_state)
+            .into_iter()
+            .map(|(state, mismatch)| {
+                quote! {
+                    if #mismatch {
+                        #err_report
+                        println!("state differs: {}", #state);
+                        assert!(false);
+                    }
                 }
-            }
-        });
+            })
+            .collect::<Vec<_>>();
+
+        let mod1_call = collect_if_iterator(
+            block_on_if_async(
+                quote! {
+                    mod1::#fn_name(#receiver_prefix s1, #(method_arg_struct.#method_args),*)
+                },
+                method.asyncness.0,
+            ),
+            method,
+        );
+        let mod2_call = collect_if_iterator(
+            block_on_if_async(
+                quote! {
+                    mod2::#fn_name(#receiver_prefix s2, #(method_arg_struct.#method_args),*)
+                },
+                method.asyncness.1,
+            ),
+            method,
+        );
+        let output_check = self.output_check(
+            &fn_name_string,
+            quote! { println!("method: {:?}", method_arg_struct); },
+        );
+        let map_err = map_panic_payload(self.compare_panic_payload);
+        let r1_call = bind_call(
+            1,
+            quote! {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    #mod1_call
+                }))
+                #map_err
+            },
+            self.capture_output,
+        );
+        let r2_call = bind_call(
+            2,
+            quote! {
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    #mod2_call
+                }))
+                #map_err
+            },
+            self.capture_output,
+        );
 
         quote! {
             #[test]
@@ -172,24 +379,18 @@ impl HarnessBackend for PBTHarnessBackend {
                 };
 
                 // Precondition assume
+                #counter
                 #precondition
+                #guard
+                #accept
 
                 // Method call
-                let r1 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    mod1::#fn_name(
-                        #receiver_prefix s1, #(method_arg_struct.#method_args),*
-                    )
-                }))
-                .map_err(|_| ());
-                let r2 = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-                    mod2::#fn_name(
-                        #receiver_prefix s2, #(method_arg_struct.#method_args),*
-                    )
-                }))
-                .map_err(|_| ());
+                #r1_call
+                #r2_call
 
                 #retv_check
-                #state_check
+                #(#state_check)*
+                #output_check
             }
         }
     }
@@ -204,6 +405,21 @@ impl HarnessBackend for PBTHarnessBackend {
     ) -> TokenStream {
         let cases = TokenStream::from_str(&self.cases.to_string()).unwrap();
         let timeout = TokenStream::from_str(&(self.timeout_secs * 1000).to_string()).unwrap();
+        let failure_persistence = self.regressions_path.as_ref().map(|path| {
+            quote! {
+                failure_persistence: Some(Box::new(
+                    proptest::test_runner::FileFailurePersistence::Direct(#path)
+                )),
+            }
+        });
+        let rng_seed = self.seed.map(|seed| {
+            quote! {
+                rng_seed: proptest::test_runner::RngSeed::Fixed(#seed),
+            }
+        });
+        let capture_output = self.capture_output.then(capture_output_helper);
+        let panic_message = self.compare_panic_payload.then(panic_message_helper);
+        let filter_counter = self.report_filter_rate.then(filter_counter_helper);
         quote! {
             #![allow(unused)]
             #![allow(non_snake_case)]
@@ -214,10 +430,15 @@ impl HarnessBackend for PBTHarnessBackend {
 
             #(#imports)*
             #(#args_structs)*
+            #capture_output
+            #panic_message
+            #filter_counter
             proptest! {
                 #![proptest_config(ProptestConfig {
                     cases: #cases,
                     timeout: #timeout,
+                    #failure_persistence
+                    #rng_seed
                     .. ProptestConfig::default()
                 })]
                 #(#functions)*
@@ -228,6 +449,64 @@ impl HarnessBackend for PBTHarnessBackend {
     }
 }
 
+/// Range of a primitive integer type, `None` for other types.
+fn int_range(ty: &str) -> Option<(i128, i128)> {
+    let range = match ty {
+        "u8" => (0, u8::MAX as i128),
+        "u16" => (0, u16::MAX as i128),
+        "u32" => (0, u32::MAX as i128),
+        "u64" => (0, u64::MAX as i128),
+        "usize" => (0, usize::MAX as i128),
+        "i8" => (i8::MIN as i128, i8::MAX as i128),
+        "i16" => (i16::MIN as i128, i16::MAX as i128),
+        "i32" => (i32::MIN as i128, i32::MAX as i128),
+        "i64" => (i64::MIN as i128, i64::MAX as i128),
+        "isize" => (isize::MIN as i128, isize::MAX as i128),
+        _ => return None,
+    };
+    Some(range)
+}
+
+/// Harness type `FilterCounter`, counting the inputs of a test accepted and rejected by its
+/// filters.
+///
+/// proptest doesn't report when a test is done, so the counts are printed as a `FILTER` line once
+/// `cases` inputs have completed, which is when proptest stops unless the test fails. Only
+/// rejections by an early return complete an input, `prop_assume!` makes proptest draw another.
+fn filter_counter_helper() -> TokenStream {
+    quote! {
+        struct FilterCounter {
+            accepted: std::sync::atomic::AtomicU64,
+            rejected: std::sync::atomic::AtomicU64,
+            completed: std::sync::atomic::AtomicU64,
+        }
+
+        impl FilterCounter {
+            const fn new() -> Self {
+                Self {
+                    accepted: std::sync::atomic::AtomicU64::new(0),
+                    rejected: std::sync::atomic::AtomicU64::new(0),
+                    completed: std::sync::atomic::AtomicU64::new(0),
+                }
+            }
+
+            fn record(&self, name: &str, accepted: bool, completes: bool, cases: u64) {
+                use std::sync::atomic::Ordering::Relaxed;
+                let count = if accepted { &self.accepted } else { &self.rejected };
+                count.fetch_add(1, Relaxed);
+                if completes && self.completed.fetch_add(1, Relaxed) + 1 == cases {
+                    println!(
+                        "FILTER {}: accepted {} rejected {}",
+                        name,
+                        self.accepted.load(Relaxed),
+                        self.rejected.load(Relaxed)
+                    );
+                }
+            }
+        }
+    }
+}
+
 /// PBT harness generator.
 type PBTHarnessGenerator = HarnessGenerator<PBTHarnessBackend>;
 
@@ -242,6 +521,15 @@ impl PropertyBasedTesting {
         Self { config }
     }
 
+    /// Absolute path of the regression file, so that it stays outside the harness project.
+    fn regressions_path(&self) -> Option<String> {
+        self.config.regressions_path.as_ref().map(|path| {
+            std::env::current_dir()
+                .map(|dir| dir.join(path).to_string_lossy().into_owned())
+                .unwrap_or_else(|_| path.clone())
+        })
+    }
+
     /// Generate the PBT harness.
     fn generate_harness(&self, checker: &Checker) -> TokenStream {
         let generator = PBTHarnessGenerator::new(
@@ -250,6 +538,12 @@ impl PropertyBasedTesting {
                 cases: self.config.test_cases,
                 timeout_secs: self.config.timeout_secs,
                 use_preconditions: self.config.use_preconditions,
+                regressions_path: self.regressions_path(),
+                capture_output: self.config.capture_output,
+                seed: self.config.seed,
+                compare_panic_payload: self.config.compare_panic_payload,
+                report_filter_rate: self.config.report_filter_rate,
+                precondition_strategies: self.config.precondition_strategies,
             },
         );
         generator.generate_harness()
@@ -261,7 +555,7 @@ impl PropertyBasedTesting {
         checker: &Checker,
         harness: TokenStream,
     ) -> anyhow::Result<()> {
-        let toml = r#"
+        let mut toml = r#"
 [package]
 name = "harness"
 version = "0.1.0"
@@ -270,7 +564,14 @@ edition = "2024"
 [dependencies]
 proptest = "1.9"
 proptest-derive = "0.2.0"
-"#;
+"#
+        .to_string();
+        if self.config.capture_output {
+            toml.push_str("gag = \"1\"\n");
+        }
+        if checker.under_checking_funcs.iter().any(|f| f.is_async()) {
+            toml.push_str("futures = \"0.3\"\n");
+        }
         // Set RUST_MIN_STACK to 16MB to avoid stack overflow in proptest
         let config = r#"
 [env]
@@ -281,7 +582,7 @@ RUST_MIN_STACK = "16777216"
             &checker.src1.content,
             &checker.src2.content,
             &harness.to_string(),
-            toml,
+            &toml,
             false,
         )?;
         std::fs::create_dir_all(format!("{}/.cargo", &self.config.harness_path))?;
@@ -294,39 +595,122 @@ RUST_MIN_STACK = "16777216"
 
     /// Run libAFL fuzzer and save the ouput in "df.tmp".
     fn run_test(&self) -> anyhow::Result<()> {
-        run_command(
+        // Output redirection is process-wide, so captured tests must run one at a time. Test
+        // output is not captured by the test harness either, otherwise it never reaches stdout.
+        // Filter rates are printed by passing tests, whose output is shown only on request.
+        let args: &[&str] = if self.config.capture_output {
+            &["test", "--", "--nocapture", "--test-threads=1"]
+        } else if self.config.report_filter_rate {
+            &["test", "--", "--show-output"]
+        } else {
+            &["test"]
+        };
+        run_command_with_retries(
             "cargo",
-            &["test"],
+            args,
             Some(&self.config.output_path),
             Some(&self.config.harness_path),
+            self.config.retries,
         )?;
         Ok(())
     }
 
     /// Analyze the fuzzer output and return the functions that are not checked.
     fn analyze_pbt_output(&self) -> CheckResult {
-        let mut res = CheckResult {
-            status: Ok(()),
-            ok: vec![],
-            fail: vec![],
-        };
+        let mut res = CheckResult::default();
 
         let re_ok = Regex::new(r"test check_\s*(\S+) ... ok").unwrap();
         let re_fail = Regex::new(r"test check_\s*(\S+) ... FAILED").unwrap();
+        // Start of a failed test's captured output, where proptest reports the shrunk input
+        let re_block = Regex::new(r"^---- check_(\S+) stdout ----").unwrap();
+        // With `capture_output`, test output is printed between the test name and its result
+        let re_start = Regex::new(r"^test check_(\S+) \.\.\. $").unwrap();
+        let re_output = Regex::new(r"^OUTPUT MISMATCH (\S+)").unwrap();
+        let re_filter = Regex::new(r"^FILTER (\S+): accepted (\d+) rejected (\d+)$").unwrap();
 
         let file = std::fs::File::open(&self.config.output_path).unwrap();
         let reader = BufReader::new(file);
+        // Function of the current failure block, and whether we are inside its minimal input
+        let mut block: Option<Path> = None;
+        let mut in_input = false;
+        // Test whose result is printed on a later line
+        let mut pending: Option<Path> = None;
 
         for line in reader.lines() {
-            if let Some(caps) = re_ok.captures(line.as_ref().unwrap()) {
+            let line = line.unwrap();
+            if let Some(caps) = re_filter.captures(&line) {
+                let accepted: u64 = caps[2].parse().unwrap();
+                let rejected: u64 = caps[3].parse().unwrap();
+                log!(
+                    Brief,
+                    Info,
+                    "Filters of `{}` accepted {} of {} inputs ({:.1}%)",
+                    &caps[1],
+                    accepted,
+                    accepted + rejected,
+                    100.0 * accepted as f64 / (accepted + rejected).max(1) as f64
+                );
+            }
+            if let Some(caps) = re_ok.captures(&line) {
                 let func_name = caps[1].to_string().replace("___", "::");
-                res.ok.push(Path::from_str(&func_name));
+                res.ok.push(Path::from(func_name.as_str()));
             }
-            if let Some(caps) = re_fail.captures(line.as_ref().unwrap()) {
+            if let Some(caps) = re_fail.captures(&line) {
                 let func_name = caps[1].to_string().replace("___", "::");
-                res.fail.push(Path::from_str(&func_name));
+                res.fail.push(Path::from(func_name.as_str()));
+            }
+            if let Some(caps) = re_start.captures(&line) {
+                pending = Some(Path::from(caps[1].replace("___", "::").as_str()));
+            } else if (line == "ok" || line == "FAILED")
+                && let Some(func) = pending.take()
+            {
+                if line == "ok" {
+                    res.ok.push(func);
+                } else {
+                    res.fail.push(func);
+                }
+            }
+            if block.is_none()
+                && let Some(caps) = re_output.captures(&line)
+            {
+                // Inline output, report the mismatch category as the counterexample
+                let func = Path::from(&caps[1]);
+                if !res.counterexamples.iter().any(|(f, _)| *f == func) {
+                    res.counterexamples.push((func, line.clone()));
+                }
+            }
+            if let Some(caps) = re_block.captures(&line) {
+                let func = Path::from(caps[1].replace("___", "::").as_str());
+                res.counterexamples.push((func.clone(), String::new()));
+                block = Some(func);
+                in_input = false;
+                continue;
+            }
+            let Some((_, report)) = block
+                .as_ref()
+                .and_then(|func| res.counterexamples.iter_mut().find(|(f, _)| f == func))
+            else {
+                continue;
+            };
+            if line.starts_with("minimal failing input:") {
+                in_input = true;
+            } else if in_input && (line.trim().is_empty() || line.starts_with('\t')) {
+                // Shrinking statistics follow the minimal input
+                in_input = false;
+            }
+            // The output mismatch is reported once per shrinking step, keep the first
+            let output_mismatch = line.starts_with("OUTPUT MISMATCH") && !report.contains(&line);
+            if in_input
+                || output_mismatch
+                || line.starts_with("proptest: Saving this and future failures")
+            {
+                if !report.is_empty() {
+                    report.push('\n');
+                }
+                report.push_str(&line);
             }
         }
+        res.counterexamples.retain(|(_, report)| !report.is_empty());
 
         res
     }
@@ -365,6 +749,11 @@ impl Component for PropertyBasedTesting {
                 return CheckResult::failed(e);
             }
         }
+        if let Some(dir) = &checker.options().dump_harness
+            && let Err(e) = dump_harness(dir, "pbt", &self.config.harness_path)
+        {
+            log!(Brief, Warning, "{}", e);
+        }
 
         let res = self.run_test();
         if let Err(e) = res {
@@ -372,17 +761,363 @@ impl Component for PropertyBasedTesting {
         }
         let check_res = self.analyze_pbt_output();
 
-        if !self.config.keep_harness {
-            if let Err(e) = self.remove_harness_project() {
-                return CheckResult::failed(e);
-            }
+        // Keep everything for inspection if any function failed
+        let keep_all = self.config.keep_on_failure && !check_res.fail.is_empty();
+        if !self.config.keep_harness
+            && !keep_all
+            && let Err(e) = self.remove_harness_project()
+        {
+            return CheckResult::failed(e);
         }
-        if !self.config.keep_output {
-            if let Err(e) = self.remove_output_file() {
-                return CheckResult::failed(e);
-            }
+        if !self.config.keep_output
+            && !keep_all
+            && let Err(e) = self.remove_output_file()
+        {
+            return CheckResult::failed(e);
         }
 
         check_res
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckerOptions, Source, check::test_checker, log::init_test_logger};
+
+    #[test]
+    fn every_getter_is_compared() {
+        init_test_logger();
+        let src = "pub struct Counter(u8, u8);
+            impl Counter {
+                pub fn verieasy_new(a: u8) -> Self { Counter(a, 0) }
+                #[verieasy_getter]
+                pub fn value(&self) -> u8 { self.0 }
+                #[verieasy_getter]
+                pub fn steps(&self) -> u8 { self.1 }
+                pub fn bump(&mut self) { self.0 = self.0.wrapping_add(1); self.1 += 1; }
+            }";
+        let checker = test_checker(src, src, vec![]);
+        let pbt = PropertyBasedTesting::new(PBTConfig::default());
+        let harness = pbt.generate_harness(&checker).to_string();
+
+        assert!(harness.contains("s1 . value () != s2 . value ()"));
+        assert!(harness.contains("s1 . steps () != s2 . steps ()"));
+    }
+
+    #[test]
+    fn failing_run_keeps_harness() {
+        init_test_logger();
+        let src = "pub fn f(x: u8) -> u8 { x }";
+        let checker = test_checker(src, src, vec![]);
+        let dir = std::env::temp_dir().join(format!("veri-easy-pbt-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = PBTConfig {
+            harness_path: dir.join("harness").to_string_lossy().into_owned(),
+            output_path: dir.join("pbt.tmp").to_string_lossy().into_owned(),
+            gen_harness: false,
+            ..Default::default()
+        };
+        // A prebuilt harness whose only test fails, as a mismatch of `f` would
+        let harness = "mod mod1; mod mod2; fn main() {} #[test] fn check_f() { panic!() }";
+        let toml = "[package]\nname = \"harness\"\nversion = \"0.1.0\"\nedition = \"2024\"\n";
+
+        for keep_on_failure in [false, true] {
+            create_harness_project(&config.harness_path, src, src, harness, toml, false).unwrap();
+            let pbt = PropertyBasedTesting::new(PBTConfig {
+                keep_on_failure,
+                ..config.clone()
+            });
+            let res = pbt.run(&checker);
+            assert_eq!(res.fail, [Path::from("f")]);
+            let harness_kept = std::path::Path::new(&config.harness_path).exists();
+            let output_kept = std::path::Path::new(&config.output_path).exists();
+            assert_eq!(harness_kept, keep_on_failure);
+            assert_eq!(output_kept, keep_on_failure);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn minimal_input_is_attributed_to_its_function() {
+        init_test_logger();
+        let dir = std::env::temp_dir().join(format!("veri-easy-pbt-out-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output_path = dir.join("pbt.tmp").to_string_lossy().into_owned();
+        let output = "running 2 tests
+test check_g ... ok
+test check_m___f ... FAILED
+
+failures:
+
+---- check_m___f stdout ----
+thread 'check_m___f' panicked at src/main.rs:12:5:
+Test failed: assertion failed: r1 == r2.
+minimal failing input: function_arg_struct = Args_m___f {
+    x: 3,
+}
+\tsuccesses: 0
+\tlocal rejects: 0
+\tglobal rejects: 0
+
+failures:
+    check_m___f
+";
+        std::fs::write(&output_path, output).unwrap();
+        let pbt = PropertyBasedTesting::new(PBTConfig {
+            output_path,
+            ..Default::default()
+        });
+        let res = pbt.analyze_pbt_output();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(res.ok, [Path::from("g")]);
+        assert_eq!(res.fail, [Path::from("m::f")]);
+        let expected = "minimal failing input: function_arg_struct = Args_m___f {\n    x: 3,\n}";
+        assert_eq!(
+            res.counterexamples,
+            [(Path::from("m::f"), expected.to_owned())]
+        );
+    }
+
+    #[test]
+    fn harness_uses_configured_regressions_path() {
+        init_test_logger();
+        let src = "pub fn f(x: u8) -> u8 { x }";
+        let checker = test_checker(src, src, vec![]);
+        let pbt = PropertyBasedTesting::new(PBTConfig {
+            regressions_path: Some("regressions/pbt.txt".to_owned()),
+            ..Default::default()
+        });
+        let harness = pbt.generate_harness(&checker).to_string();
+
+        // Relative paths are resolved against the working directory, not the harness project
+        let path = std::env::current_dir().unwrap().join("regressions/pbt.txt");
+        let persistence = format!("FileFailurePersistence :: Direct ({:?})", path);
+        assert!(harness.contains(&persistence));
+
+        let pbt = PropertyBasedTesting::new(PBTConfig::default());
+        let harness = pbt.generate_harness(&checker).to_string();
+        assert!(!harness.contains("failure_persistence"));
+    }
+
+    #[test]
+    fn output_divergence_is_flagged() {
+        init_test_logger();
+        let src1 = "pub fn f(x: u8) -> u8 { println!(\"{}\", x); x }";
+        let src2 = "pub fn f(x: u8) -> u8 { x }";
+        let checker = test_checker(src1, src2, vec![]);
+        let pbt = PropertyBasedTesting::new(PBTConfig {
+            capture_output: true,
+            ..Default::default()
+        });
+        let harness = pbt.generate_harness(&checker).to_string();
+        assert!(harness.contains("let (r1 , out1) = capture_output (||"));
+        assert!(harness.contains("if out1 != out2 { println ! (\"OUTPUT MISMATCH {}\" , \"f\")"));
+
+        // The mismatch printed by the harness fails the function
+        let dir = std::env::temp_dir().join(format!("veri-easy-pbt-cap-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output_path = dir.join("pbt.tmp").to_string_lossy().into_owned();
+        let output = "running 1 test\ntest check_f ... \nOUTPUT MISMATCH f\nFAILED\n";
+        std::fs::write(&output_path, output).unwrap();
+        let pbt = PropertyBasedTesting::new(PBTConfig {
+            output_path,
+            ..Default::default()
+        });
+        let res = pbt.analyze_pbt_output();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(res.fail, [Path::from("f")]);
+        let report = (Path::from("f"), "OUTPUT MISMATCH f".to_owned());
+        assert_eq!(res.counterexamples, [report]);
+    }
+
+    #[test]
+    fn async_calls_are_blocked_on() {
+        init_test_logger();
+        let src = "pub async fn f(x: u8) -> u8 { x }";
+        let checker = test_checker(src, src, vec![]);
+        let pbt = PropertyBasedTesting::new(PBTConfig::default());
+        let harness = pbt.generate_harness(&checker).to_string();
+
+        for module in ["mod1", "mod2"] {
+            let call = format!("futures :: executor :: block_on ({} :: f (", module);
+            assert!(harness.contains(&call));
+        }
+    }
+
+    #[test]
+    fn getterless_comparable_type_is_compared_whole() {
+        init_test_logger();
+        let src = "#[derive(Debug, PartialEq)]
+            pub struct Counter(u8);
+            impl Counter {
+                pub fn verieasy_new(a: u8) -> Self { Counter(a) }
+                pub fn bump(&mut self) { self.0 = self.0.wrapping_add(1); }
+            }";
+        let state_check = "format ! (\"{:?}\" , s1) != format ! (\"{:?}\" , s2)";
+        for (src, compared) in [(src, true), (&src.replace("PartialEq", "Clone"), false)] {
+            let checker = test_checker(src, src, vec![]);
+            let pbt = PropertyBasedTesting::new(PBTConfig::default());
+            let harness = pbt.generate_harness(&checker).to_string();
+            assert_eq!(harness.contains(state_check), compared);
+        }
+    }
+
+    #[test]
+    fn inlined_specs_are_used() {
+        init_test_logger();
+        let src = "pub fn f(x: u64) -> u64 { x }";
+        // Generated from a source 2 that defines the spec function `is_small` and requires it
+        let generated = "pub fn is_small(x: u64) -> bool { x < 10 }
+            pub fn verieasy_pre_f(x: u64) -> bool { is_small(x) }";
+        let mut s2 = Source::from_string("b.rs", src).unwrap();
+        s2.append_preconditions(generated);
+        assert!(s2.content.contains("pub fn verieasy_pre_f"));
+        let checker = Checker::new(
+            Source::from_string("a.rs", src).unwrap(),
+            s2,
+            vec![],
+            vec![Precondition::new(Path::from("f"), false)],
+            CheckerOptions::default(),
+        );
+        let pbt = PropertyBasedTesting::new(PBTConfig::default());
+        let harness = pbt.generate_harness(&checker).to_string();
+        assert!(harness.contains("prop_assume ! (verieasy_pre_f (function_arg_struct . x"));
+    }
+
+    #[test]
+    fn panic_messages_are_compared() {
+        init_test_logger();
+        let src1 = "pub fn f(x: u8) -> u8 { if x > 9 { panic!(\"too large\") } x }";
+        let src2 = "pub fn f(x: u8) -> u8 { if x > 9 { panic!(\"out of range\") } x }";
+        let checker = test_checker(src1, src2, vec![]);
+        let harness = |compare_panic_payload| {
+            let pbt = PropertyBasedTesting::new(PBTConfig {
+                compare_panic_payload,
+                ..Default::default()
+            });
+            pbt.generate_harness(&checker).to_string()
+        };
+        // Panics become their messages, so that `r1 != r2` if the messages differ
+        let compared = harness(true);
+        assert_eq!(compared.matches(". map_err (panic_message)").count(), 2);
+        assert!(compared.contains("fn panic_message (payload : Box < dyn std :: any :: Any"));
+        let ignored = harness(false);
+        assert_eq!(ignored.matches(". map_err (| _ | ())").count(), 2);
+        assert!(!ignored.contains("fn panic_message"));
+    }
+
+    #[test]
+    fn fixed_width_usize_is_cast_at_calls() {
+        init_test_logger();
+        let src = "pub fn f(n: usize, m: u8) -> usize { n }";
+        let harness = |fixed_width_usize| {
+            let checker = Checker::new(
+                Source::from_string("a.rs", src).unwrap(),
+                Source::from_string("b.rs", src).unwrap(),
+                vec![],
+                Vec::new(),
+                CheckerOptions {
+                    fixed_width_usize,
+                    ..Default::default()
+                },
+            );
+            let pbt = PropertyBasedTesting::new(PBTConfig::default());
+            pbt.generate_harness(&checker).to_string()
+        };
+        let fixed = harness(true);
+        assert!(fixed.contains("pub struct Argsf { pub n : u64 , pub m : u8 }"));
+        let call = "mod1 :: f (function_arg_struct . n as usize , function_arg_struct . m";
+        assert!(fixed.contains(call));
+        let native = harness(false);
+        assert!(native.contains("pub struct Argsf { pub n : usize , pub m : u8 }"));
+        assert!(!native.contains("as usize"));
+    }
+
+    #[test]
+    fn filter_rate_counters_are_generated() {
+        init_test_logger();
+        let src1 = "pub fn f(x: u64) -> u64 { x }";
+        let src2 = src1.to_owned() + "\npub fn verieasy_pre_f(x: u64) -> bool { x < 10 }";
+        let checker = Checker::new(
+            Source::from_string("a.rs", src1).unwrap(),
+            Source::from_string("b.rs", &src2).unwrap(),
+            vec![],
+            vec![Precondition::new(Path::from("f"), false)],
+            CheckerOptions::default(),
+        );
+        let pbt = PropertyBasedTesting::new(PBTConfig {
+            report_filter_rate: true,
+            ..Default::default()
+        });
+        let harness = pbt.generate_harness(&checker).to_string();
+
+        assert!(harness.contains("struct FilterCounter"));
+        assert!(harness.contains("static FILTER : FilterCounter = FilterCounter :: new ()"));
+        // A rejection makes proptest draw another input, an acceptance completes one
+        let reject = "FILTER . record (\"f\" , false , false , 10000u64) ; prop_assume ! (false)";
+        assert!(harness.contains(reject));
+        assert!(harness.contains("FILTER . record (\"f\" , true , true , 10000u64)"));
+    }
+
+    #[test]
+    fn bounded_arguments_get_range_strategies() {
+        init_test_logger();
+        let src = "pub fn alloc(size: usize, align: usize) -> usize { size }";
+        let mut precondition = Precondition::new(Path::from("alloc"), false);
+        precondition.bounds = vec![ArgBound {
+            arg: "size".to_owned(),
+            min: None,
+            max: Some(4095),
+        }];
+        let checker = Checker::new(
+            Source::from_string("a.rs", src).unwrap(),
+            Source::from_string("b.rs", src).unwrap(),
+            vec![],
+            vec![precondition],
+            CheckerOptions::default(),
+        );
+        let pbt = PropertyBasedTesting::new(PBTConfig::default());
+        let harness = pbt.generate_harness(&checker).to_string();
+
+        let size = "# [cfg_attr (test , proptest (strategy = \"0usize..4096usize\"))] pub size";
+        assert!(harness.contains(size));
+        assert!(harness.contains(", pub align : usize }"));
+        // The precondition is still checked
+        assert!(harness.contains("prop_assume ! (verieasy_pre_alloc"));
+    }
+
+    #[test]
+    fn widened_functions_are_compared_over_the_narrower_types() {
+        init_test_logger();
+        let src1 = "pub fn f(x: u16, y: bool) -> u16 { x }";
+        let checker = |src2: &str| {
+            let options = CheckerOptions {
+                width_tolerant: true,
+                ..Default::default()
+            };
+            Checker::new(
+                Source::from_string("a.rs", src1).unwrap(),
+                Source::from_string("b.rs", src2).unwrap(),
+                vec![],
+                Vec::new(),
+                options,
+            )
+        };
+        // Signedness must match
+        let signed = checker("pub fn f(x: i32, y: bool) -> i32 { x }");
+        assert!(signed.under_checking_funcs.is_empty());
+        let checker = checker("pub fn f(x: u32, y: bool) -> u32 { x }");
+        let pbt = PropertyBasedTesting::new(PBTConfig::default());
+        let harness = pbt.generate_harness(&checker).to_string();
+
+        // Arguments are drawn from the narrower type, the narrower result is widened
+        assert!(harness.contains("pub struct Argsf { pub x : u16 , pub y : bool }"));
+        let call1 = "(mod1 :: f (function_arg_struct . x . clone () as u16 ,";
+        assert!(harness.contains(call1));
+        assert!(harness.contains(". y . clone ())) as u32"));
+        let call2 = "mod2 :: f (function_arg_struct . x . clone () as u32 ,";
+        assert!(harness.contains(call2));
+    }
+}
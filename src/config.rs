@@ -1,30 +1,151 @@
 //! Configuration Veri-easy workflow and components.
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
 
-use crate::{check::Component, components::*, log, log::LogLevel};
+use crate::{
+    check::Component,
+    log,
+    log::{LogFormat, LogLevel},
+    registry::get_registry,
+};
 
 /// Veri-easy Functional Equivalence Checker.
 #[derive(Debug, Parser)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct VerieasyConfig {
-    /// Path to the workflow configuration file.
-    #[clap(short, long, default_value = "workflow.toml")]
-    pub config: String,
+    /// Path to the workflow configuration file. Several comma-separated files are run one after
+    /// another on the same sources, followed by a combined summary.
+    #[clap(short, long, default_value = "workflow.toml", value_delimiter = ',')]
+    pub config: Vec<String>,
     /// Log level.
     #[clap(short, long, default_value = "normal")]
     #[arg(value_enum)]
     pub log: LogLevel,
-    /// File from which to collect preconditions.
+    /// Log format: human-readable `text`, or `json` with one object per line.
+    #[clap(long, default_value = "text")]
+    #[arg(value_enum)]
+    pub log_format: LogFormat,
+    /// Prefix text log lines with the wall-clock time and the elapsed time since start.
+    #[clap(long, default_value_t = false)]
+    pub log_timestamps: bool,
+    /// Also write all log messages, regardless of the log level, to this file.
+    #[clap(long)]
+    pub log_file: Option<String>,
+    /// File from which to collect preconditions, or `self` to collect them from source file 2.
     #[clap(short = 'p', long)]
     pub preconditions: Option<String>,
-    /// Strict mode: exit on first error.
+    /// Directory of hand-written precondition checkers. A file `verieasy_pre_<name>.rs` replaces
+    /// the checker translated for that function.
+    #[clap(long, requires = "preconditions")]
+    pub precondition_overrides: Option<String>,
+    /// Enforce the `recommends` clauses of the Verus specs like `requires` clauses when
+    /// filtering inputs.
+    #[clap(long, default_value_t = false, requires = "preconditions")]
+    pub enforce_recommends: bool,
+    /// Strict mode: exit on first failure of a testing component or function refuted by a formal
+    /// component with a counterexample.
     #[clap(short = 's', long, default_value_t = false)]
     pub strict: bool,
-    /// Source file 1, usually the original source.
-    pub file1: String,
-    /// Source file 2, usually the Verus refactored source.
-    pub file2: String,
+    /// Strict formal mode: exit when a formal component leaves any function undetermined.
+    #[clap(long, default_value_t = false)]
+    pub strict_formal: bool,
+    /// Cache file recording functions verified by formal components. Unchanged functions found
+    /// in the cache are not checked again.
+    #[clap(long)]
+    pub cache: Option<String>,
+    /// Replay a saved fuzzing input with the differential fuzzing harness instead of fuzzing.
+    #[clap(long)]
+    pub replay: Option<String>,
+    /// Seed of the random generators of property-based testing and of the initial fuzzing
+    /// inputs, so that a run can be reproduced.
+    #[clap(long)]
+    pub seed: Option<u64>,
+    /// Baseline version of source file 2. Only functions whose body or signature changed since the
+    /// baseline, and their callers, are checked.
+    #[clap(long)]
+    pub changed_since: Option<String>,
+    /// Check only the first N matched functions, in the order of source file 1. Constructors and
+    /// getters are always kept.
+    #[clap(long, value_name = "N")]
+    pub max_functions: Option<usize>,
+    /// Print the matched common functions, constructors, getters and unique functions, then exit.
+    #[clap(long, default_value_t = false)]
+    pub list_functions: bool,
+    /// Override `use_preconditions` of every component: `on`, `off`, or `default` to keep the
+    /// workflow configuration.
+    #[clap(long, default_value = "default")]
+    #[arg(value_enum)]
+    pub use_preconditions: PrecondOverride,
+    /// Copy the generated harness source of each component into `<DIR>/<component>/`,
+    /// regardless of the cleanup settings.
+    #[clap(long)]
+    pub dump_harness: Option<String>,
+    /// Validate the workflow configuration, then exit.
+    #[clap(long, default_value_t = false)]
+    pub validate_config: bool,
+    /// Run only the formal components of the workflow.
+    #[clap(long, default_value_t = false, conflicts_with = "testing_only")]
+    pub formal_only: bool,
+    /// Run only the testing components of the workflow.
+    #[clap(long, default_value_t = false)]
+    pub testing_only: bool,
+    /// Source file 1, usually the original source. Not needed with `--validate-config`.
+    #[arg(required_unless_present = "validate_config")]
+    pub file1: Option<String>,
+    /// Source file 2, usually the Verus refactored source. Not needed with `--validate-config`.
+    #[arg(required_unless_present = "validate_config")]
+    pub file2: Option<String>,
+    /// Run a tool instead of checking equivalence.
+    #[command(subcommand)]
+    pub command: Option<VerieasyCommand>,
+}
+
+impl VerieasyConfig {
+    /// Keep only the formal components with `--formal-only`, or only the testing ones with
+    /// `--testing-only`.
+    pub fn filter_components(&self, components: &mut Vec<Box<dyn Component>>) {
+        if !self.formal_only && !self.testing_only {
+            return;
+        }
+        components.retain(|component| {
+            let keep = component.is_formal() == self.formal_only;
+            if !keep {
+                log!(Normal, Info, "Skipping component `{}`", component.name());
+            }
+            keep
+        });
+    }
+}
+
+/// Standalone tools of Veri-easy.
+#[derive(Debug, Subcommand)]
+pub enum VerieasyCommand {
+    /// Translate the preconditions and spec functions of a Verus file into executable Rust, and
+    /// list the translated and dropped items.
+    TranslatePreconds {
+        /// Verus source file.
+        verus_file: String,
+        /// Output file of the generated code. Printed to stdout if not given.
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+}
+
+/// Global override of the `use_preconditions` component settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PrecondOverride {
+    /// Use preconditions in all components.
+    On,
+    /// Use preconditions in no component.
+    Off,
+    /// Keep the settings from the workflow configuration.
+    Default,
 }
 
 /// Configuration for Kani component.
@@ -43,10 +164,31 @@ pub struct KaniConfig {
     pub keep_harness: bool,
     /// Keep Kani output file.
     pub keep_output: bool,
+    /// Keep harness project and output file when any function fails, regardless of
+    /// `keep_harness` and `keep_output`.
+    pub keep_on_failure: bool,
     /// Use preconditions. If true, preconditions will be added to the harness through `kani::assume`.
     pub use_preconditions: bool,
     /// Loop unwind bound. If set, `#[kani(unwind(N))]` will be added to functions.
     pub loop_unwind: Option<u32>,
+    /// Maximum loop unwind bound. If set together with `loop_unwind`, harnesses failing only on
+    /// unwinding assertions are verified again with doubled bounds up to this cap.
+    pub max_unwind: Option<u32>,
+    /// Wrap calls in `catch_unwind` and compare `Result<_, ()>`, matching the panic semantics of
+    /// the DF and PBT harnesses. Kani does not model unwinding, so a reachable panic still fails
+    /// the harness and leaves the function undetermined for the testing components to decide.
+    pub catch_panic: bool,
+    /// Number of harnesses verified concurrently. If greater than 1, each harness is verified by
+    /// its own `cargo kani --harness` invocation.
+    pub jobs: usize,
+    /// Functions replaced by stubs during verification, as `[original, stub]` paths relative to
+    /// the harness root (e.g. `["mod1::alloc_page", "stub_alloc_page"]`). Each proof harness gets
+    /// a `#[kani::stub(original, stub)]` attribute.
+    pub stubs: Vec<(String, String)>,
+    /// Rust file with the stub definitions, included in the harness.
+    pub stub_file: Option<String>,
+    /// Number of retries of a Kani run that fails transiently. Compilation errors are not retried.
+    pub retries: u32,
 }
 
 impl Default for KaniConfig {
@@ -58,8 +200,15 @@ impl Default for KaniConfig {
             gen_harness: true,
             keep_harness: false,
             keep_output: false,
+            keep_on_failure: false,
             use_preconditions: true,
             loop_unwind: None,
+            max_unwind: None,
+            catch_panic: false,
+            jobs: 1,
+            stubs: Vec::new(),
+            stub_file: None,
+            retries: 0,
         }
     }
 }
@@ -74,6 +223,8 @@ pub struct Alive2Config {
     pub output_path: String,
     /// Keep Alive2 output file.
     pub keep_output: bool,
+    /// Number of retries of `rustc` and `alive-tv` invocations that fail transiently.
+    pub retries: u32,
 }
 
 impl Default for Alive2Config {
@@ -82,10 +233,57 @@ impl Default for Alive2Config {
             alive2_path: "alive2-tv".to_string(),
             output_path: "alive2.tmp".to_string(),
             keep_output: false,
+            retries: 0,
         }
     }
 }
 
+/// Configuration for external formal verifier component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalFormalConfig {
+    /// Path to the external verifier binary.
+    pub verifier_path: String,
+    /// Arguments passed to the verifier. `{file1}` and `{file2}` are replaced with the source paths.
+    pub args: Vec<String>,
+    /// Verifier output path.
+    pub output_path: String,
+    /// Regex matching a verified function in the output. The first capture group is the function name.
+    pub success_regex: String,
+    /// Regex matching a function that could not be verified. The first capture group is the function name.
+    pub fail_regex: String,
+    /// Keep verifier output file.
+    pub keep_output: bool,
+    /// Number of retries of a verifier run that fails transiently.
+    pub retries: u32,
+}
+
+impl Default for ExternalFormalConfig {
+    fn default() -> Self {
+        ExternalFormalConfig {
+            verifier_path: "verifier".to_string(),
+            args: vec!["{file1}".to_string(), "{file2}".to_string()],
+            output_path: "external.tmp".to_string(),
+            success_regex: r"^SUCCESS: ([0-9a-zA-Z_:]+)$".to_string(),
+            fail_regex: r"^FAILURE: ([0-9a-zA-Z_:]+)$".to_string(),
+            keep_output: false,
+            retries: 0,
+        }
+    }
+}
+
+/// Serialization format used to decode fuzzer input into argument structs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// `postcard` crate.
+    Postcard,
+    /// `bincode` crate (2.x, serde API).
+    Bincode,
+    /// CBOR through the `ciborium` crate.
+    Cbor,
+}
+
 /// Configuration for Differential Fuzzing component.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -106,6 +304,9 @@ pub struct DiffFuzzConfig {
     pub keep_harness: bool,
     /// Keep fuzzing output file.
     pub keep_output: bool,
+    /// Keep harness project and output file when any function fails, regardless of
+    /// `keep_harness` and `keep_output`.
+    pub keep_on_failure: bool,
     /// Use preconditions.
     pub use_preconditions: bool,
     /// Catch panic unwind. If false, the fuzzer may crash on invalid inputs. You should always enable 
@@ -117,6 +318,38 @@ pub struct DiffFuzzConfig {
     /// Execute custom command before fuzzing. This can be used to modify the generated harness,
     /// e.g., replacing the `main.rs` file with a custom one.
     pub pre_fuzz_cmd: Option<String>,
+    /// Replay a saved input file against the harness instead of fuzzing.
+    pub replay_input: Option<String>,
+    /// Serialization format of fuzzer input: `postcard`, `bincode` or `cbor`.
+    pub serialization: Format,
+    /// Panic on mismatch so that AFL records the input as a crash. If false, mismatches are only
+    /// written to the harness log and fuzzing continues; `harness_log` is then always enabled.
+    pub panic_on_mismatch: bool,
+    /// Directory of a reusable corpus. If set, fuzzing is seeded from it and the queue is
+    /// minimized into it with `cargo afl cmin` after each run.
+    pub corpus_path: Option<String>,
+    /// Capture stdout and stderr written by each call and report a mismatch if the captured
+    /// output differs between the two implementations.
+    pub capture_output: bool,
+    /// Pass the fuzzer a dictionary of boundary values (0, 1, MIN, MAX, powers of two and
+    /// integer constants of the sources) of the argument integer types. Postcard format only.
+    pub dictionary: bool,
+    /// Number of retries of the AFL build and fuzzing commands when they fail transiently.
+    pub retries: u32,
+    /// Seed of the generated initial inputs, also passed to `afl fuzz -s`. Random if unset.
+    pub seed: Option<u64>,
+    /// Compare the methods of a type in call sequences: after constructing both receivers, each
+    /// input applies a sequence of method calls decoded from it to both, and stops at the first
+    /// divergence of a return value or of the state. Methods taking `self` by value are still
+    /// compared one call at a time.
+    pub stateful: bool,
+    /// Compare the messages of caught panics, so that two calls panicking with different
+    /// messages are a mismatch. Requires `catch_panic`.
+    pub compare_panic_payload: bool,
+    /// Names of `&mut self` methods used to precondition the receivers: before a compared method
+    /// call, each input applies a sequence of these calls decoded from it to both receivers, e.g.
+    /// `remove` to fill an allocator up to exhaustion. Their results are not compared.
+    pub setup_methods: Vec<String>,
 }
 
 impl Default for DiffFuzzConfig {
@@ -130,16 +363,28 @@ impl Default for DiffFuzzConfig {
             gen_harness: true,
             keep_harness: false,
             keep_output: false,
+            keep_on_failure: false,
             use_preconditions: true,
             catch_panic: true,
             harness_log: true,
             pre_fuzz_cmd: None,
+            replay_input: None,
+            serialization: Format::Postcard,
+            panic_on_mismatch: true,
+            corpus_path: None,
+            capture_output: false,
+            dictionary: true,
+            retries: 0,
+            seed: None,
+            stateful: false,
+            compare_panic_payload: false,
+            setup_methods: Vec::new(),
         }
     }
 }
 
 /// Configuration for Property-Based Testing component.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct PBTConfig {
     /// PBT harness path.
@@ -156,9 +401,33 @@ pub struct PBTConfig {
     pub keep_harness: bool,
     /// Keep PBT output file.
     pub keep_output: bool,
+    /// Keep harness project and output file when any function fails, regardless of
+    /// `keep_harness` and `keep_output`.
+    pub keep_on_failure: bool,
     /// Use preconditions. If true, preconditions will be added to the harness by filtering
     /// generated test cases.
     pub use_preconditions: bool,
+    /// Proptest regression file. If set, failing seeds are persisted there and retried on later
+    /// runs, even if the harness project is removed.
+    pub regressions_path: Option<String>,
+    /// Capture stdout and stderr written by each call and report a mismatch if the captured
+    /// output differs between the two implementations. Tests then run on a single thread.
+    pub capture_output: bool,
+    /// Number of retries of `cargo test` when it fails transiently. Failed tests are not retried.
+    pub retries: u32,
+    /// Fixed proptest RNG seed. Set `#![proptest_config(ProptestConfig { rng_seed: .. })]` in
+    /// harness. Random if unset.
+    pub seed: Option<u64>,
+    /// Compare the messages of panics: two calls panicking with different messages are a
+    /// mismatch, instead of any panic being equal to any other.
+    pub compare_panic_payload: bool,
+    /// Count the inputs accepted and rejected by preconditions and guards of each test, and log
+    /// the acceptance rate of the tests that pass.
+    pub report_filter_rate: bool,
+    /// Generate integer arguments within the range implied by precondition clauses such as
+    /// `size < 4096`, instead of generating any value and rejecting most of them. Only used
+    /// together with `use_preconditions`.
+    pub precondition_strategies: bool,
 }
 
 impl Default for PBTConfig {
@@ -171,7 +440,57 @@ impl Default for PBTConfig {
             gen_harness: true,
             keep_harness: false,
             keep_output: false,
+            keep_on_failure: false,
+            use_preconditions: true,
+            regressions_path: None,
+            capture_output: false,
+            retries: 0,
+            seed: None,
+            compare_panic_payload: false,
+            report_filter_rate: false,
+            precondition_strategies: true,
+        }
+    }
+}
+
+/// Configuration for Exhaustive component.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExhaustiveConfig {
+    /// Exhaustive harness path.
+    pub harness_path: String,
+    /// Exhaustive output path.
+    pub output_path: String,
+    /// Maximum number of inputs of a function. Functions with larger input domains are left to
+    /// the other components.
+    pub max_cases: u64,
+    /// Generate new harness.
+    pub gen_harness: bool,
+    /// Keep exhaustive harness project.
+    pub keep_harness: bool,
+    /// Keep exhaustive output file.
+    pub keep_output: bool,
+    /// Keep harness project and output file when any function fails, regardless of
+    /// `keep_harness` and `keep_output`.
+    pub keep_on_failure: bool,
+    /// Use preconditions. If true, inputs violating the precondition are skipped.
+    pub use_preconditions: bool,
+    /// Number of retries of the harness run when it fails transiently.
+    pub retries: u32,
+}
+
+impl Default for ExhaustiveConfig {
+    fn default() -> Self {
+        ExhaustiveConfig {
+            harness_path: "exhaustive_harness".to_string(),
+            output_path: "exhaustive.tmp".to_string(),
+            max_cases: 1 << 20,
+            gen_harness: true,
+            keep_harness: false,
+            keep_output: false,
+            keep_on_failure: false,
             use_preconditions: true,
+            retries: 0,
         }
     }
 }
@@ -189,6 +508,31 @@ pub struct WorkflowConfig {
     pub diff_fuzz: Option<DiffFuzzConfig>,
     /// Property-Based Testing component configuration.
     pub pbt: Option<PBTConfig>,
+    /// External formal verifier component configuration.
+    pub external: Option<ExternalFormalConfig>,
+    /// Exhaustive enumeration component configuration.
+    pub exhaustive: Option<ExhaustiveConfig>,
+    /// Skip functions failed by a testing component in all later formal components.
+    #[serde(default)]
+    pub fail_fast_testing: bool,
+    /// Additional symbols treated as nondeterministic, e.g. `my_crate::clock::now`. Functions
+    /// referring to them are not checked.
+    #[serde(default)]
+    pub nondeterministic: Vec<String>,
+    /// Symbols not treated as nondeterministic, overriding the defaults and `nondeterministic`.
+    #[serde(default)]
+    pub deterministic: Vec<String>,
+    /// Generate `usize` and `isize` arguments as `u64` and `i64` in harnesses, so that inputs
+    /// cover the same range whatever the pointer width of the host.
+    #[serde(default)]
+    pub fixed_width_usize: bool,
+    /// Also compare free functions whose signatures differ only in integer widths, e.g.
+    /// `fn f(x: u16) -> u16` and `fn f(x: u32) -> u32`, over the narrower types.
+    #[serde(default)]
+    pub width_tolerant: bool,
+    /// Configuration sections of custom components, keyed by component name.
+    #[serde(flatten)]
+    pub sections: toml::Table,
 }
 
 impl WorkflowConfig {
@@ -232,17 +576,139 @@ impl WorkflowConfig {
                         config.alive2 = Some(Alive2Config::default());
                     }
                 }
-                other => {
+                "external" => {
+                    if config.external.is_none() {
+                        log!(Brief, Warning, &msg("External"));
+                        config.external = Some(ExternalFormalConfig::default());
+                    }
+                }
+                "exhaustive" => {
+                    if config.exhaustive.is_none() {
+                        log!(Brief, Warning, &msg("Exhaustive"));
+                        config.exhaustive = Some(ExhaustiveConfig::default());
+                    }
+                }
+                // Custom components, or unknown components reported by `validate`
+                _ => continue,
+            }
+        }
+        if config.fail_fast_testing && !config.has_testing_before_formal() {
+            log!(
+                Brief,
+                Warning,
+                "`fail_fast_testing` is enabled but no testing component runs before a formal component."
+            );
+        }
+        Ok(config)
+    }
+
+    /// Whether any testing component is ordered before a formal component.
+    fn has_testing_before_formal(&self) -> bool {
+        match self
+            .components
+            .iter()
+            .position(|comp| is_testing_component(comp))
+        {
+            Some(first_testing) => self.components[first_testing..]
+                .iter()
+                .any(|comp| is_formal_component(comp)),
+            None => false,
+        }
+    }
+
+    /// Validate the workflow.
+    ///
+    /// Unknown components are an error in strict mode, and skipped with a warning otherwise.
+    /// Duplicate components and components that use preconditions without a preconditions file
+    /// only produce warnings.
+    pub fn validate(&self, strict: bool, has_preconditions: bool) -> anyhow::Result<()> {
+        for component in &self.components {
+            let name = normalize(&component.to_lowercase()).to_owned();
+            if is_known_component(&name) {
+                continue;
+            }
+            if strict {
+                return Err(anyhow::anyhow!("Unknown component `{}` in workflow", name));
+            }
+            log!(
+                Brief,
+                Warning,
+                "Unknown component `{}` in configuration. Ignoring.",
+                component
+            );
+        }
+        let known = self.known_components();
+        if known.iter().any(|comp| comp == "external") {
+            let config = self.external.clone().unwrap_or_default();
+            for (field, re) in [
+                ("success_regex", &config.success_regex),
+                ("fail_regex", &config.fail_regex),
+            ] {
+                let re = regex::Regex::new(re)
+                    .map_err(|e| anyhow::anyhow!("Invalid `{}` of `external`: {}", field, e))?;
+                if re.captures_len() < 2 {
+                    return Err(anyhow::anyhow!(
+                        "`{}` of `external` has no capture group for the function name",
+                        field
+                    ));
+                }
+            }
+        }
+        for name in self.duplicate_components() {
+            log!(
+                Brief,
+                Warning,
+                "Component `{}` appears more than once in workflow and will run again.",
+                name
+            );
+        }
+
+        if !has_preconditions {
+            let uses_preconditions = [
+                ("kani", self.kani.as_ref().map(|c| c.use_preconditions)),
+                (
+                    "exhaustive",
+                    self.exhaustive.as_ref().map(|c| c.use_preconditions),
+                ),
+                ("pbt", self.pbt.as_ref().map(|c| c.use_preconditions)),
+                (
+                    "difffuzz",
+                    self.diff_fuzz.as_ref().map(|c| c.use_preconditions),
+                ),
+            ];
+            for (name, used) in uses_preconditions {
+                if used == Some(true) && known.iter().any(|comp| comp == name) {
                     log!(
-                        Brief,
+                        Normal,
                         Warning,
-                        "Unknown component `{}` in configuration. Ignoring.",
-                        other
+                        "Component `{}` uses preconditions but no preconditions file is given.",
+                        name
                     );
                 }
             }
         }
-        Ok(config)
+        Ok(())
+    }
+
+    /// Known components of the workflow in order, by normalized name.
+    fn known_components(&self) -> Vec<String> {
+        self.components
+            .iter()
+            .map(|component| normalize(&component.to_lowercase()).to_owned())
+            .filter(|name| is_known_component(name))
+            .collect()
+    }
+
+    /// Known components appearing more than once in the workflow, by normalized name.
+    fn duplicate_components(&self) -> Vec<String> {
+        let known = self.known_components();
+        let mut duplicates: Vec<String> = Vec::new();
+        for (i, name) in known.iter().enumerate() {
+            if known[..i].contains(name) && !duplicates.contains(name) {
+                duplicates.push(name.clone());
+            }
+        }
+        duplicates
     }
 
     /// Log the loaded workflow configuration.
@@ -253,6 +719,9 @@ impl WorkflowConfig {
             "Workflow: {}",
             self.components.join(" -> ")
         );
+        if self.fail_fast_testing {
+            log!(Brief, Info, "Fail-fast testing enabled");
+        }
         if let Some(kani_cfg) = &self.kani {
             log!(Normal, Info, "Kani Config: {:?}", kani_cfg);
         }
@@ -270,30 +739,189 @@ impl WorkflowConfig {
         if let Some(pbt_cfg) = &self.pbt {
             log!(Normal, Info, "Property-Based Testing Config: {:?}", pbt_cfg);
         }
+        if let Some(external_cfg) = &self.external {
+            log!(Normal, Info, "External Formal Config: {:?}", external_cfg);
+        }
+        if let Some(exhaustive_cfg) = &self.exhaustive {
+            log!(Normal, Info, "Exhaustive Config: {:?}", exhaustive_cfg);
+        }
     }
 
     /// Construct workflow components based on the configuration.
-    pub fn construct_workflow(&self) -> Vec<Box<dyn Component>> {
+    ///
+    /// Every component is created through the registry from its configuration section, the
+    /// parsed configuration for built-in components. Unless `precond_override` is `Default`, it
+    /// replaces `use_preconditions` of every section that has one. Fails if a section is invalid.
+    pub fn construct_workflow(
+        &self,
+        precond_override: PrecondOverride,
+    ) -> anyhow::Result<Vec<Box<dyn Component>>> {
+        let registry = get_registry();
         let mut components: Vec<Box<dyn Component>> = Vec::new();
         for component in &self.components {
-            match component.to_lowercase().as_str() {
-                "identical" => components.push(Box::new(Identical)),
-                "kani" => components.push(Box::new(Kani::new(self.kani.to_owned().unwrap()))),
-                "pbt" => components.push(Box::new(PropertyBasedTesting::new(
-                    self.pbt.to_owned().unwrap(),
-                ))),
-                "difffuzz" | "diff-fuzz" | "diff_fuzz" => components.push(Box::new(
-                    DifferentialFuzzing::new(self.diff_fuzz.to_owned().unwrap()),
-                )),
-                "alive2" => components.push(Box::new(Alive2::new(self.alive2.to_owned().unwrap()))),
-                other => log!(
-                    Brief,
-                    Warning,
-                    "Unknown component `{}` in configuration. Ignoring.",
-                    other
-                ),
+            let mut section = self.section(component)?;
+            if let Some(table) = section.as_table_mut()
+                && let Some(toml::Value::Boolean(configured)) = table.get("use_preconditions")
+            {
+                let used = match precond_override {
+                    PrecondOverride::On => true,
+                    PrecondOverride::Off => false,
+                    PrecondOverride::Default => *configured,
+                };
+                table.insert("use_preconditions".to_owned(), toml::Value::Boolean(used));
+            }
+            // Unknown components are skipped, `validate` reports them
+            if let Some(created) = registry.create(component, &section) {
+                components.push(created.map_err(|e| {
+                    anyhow::anyhow!("Invalid configuration of `{}`: {}", component, e)
+                })?);
             }
         }
-        components
+        Ok(components)
+    }
+
+    /// Configuration section of `component`, the default configuration for a built-in component
+    /// and an empty table for a custom component without one.
+    fn section(&self, component: &str) -> anyhow::Result<toml::Value> {
+        let lower = component.to_lowercase();
+        let section = match normalize(&lower) {
+            "kani" => toml::Value::try_from(self.kani.clone().unwrap_or_default()),
+            "pbt" => toml::Value::try_from(self.pbt.clone().unwrap_or_default()),
+            "difffuzz" => toml::Value::try_from(self.diff_fuzz.clone().unwrap_or_default()),
+            "alive2" => toml::Value::try_from(self.alive2.clone().unwrap_or_default()),
+            "external" => toml::Value::try_from(self.external.clone().unwrap_or_default()),
+            "exhaustive" => toml::Value::try_from(self.exhaustive.clone().unwrap_or_default()),
+            other => Ok(self
+                .sections
+                .get(other)
+                .cloned()
+                .unwrap_or_else(|| toml::Value::Table(toml::Table::new()))),
+        };
+        section.map_err(|e| anyhow::anyhow!("Invalid configuration of `{}`: {}", component, e))
+    }
+}
+
+/// Whether `name` is a formal component.
+fn is_formal_component(name: &str) -> bool {
+    matches!(
+        name.to_lowercase().as_str(),
+        "identical" | "kani" | "alive2" | "external" | "exhaustive"
+    )
+}
+
+/// Whether `name` is a testing component.
+fn is_testing_component(name: &str) -> bool {
+    matches!(
+        name.to_lowercase().as_str(),
+        "pbt" | "difffuzz" | "diff-fuzz" | "diff_fuzz"
+    )
+}
+
+/// Whether `name` is a built-in or registered custom component.
+fn is_known_component(name: &str) -> bool {
+    is_formal_component(name) || is_testing_component(name) || get_registry().contains(name)
+}
+
+/// Normalize alternative spellings of component names.
+fn normalize(name: &str) -> &str {
+    match name {
+        "diff-fuzz" | "diff_fuzz" => "difffuzz",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::log::init_test_logger;
+
+    /// Workflow configuration running `components` in order.
+    fn workflow(components: &[&str]) -> WorkflowConfig {
+        let components = components.iter().map(|comp| format!("{:?}", comp));
+        let list = components.collect::<Vec<_>>().join(", ");
+        let toml = format!("components = [{}]", list);
+        toml::from_str(&toml).unwrap()
+    }
+
+    #[test]
+    fn testing_must_precede_formal_for_fail_fast() {
+        assert!(workflow(&["pbt", "kani"]).has_testing_before_formal());
+        assert!(workflow(&["identical", "diff-fuzz", "alive2"]).has_testing_before_formal());
+        assert!(!workflow(&["kani", "pbt"]).has_testing_before_formal());
+        assert!(!workflow(&["kani", "alive2"]).has_testing_before_formal());
+        assert!(!workflow(&["pbt", "difffuzz"]).has_testing_before_formal());
+    }
+
+    #[test]
+    fn unknown_component_is_an_error_in_strict_mode() {
+        init_test_logger();
+        let config = workflow(&["identical", "z3"]);
+        let err = config.validate(true, false).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown component `z3` in workflow");
+        assert!(config.validate(false, false).is_ok());
+    }
+
+    #[test]
+    fn validation_needs_no_source_files() {
+        let config = VerieasyConfig::try_parse_from(["veri-easy", "--validate-config"]).unwrap();
+        assert!(config.validate_config && config.file1.is_none());
+        assert!(VerieasyConfig::try_parse_from(["veri-easy"]).is_err());
+        assert!(VerieasyConfig::try_parse_from(["veri-easy", "a.rs"]).is_err());
+    }
+
+    #[test]
+    fn duplicate_components_are_reported() {
+        init_test_logger();
+        let config = workflow(&["kani", "z3", "Kani", "diff-fuzz", "z3", "difffuzz"]);
+        assert_eq!(config.duplicate_components(), ["kani", "difffuzz"]);
+        // Duplicates only produce a warning, unknown components are skipped
+        assert!(config.validate(false, false).is_ok());
+    }
+
+    #[test]
+    fn external_regex_needs_a_capture_group() {
+        init_test_logger();
+        let mut config = workflow(&["external"]);
+        assert!(config.validate(false, false).is_ok());
+        config.external = Some(ExternalFormalConfig {
+            success_regex: "^SUCCESS".to_owned(),
+            ..Default::default()
+        });
+        let err = config.validate(false, false).unwrap_err();
+        assert!(err.to_string().contains("`success_regex`"));
+    }
+
+    #[test]
+    fn override_disables_kani_preconditions() {
+        init_test_logger();
+        let config = workflow(&["kani"]);
+        let key = |precond_override| {
+            let components = config.construct_workflow(precond_override).unwrap();
+            components[0].cache_key()
+        };
+        assert_eq!(key(PrecondOverride::Default), key(PrecondOverride::Default));
+        // Kani uses preconditions by default
+        assert_eq!(key(PrecondOverride::Default), key(PrecondOverride::On));
+        assert_ne!(key(PrecondOverride::Default), key(PrecondOverride::Off));
+    }
+
+    #[test]
+    fn components_are_filtered_by_kind() {
+        init_test_logger();
+        let workflow = workflow(&["identical", "kani", "pbt", "diff-fuzz", "alive2"]);
+        let names = |flag: &str| {
+            let config = VerieasyConfig::parse_from(["veri-easy", flag, "a.rs", "b.rs"]);
+            let mut components = workflow
+                .construct_workflow(PrecondOverride::Default)
+                .unwrap();
+            config.filter_components(&mut components);
+            components
+                .iter()
+                .map(|component| component.name().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names("--formal-only"), ["Identical", "Kani", "Alive2"]);
+        let testing = ["Property-Based Testing", "Differential Fuzzing"];
+        assert_eq!(names("--testing-only"), testing);
     }
 }
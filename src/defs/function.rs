@@ -28,6 +28,105 @@ impl PartialEq for Signature {
     }
 }
 
+impl Signature {
+    /// If the signatures only differ in the widths of integer argument and return types of the
+    /// same signedness, e.g. `fn f(x: u16) -> u16` and `fn f(x: u32) -> u32`.
+    pub fn eq_up_to_int_width(&self, other: &Self) -> bool {
+        let same_or_widened = |a: &syn::Type, b: &syn::Type| {
+            type_eq(a, b)
+                || matches!((int_width(a), int_width(b)), (Some(a), Some(b)) if a.0 == b.0)
+        };
+        self != other
+            && self.0.ident == other.0.ident
+            && self.0.inputs.len() == other.0.inputs.len()
+            && self
+                .0
+                .inputs
+                .iter()
+                .zip(other.0.inputs.iter())
+                .all(|(a, b)| match (a, b) {
+                    (syn::FnArg::Typed(a), syn::FnArg::Typed(b)) => same_or_widened(&a.ty, &b.ty),
+                    _ => false,
+                })
+            && match (&self.0.output, &other.0.output) {
+                (syn::ReturnType::Default, syn::ReturnType::Default) => true,
+                (syn::ReturnType::Type(_, a), syn::ReturnType::Type(_, b)) => same_or_widened(a, b),
+                _ => false,
+            }
+    }
+
+    /// Describe the first difference from `other`, or `None` if the signatures are equal.
+    pub fn mismatch(&self, other: &Self) -> Option<String> {
+        let describe = |ty: &syn::Type| match type_to_string(ty, "::").as_str() {
+            "unsupported" => quote::quote!(#ty).to_string(),
+            name => name.to_owned(),
+        };
+        if self.0.inputs.len() != other.0.inputs.len() {
+            return Some(format!(
+                "{} arguments vs {} arguments",
+                self.0.inputs.len(),
+                other.0.inputs.len()
+            ));
+        }
+        for (i, (a, b)) in self.0.inputs.iter().zip(other.0.inputs.iter()).enumerate() {
+            match (a, b) {
+                (syn::FnArg::Receiver(_), syn::FnArg::Receiver(_)) => (),
+                (syn::FnArg::Typed(a), syn::FnArg::Typed(b)) => {
+                    if !type_eq(&a.ty, &b.ty) {
+                        let pat = &a.pat;
+                        return Some(format!(
+                            "argument {} `{}` has type `{}` vs `{}`",
+                            i,
+                            quote::quote!(#pat),
+                            describe(&a.ty),
+                            describe(&b.ty)
+                        ));
+                    }
+                }
+                _ => return Some(format!("argument {} is `self` in only one source", i)),
+            }
+        }
+        match (&self.0.output, &other.0.output) {
+            (syn::ReturnType::Default, syn::ReturnType::Default) => None,
+            (syn::ReturnType::Type(_, a), syn::ReturnType::Type(_, b)) => (!type_eq(a, b))
+                .then(|| format!("return type `{}` vs `{}`", describe(a), describe(b))),
+            (syn::ReturnType::Type(_, a), _) => {
+                Some(format!("return type `{}` vs `()`", describe(a)))
+            }
+            (_, syn::ReturnType::Type(_, b)) => {
+                Some(format!("return type `()` vs `{}`", describe(b)))
+            }
+        }
+    }
+}
+
+/// Role of a method given by an attribute instead of its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FunctionRole {
+    /// Tagged `#[verieasy_constructor]`, used like `verieasy_new`.
+    Constructor,
+    /// Tagged `#[verieasy_getter]`, used like `verieasy_get`.
+    Getter,
+}
+
+impl FunctionRole {
+    /// Attribute names tagging functions with a role.
+    pub const ATTRIBUTES: [&str; 2] = ["verieasy_constructor", "verieasy_getter"];
+
+    /// Role given by the attributes of a function, if any.
+    pub fn from_attrs(attrs: &[syn::Attribute]) -> Option<Self> {
+        attrs.iter().find_map(|attr| {
+            if attr.path().is_ident("verieasy_constructor") {
+                Some(Self::Constructor)
+            } else if attr.path().is_ident("verieasy_getter") {
+                Some(Self::Getter)
+            } else {
+                None
+            }
+        })
+    }
+}
+
 /// Function metadata, including name, signature, impl type and trait (if any).
 #[derive(Clone)]
 pub struct FunctionMetadata {
@@ -37,6 +136,8 @@ pub struct FunctionMetadata {
     pub signature: Signature,
     /// If the function is an impl method, the impl type.
     pub impl_type: Option<Type>,
+    /// Role given by a `#[verieasy_constructor]` or `#[verieasy_getter]` attribute.
+    pub role: Option<FunctionRole>,
 }
 
 impl FunctionMetadata {
@@ -46,6 +147,7 @@ impl FunctionMetadata {
             name,
             signature,
             impl_type,
+            role: None,
         }
     }
 
@@ -54,20 +156,123 @@ impl FunctionMetadata {
         self.signature.0.ident.to_string()
     }
 
-    /// If the function is a constructor.
+    /// If the function is a constructor, named `verieasy_new` or tagged `#[verieasy_constructor]`.
     pub fn is_constructor(&self) -> bool {
-        self.impl_type.is_some() && self.signature.0.ident == "verieasy_new"
+        self.impl_type.is_some()
+            && (self.signature.0.ident == "verieasy_new"
+                || self.role == Some(FunctionRole::Constructor))
     }
 
-    /// If the function is a getter.
+    /// If the function is a getter, named `verieasy_get` or tagged `#[verieasy_getter]`.
     pub fn is_getter(&self) -> bool {
         self.impl_type.is_some()
             && matches!(
                 self.signature.0.inputs.first(),
                 Some(syn::FnArg::Receiver(_))
             )
-            && self.signature.0.ident == "verieasy_get"
+            && (self.signature.0.ident == "verieasy_get" || self.role == Some(FunctionRole::Getter))
     }
+
+    /// If the function returns a reference (`&T` or `&mut T`).
+    pub fn returns_reference(&self) -> bool {
+        matches!(
+            &self.signature.0.output,
+            syn::ReturnType::Type(_, ty) if matches!(**ty, syn::Type::Reference(_))
+        )
+    }
+
+    /// If the function returns an iterator: `impl Iterator<..>`, `Box<dyn Iterator<..>>` or an
+    /// iterator type of the standard library such as `std::iter::Map<..>`.
+    pub fn returns_iterator(&self) -> bool {
+        let syn::ReturnType::Type(_, ty) = &self.signature.0.output else {
+            return false;
+        };
+        iterator_bounds(ty).is_some() || is_std_iterator(ty)
+    }
+
+    /// Item type of the returned iterator, if it is given by an `Item = T` binding.
+    pub fn iterator_item(&self) -> Option<&syn::Type> {
+        let syn::ReturnType::Type(_, ty) = &self.signature.0.output else {
+            return None;
+        };
+        iterator_bounds(ty)?.iter().find_map(|bound| {
+            let syn::TypeParamBound::Trait(bound) = bound else {
+                return None;
+            };
+            let syn::PathArguments::AngleBracketed(args) = &bound.path.segments.last()?.arguments
+            else {
+                return None;
+            };
+            args.args.iter().find_map(|arg| match arg {
+                syn::GenericArgument::AssocType(assoc) if assoc.ident == "Item" => Some(&assoc.ty),
+                _ => None,
+            })
+        })
+    }
+}
+
+/// Iterator types of the standard library returned by lazy refactors.
+const STD_ITERATORS: &[&str] = &[
+    "Iter",
+    "IterMut",
+    "IntoIter",
+    "Map",
+    "Filter",
+    "FilterMap",
+    "FlatMap",
+    "Flatten",
+    "Rev",
+    "Chain",
+    "Zip",
+    "Take",
+    "TakeWhile",
+    "Skip",
+    "SkipWhile",
+    "Enumerate",
+    "StepBy",
+    "Cloned",
+    "Copied",
+    "Peekable",
+    "Chars",
+    "Bytes",
+];
+
+/// Bounds of `impl Trait` or `Box<dyn Trait>` if one of them is an iterator trait.
+fn iterator_bounds(
+    ty: &syn::Type,
+) -> Option<&syn::punctuated::Punctuated<syn::TypeParamBound, syn::Token![+]>> {
+    let bounds = match ty {
+        syn::Type::ImplTrait(ty) => &ty.bounds,
+        syn::Type::Path(path) => {
+            let last = path.path.segments.last()?;
+            let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+                return None;
+            };
+            match (last.ident == "Box", args.args.first()?) {
+                (true, syn::GenericArgument::Type(syn::Type::TraitObject(obj))) => &obj.bounds,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    bounds
+        .iter()
+        .any(|bound| {
+            matches!(bound, syn::TypeParamBound::Trait(bound)
+                if bound.path.segments.last().is_some_and(|seg| seg.ident.to_string().ends_with("Iterator")))
+        })
+        .then_some(bounds)
+}
+
+/// If `ty` is an iterator type of `std`, `core` or `alloc`, e.g. `std::slice::Iter<'_, T>`.
+fn is_std_iterator(ty: &syn::Type) -> bool {
+    let syn::Type::Path(path) = ty else {
+        return false;
+    };
+    let segments = &path.path.segments;
+    segments.len() > 1
+        && ["std", "core", "alloc"].contains(&segments[0].ident.to_string().as_str())
+        && STD_ITERATORS.contains(&segments.last().unwrap().ident.to_string().as_str())
 }
 
 impl Debug for FunctionMetadata {
@@ -83,12 +288,18 @@ pub struct Function {
     pub metadata: FunctionMetadata,
     /// Function body.
     pub body: String,
+    /// Attributes of the function other than doc comments, e.g. `# [inline]`.
+    pub attrs: Vec<String>,
 }
 
 impl Function {
     /// Create a new Function.
-    pub fn new(metadata: FunctionMetadata, body: String) -> Self {
-        Self { metadata, body }
+    pub fn new(metadata: FunctionMetadata, body: String, attrs: Vec<String>) -> Self {
+        Self {
+            metadata,
+            body,
+            attrs,
+        }
     }
 }
 
@@ -107,17 +318,30 @@ pub struct CommonFunction {
     pub body1: String,
     /// Body from second source file.
     pub body2: String,
+    /// Whether the function is `async` in the first and second source file.
+    pub asyncness: (bool, bool),
+    /// Signature in the second source file, if it differs from `metadata.signature` in integer
+    /// widths only (see `Signature::eq_up_to_int_width`).
+    pub signature2: Option<Signature>,
 }
 
 impl CommonFunction {
-    /// Create a new CommonFunction.
+    /// Create a new CommonFunction. Both versions are assumed to be `async` if the signature in
+    /// `metadata` is.
     pub fn new(metadata: FunctionMetadata, body1: String, body2: String) -> Self {
+        let is_async = metadata.signature.0.asyncness.is_some();
         Self {
             metadata,
             body1,
             body2,
+            asyncness: (is_async, is_async),
+            signature2: None,
         }
     }
+    /// If the function is `async` in either source file.
+    pub fn is_async(&self) -> bool {
+        self.asyncness.0 || self.asyncness.1
+    }
     /// Get the implementation type unchecked.
     pub fn impl_type(&self) -> &Type {
         self.metadata.impl_type.as_ref().unwrap()
@@ -130,6 +354,17 @@ impl Debug for CommonFunction {
     }
 }
 
+/// Range of an integer argument implied by simple precondition clauses, e.g. `size < 4096`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgBound {
+    /// Argument name.
+    pub arg: String,
+    /// Inclusive lower bound, if any.
+    pub min: Option<i128>,
+    /// Inclusive upper bound, if any.
+    pub max: Option<i128>,
+}
+
 /// Precondition for a function.
 #[derive(Clone)]
 pub struct Precondition {
@@ -137,13 +372,15 @@ pub struct Precondition {
     pub name: Path,
     /// Implementation type (if any).
     pub impl_type: Option<Type>,
+    /// Argument ranges implied by the checker, used to generate inputs that already satisfy it.
+    pub bounds: Vec<ArgBound>,
 }
 
 impl Precondition {
     /// Construct from the Path of the original function.
     pub fn new(name: Path, is_method: bool) -> Self {
         let impl_type = if is_method {
-            if name.0.len() >= 2 {
+            if name.segments().len() >= 2 {
                 Some(Type::from_path(name.parent().unwrap()))
             } else {
                 None
@@ -151,12 +388,21 @@ impl Precondition {
         } else {
             None
         };
-        Self { name, impl_type }
+        Self {
+            name,
+            impl_type,
+            bounds: Vec::new(),
+        }
     }
 
     /// Get the function identifier.
     pub fn ident(&self) -> String {
-        self.name.0.last().cloned().unwrap()
+        self.name.last().cloned().unwrap()
+    }
+
+    /// The range of argument `arg` implied by the checker, if any.
+    pub fn bound(&self, arg: &str) -> Option<&ArgBound> {
+        self.bounds.iter().find(|bound| bound.arg == arg)
     }
 
     /// The name of the check function.
@@ -191,6 +437,27 @@ fn type_to_string(ty: &syn::Type, sep: &str) -> String {
     }
 }
 
+/// Signedness and width in bits of a fixed-width primitive integer type. Pointer-sized integers
+/// are not considered fixed-width.
+pub fn int_width(ty: &syn::Type) -> Option<(bool, u32)> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    if path.qself.is_some() {
+        return None;
+    }
+    let name = path.path.get_ident()?.to_string();
+    let signed = match name.as_bytes().first()? {
+        b'u' => false,
+        b'i' => true,
+        _ => return None,
+    };
+    match name[1..].parse() {
+        Ok(bits @ (8 | 16 | 32 | 64 | 128)) => Some((signed, bits)),
+        _ => None,
+    }
+}
+
 /// Check if two types are equal
 fn type_eq(a: &syn::Type, b: &syn::Type) -> bool {
     type_to_string(a, "::") == type_to_string(b, "::")
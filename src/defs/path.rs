@@ -21,6 +21,27 @@ impl Path {
         self.0.join("___")
     }
 
+    /// Get the segments of the path.
+    pub fn segments(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Check whether `prefix` is made of the leading segments of this path.
+    ///
+    /// Segments are compared whole, so `Foo::bar` starts with `Foo` but not with `Fo`. The empty
+    /// path is a prefix of every path.
+    pub fn starts_with(&self, prefix: &Path) -> bool {
+        self.0.starts_with(&prefix.0)
+    }
+
+    /// Get the path left after removing the leading segments `prefix`, or `None` if this path does
+    /// not start with `prefix`.
+    pub fn strip_prefix(&self, prefix: &Path) -> Option<Path> {
+        self.0
+            .strip_prefix(prefix.0.as_slice())
+            .map(|rest| Path(rest.to_vec()))
+    }
+
     /// Get the last segment of the path.
     pub fn last(&self) -> Option<&String> {
         self.0.last()
@@ -37,12 +58,6 @@ impl Path {
         }
     }
 
-    /// Parse from a string representation with "::" separator.
-    pub fn from_str(s: &str) -> Self {
-        let segments: Vec<String> = s.split("::").map(|seg| seg.to_string()).collect();
-        Path(segments)
-    }
-
     /// Concatenate a string to this one.
     pub fn join(mut self, seg: String) -> Path {
         self.0.push(seg);
@@ -50,6 +65,44 @@ impl Path {
     }
 }
 
+impl FromStr for Path {
+    type Err = std::convert::Infallible;
+
+    /// Parse from a string representation with "::" separator.
+    ///
+    /// Separators inside generic arguments are kept, so `Foo<a::B>::f` has segments `Foo<a::B>`
+    /// and `f`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0;
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'<' => depth += 1,
+                b'>' => depth = depth.saturating_sub(1),
+                b':' if depth == 0 && bytes.get(i + 1) == Some(&b':') => {
+                    segments.push(s[start..i].to_string());
+                    start = i + 2;
+                    i += 1;
+                }
+                _ => (),
+            }
+            i += 1;
+        }
+        segments.push(s[start..].to_string());
+        Ok(Path(segments))
+    }
+}
+
+impl From<&str> for Path {
+    fn from(s: &str) -> Self {
+        let Ok(path) = s.parse();
+        path
+    }
+}
+
 impl std::fmt::Debug for Path {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.to_string())
@@ -73,7 +126,11 @@ impl From<Path> for syn::Path {
             .0
             .into_iter()
             .map(|seg| syn::PathSegment {
-                ident: syn::Ident::new(&seg, proc_macro2::Span::call_site()),
+                // Raw identifiers like `r#type` must be rebuilt as raw
+                ident: match seg.strip_prefix("r#") {
+                    Some(raw) => syn::Ident::new_raw(raw, proc_macro2::Span::call_site()),
+                    None => syn::Ident::new(&seg, proc_macro2::Span::call_site()),
+                },
                 arguments: syn::PathArguments::None,
             })
             .collect();
@@ -90,3 +147,49 @@ impl quote::ToTokens for Path {
         tokens.extend(ts);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::defs::{Precondition, Type};
+
+    #[test]
+    fn generic_arguments_are_one_segment() {
+        let path = Path::from("Foo<a::B, C>::f");
+        assert_eq!(path.segments(), ["Foo<a::B, C>", "f"]);
+        assert_eq!(Path::from("a::b::c").segments(), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn generic_impl_method_precondition() {
+        let pre = Precondition::new(Path::from("BitAllocCascade16<T>::dealloc"), true);
+        let impl_type = pre.impl_type.as_ref().unwrap();
+        assert!(impl_type.eq_ignore_generics(&Type::from_path(Path::from(
+            "BitAllocCascade16<BitAlloc16>"
+        ))));
+        assert_eq!(pre.ident(), "dealloc");
+        assert_eq!(pre.checker_name().to_string(), "verieasy_pre_dealloc");
+    }
+
+    #[test]
+    fn prefixes_match_whole_segments() {
+        let path = Path::from("a::b::c");
+        assert!(path.starts_with(&Path::from("a::b")));
+        assert!(!path.starts_with(&Path::from("a::bc")));
+        assert!(!Path::from("Foo::bar").starts_with(&Path::from("Fo")));
+        let rest = path.strip_prefix(&Path::from("a"));
+        assert_eq!(rest, Some(Path::from("b::c")));
+        assert_eq!(path.strip_prefix(&path), Some(Path::empty()));
+        assert_eq!(path.strip_prefix(&Path::from("b")), None);
+    }
+
+    #[test]
+    fn empty_path_is_a_prefix_of_every_path() {
+        let path = Path::from("a::b");
+        assert!(path.starts_with(&Path::empty()));
+        assert!(Path::empty().starts_with(&Path::empty()));
+        assert!(!Path::empty().starts_with(&path));
+        assert_eq!(path.strip_prefix(&Path::empty()), Some(path.clone()));
+        assert_eq!(Path::empty().strip_prefix(&path), None);
+    }
+}
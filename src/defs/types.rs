@@ -11,34 +11,30 @@ pub enum Type {
 
 impl Type {
     /// Parse type from its path representation.
+    ///
+    /// Generic arguments may be nested types (`Foo<Bar<Baz>, Qux>`) or numeric const generics
+    /// (`BitArray<256>`), which are kept as precise types named by the number.
     pub fn from_path(path: Path) -> Self {
-        if path.last().unwrap().contains('<') {
-            // Generic type
-            let last = path.last().unwrap();
-            let base_name = last.split('<').next().unwrap().to_string();
-            let base_path = {
-                let mut p = path.clone();
-                *p.0.last_mut().unwrap() = base_name;
-                p
-            };
-            let generics_str = last
-                .split('<')
-                .nth(1)
-                .unwrap()
-                .trim_end_matches('>')
-                .to_string();
-            // Split generics by comma
-            let generic_types: Vec<Type> = generics_str
-                .split(',')
-                .map(|s| Type::from_path(Path(vec![s.trim().to_string()])))
-                .collect();
-            Type::Generic(GenericType {
-                path: base_path,
-                generics: generic_types,
-            })
-        } else {
+        let last = path.last().unwrap();
+        match (last.find('<'), last.rfind('>')) {
+            (Some(open), Some(close)) if open < close => {
+                // Generic type
+                let base_path = {
+                    let mut p = path.clone();
+                    *p.0.last_mut().unwrap() = last[..open].trim().to_string();
+                    p
+                };
+                let generic_types: Vec<Type> = split_generic_args(&last[open + 1..close])
+                    .into_iter()
+                    .map(|s| Type::from_path(Path(vec![s.to_string()])))
+                    .collect();
+                Type::Generic(GenericType {
+                    path: base_path,
+                    generics: generic_types,
+                })
+            }
             // Precise type
-            Type::Precise(PreciseType(path))
+            _ => Type::Precise(PreciseType(path)),
         }
     }
 
@@ -58,6 +54,30 @@ impl Type {
             _ => false,
         }
     }
+
+    /// Check equality ignoring type parameters, but not the shape of generic arguments.
+    ///
+    /// Unlike `eq_ignore_generics`, base paths and argument counts of nested generic arguments
+    /// must match, so `Vec<Foo<T>>` equals `Vec<Foo<u16>>` but not `Vec<Bar<u16>>`. A precise
+    /// argument may be a type parameter, so it matches any argument.
+    pub fn eq_generic_shape(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Generic(g1), Type::Generic(g2)) => {
+                g1.path == g2.path
+                    && g1.generics.len() == g2.generics.len()
+                    && g1
+                        .generics
+                        .iter()
+                        .zip(&g2.generics)
+                        .all(|(a, b)| match (a, b) {
+                            (Type::Generic(_), Type::Generic(_)) => a.eq_generic_shape(b),
+                            _ => true,
+                        })
+            }
+            (Type::Precise(p1), Type::Precise(p2)) => p1 == p2,
+            _ => false,
+        }
+    }
 }
 
 impl TryFrom<syn::Type> for Type {
@@ -77,6 +97,14 @@ impl TryFrom<syn::Type> for Type {
                                     let ty_converted = Type::try_from(ty).map_err(|_| ())?;
                                     generics.push(ty_converted);
                                 }
+                                // Numeric const generic, e.g. `BitArray<256>`
+                                syn::GenericArgument::Const(syn::Expr::Lit(syn::ExprLit {
+                                    lit: syn::Lit::Int(int),
+                                    ..
+                                })) => {
+                                    let value = int.base10_digits().to_string();
+                                    generics.push(Type::Precise(PreciseType(Path(vec![value]))));
+                                }
                                 _ => return Err(()),
                             }
                         }
@@ -90,6 +118,27 @@ impl TryFrom<syn::Type> for Type {
     }
 }
 
+/// Split generic arguments at top-level commas, keeping nested `<...>` intact.
+fn split_generic_args(s: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                args.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    args.push(s[start..].trim());
+    args.retain(|arg| !arg.is_empty());
+    args
+}
+
 /// A precise type.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct PreciseType(pub Path);
@@ -132,3 +181,49 @@ pub struct InstantiatedType {
     /// The concrete type it instantiates.
     pub concrete: Type,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Precise type named `name`.
+    fn precise(name: &str) -> Type {
+        Type::Precise(PreciseType(Path::from(name)))
+    }
+
+    /// Generic type `path<generics>`.
+    fn generic(path: &str, generics: Vec<Type>) -> Type {
+        let path = Path::from(path);
+        Type::Generic(GenericType { path, generics })
+    }
+
+    #[test]
+    fn nested_generic_arguments() {
+        let ty = Type::from_path(Path::from("Foo<Bar<Baz>, Qux>"));
+        let bar = generic("Bar", vec![precise("Baz")]);
+        assert_eq!(ty, generic("Foo", vec![bar, precise("Qux")]));
+        assert_eq!(ty.to_path().to_string(), "Foo<Bar<Baz>, Qux>");
+    }
+
+    #[test]
+    fn const_generic_arguments() {
+        let ty = Type::from_path(Path::from("BitArray<256>"));
+        assert_eq!(ty, generic("BitArray", vec![precise("256")]));
+
+        let parsed: syn::Type = syn::parse_str("BitArray<256>").unwrap();
+        assert_eq!(Type::try_from(parsed), Ok(ty));
+    }
+
+    #[test]
+    fn generic_shape_recurses_into_arguments() {
+        let ty = |s: &str| Type::from_path(Path::from(s));
+        assert!(ty("Foo<T>").eq_generic_shape(&ty("Foo<u16>")));
+        assert!(ty("Vec<Foo<T>>").eq_generic_shape(&ty("Vec<Foo<u16>>")));
+        assert!(!ty("Vec<Foo<T>>").eq_generic_shape(&ty("Vec<Bar<u16>>")));
+        assert!(!ty("Map<K, V>").eq_generic_shape(&ty("Map<u8>")));
+
+        // The loose comparison only looks at the outer path
+        assert!(ty("Vec<Foo<T>>").eq_ignore_generics(&ty("Vec<Bar<u16>>")));
+        assert!(!ty("Foo<T>").eq_ignore_generics(&ty("Foo")));
+    }
+}
@@ -1,11 +1,11 @@
 //! Harness generator used by various steps (Kani, PBT, DFT).
 use proc_macro2::TokenStream;
-use quote::{format_ident, quote};
+use quote::{ToTokens, format_ident, quote};
 use std::collections::BTreeMap;
 
 use crate::{
     check::Checker,
-    defs::{CommonFunction, Path, Precondition, Type},
+    defs::{ArgBound, CommonFunction, Path, Precondition, Type, int_width},
     log,
 };
 
@@ -14,7 +14,8 @@ use crate::{
 /// - Free-standing functions (without `self` receiver)
 /// - methods (with `self` receiver)
 /// - constructors (functions that has name `verieasy_new` inside an `impl` block)
-/// - state getters (functions that has name `verieasy_get` inside an `impl` block)
+/// - state getters (functions that has name `verieasy_get` inside an `impl` block, or tagged
+///   `#[verieasy_getter]`, possibly several per type)
 #[derive(Debug)]
 pub struct FunctionCollection {
     /// Free-standing functions.
@@ -23,10 +24,16 @@ pub struct FunctionCollection {
     pub methods: Vec<CommonFunction>,
     /// Constructors mapped by their type.
     pub constructors: BTreeMap<Type, CommonFunction>,
-    /// State getters mapped by their type.
-    pub getters: BTreeMap<Type, CommonFunction>,
+    /// State getters grouped by their type.
+    pub getters: BTreeMap<Type, Vec<CommonFunction>>,
     /// Preconditions
     pub preconditions: Vec<Precondition>,
+    /// Guards of methods, derived from other methods of their types.
+    pub guards: Vec<MethodGuard>,
+    /// Types whose whole state is compared if they have no getter.
+    pub comparable_types: Vec<Type>,
+    /// Generate `usize`/`isize` arguments as `u64`/`i64`, see `fixed_width_type`.
+    pub fixed_width_usize: bool,
 }
 
 impl FunctionCollection {
@@ -45,6 +52,9 @@ impl FunctionCollection {
             constructors: BTreeMap::new(),
             getters: BTreeMap::new(),
             preconditions,
+            guards: Vec::new(),
+            comparable_types: Vec::new(),
+            fixed_width_usize: false,
         };
         for func in functions {
             if let Some(_) = &func.metadata.impl_type {
@@ -74,7 +84,10 @@ impl FunctionCollection {
         }
         for getter in getters {
             if let Some(impl_type) = &getter.metadata.impl_type {
-                res.getters.insert(impl_type.clone(), getter);
+                res.getters
+                    .entry(impl_type.clone())
+                    .or_default()
+                    .push(getter);
             }
         }
         res
@@ -87,7 +100,32 @@ impl FunctionCollection {
             .find(|pre| pre.name == func.metadata.name)
     }
 
-    /// If `methods` doesn't have a method of type `T`, then its constructor and getter asre unused.
+    /// Get the guard for the given method.
+    pub fn get_guard(&self, method: &CommonFunction) -> Option<&MethodGuard> {
+        self.guards
+            .iter()
+            .find(|guard| guard.method == method.metadata.name)
+    }
+
+    /// Derive guards for methods from the other methods of their types in `known`.
+    pub fn derive_guards(&mut self, known: &[CommonFunction]) {
+        self.guards = self
+            .methods
+            .iter()
+            .filter_map(|method| MethodGuard::derive(method, known, self.fixed_width_usize))
+            .collect();
+        for guard in &self.guards {
+            log!(
+                Verbose,
+                Info,
+                "Guard `{:?}` with `{}`",
+                guard.method,
+                guard.condition()
+            );
+        }
+    }
+
+    /// If `methods` doesn't have a method of type `T`, then its constructor and getters are unused.
     ///
     /// This function removes those constructors and getters.
     pub fn remove_unused_constructors_and_getters(&mut self) {
@@ -105,7 +143,7 @@ impl FunctionCollection {
             log!(
                 Verbose,
                 Warning,
-                "Type `{:?}` doesn't have any methods, remove its constructor and getter.",
+                "Type `{:?}` doesn't have any methods, remove its constructor and getters.",
                 type_.to_path()
             );
             self.constructors.remove(type_);
@@ -161,6 +199,19 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
         );
         collection.remove_unused_constructors_and_getters();
         collection.remove_methods_without_constructors();
+        let known = [
+            &checker.under_checking_funcs,
+            &checker.verified_funcs,
+            &checker.tested_funcs,
+            &checker.failed_funcs,
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+        collection.fixed_width_usize = checker.options().fixed_width_usize;
+        collection.derive_guards(&known);
+        collection.comparable_types = checker.comparable_types.clone();
         Self {
             collection,
             mod1_imports: checker.src1.symbols.clone(),
@@ -172,17 +223,39 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
     /// Generate argument struct `ArgsFoo` for function `foo`; backend supplies the derive/attrs.
     fn generate_arg_struct(&self, func: &CommonFunction) -> TokenStream {
         let struct_name = format_ident!("Args{}", func.metadata.name.to_ident());
+        let precondition = self.collection.get_precondition(func);
         let mut fields = Vec::<TokenStream>::new();
-        for arg in &func.metadata.signature.0.inputs {
-            if matches!(arg, syn::FnArg::Typed(_)) {
-                fields.push(quote! { #arg });
-            }
+        let args = typed_args(&func.metadata.signature.0);
+        for (index, pat_type) in args.into_iter().enumerate() {
+            // Borrowed slices and strings, and `&mut` arguments are stored as owned values
+            let owned = owned_arg_type(&pat_type.ty)
+                .map(|(owned, _)| owned)
+                .or_else(|| owned_mut_arg_type(&pat_type.ty).map(|(owned, _)| owned))
+                .or_else(|| {
+                    self.collection
+                        .fixed_width_usize
+                        .then(|| fixed_width_type(&pat_type.ty))
+                        .flatten()
+                });
+            let ident = arg_ident(index, pat_type);
+            let ty = match widened_arg(func, index) {
+                Some((ty1, ty2)) => narrower(ty1, ty2).to_token_stream(),
+                None => owned.unwrap_or_else(|| pat_type.ty.to_token_stream()),
+            };
+            // Arguments ranged by the precondition may be generated within the range
+            let attrs = match &*pat_type.pat {
+                syn::Pat::Ident(pat_ident) => precondition
+                    .and_then(|pre| pre.bound(&pat_ident.ident.to_string()))
+                    .map(|bound| self.backend.bounded_field_attrs(&ty, bound)),
+                _ => None,
+            };
+            fields.push(quote! { #attrs pub #ident: #ty });
         }
         let attrs = self.backend.arg_struct_attrs();
         quote! {
             #attrs
             pub struct #struct_name {
-                #(pub #fields),*
+                #(#fields),*
             }
         }
     }
@@ -223,48 +296,61 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
         func_structs
     }
 
+    /// Warn if `func` returns an iterator whose items may not be comparable once collected.
+    fn warn_incomparable_items(&self, func: &CommonFunction) {
+        let Some(item) = func.metadata.iterator_item() else {
+            return;
+        };
+        let comparable = is_comparable_item(item)
+            || Type::try_from(item.clone())
+                .is_ok_and(|ty| self.collection.comparable_types.contains(&ty));
+        if !comparable {
+            log!(
+                Normal,
+                Warning,
+                "Items `{}` of the iterator returned by `{:?}` are compared in a `Vec`, they must implement `PartialEq` and `Debug`",
+                item.to_token_stream(),
+                func.metadata.name
+            );
+        }
+    }
+
     /// Generate a harness function for comparing two free-standing functions.
     fn generate_harness_for_function(&self, func: &CommonFunction) -> TokenStream {
+        self.warn_incomparable_items(func);
         let precondition = self.collection.get_precondition(func);
-
-        let mut function_args = Vec::<TokenStream>::new();
-        for arg in &func.metadata.signature.0.inputs {
-            if let syn::FnArg::Typed(pat_type) = arg {
-                let arg_name = match &*pat_type.pat {
-                    syn::Pat::Ident(pat_ident) => pat_ident.ident.to_string(),
-                    _ => "arg".to_string(),
-                };
-                let ident = format_ident!("{}", arg_name);
-                function_args.push(quote! { #ident.clone() });
-            }
-        }
+        let call_args = FunctionCallArgs::new(func, self.collection.fixed_width_usize);
         self.backend
-            .make_harness_for_function(func, &function_args, precondition)
+            .make_harness_for_function(func, &call_args, precondition)
     }
 
     /// Generate a harness function for comparing two methods.
     fn generate_harness_for_method(&self, method: &CommonFunction) -> TokenStream {
+        self.warn_incomparable_items(method);
         let constructor = self
             .collection
             .constructors
             .get(method.impl_type())
             .unwrap();
-        // getter may be absent
-        let getter = self.collection.getters.get(method.impl_type());
+        // getters may be absent, the whole state is compared instead if possible
+        let getters = self
+            .collection
+            .getters
+            .get(method.impl_type())
+            .map_or(&[][..], Vec::as_slice);
+        let compare_state = getters.is_empty()
+            && self
+                .collection
+                .comparable_types
+                .contains(method.impl_type());
         let precondition = self.collection.get_precondition(method);
+        let guard = self
+            .collection
+            .get_guard(method)
+            .map(MethodGuard::condition);
 
         // collect constructor args
-        let mut constructor_args = Vec::new();
-        for arg in &constructor.metadata.signature.0.inputs {
-            if let syn::FnArg::Typed(pat_type) = arg {
-                let name = match &*pat_type.pat {
-                    syn::Pat::Ident(pi) => pi.ident.to_string(),
-                    _ => "arg".into(),
-                };
-                let ident = format_ident!("{}", name);
-                constructor_args.push(quote! { #ident.clone() });
-            }
-        }
+        let constructor_args = struct_call_args(constructor, self.collection.fixed_width_usize);
 
         // method args and receiver info
         let mut method_args = Vec::new();
@@ -277,12 +363,11 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
                     receiver_ref = rec.reference.clone();
                 }
                 syn::FnArg::Typed(pat) => {
-                    let name = match &*pat.pat {
-                        syn::Pat::Ident(pi) => pi.ident.to_string(),
-                        _ => "arg".into(),
-                    };
-                    let ident = format_ident!("{}", name);
-                    method_args.push(quote! { #ident.clone() });
+                    method_args.push(call_arg(
+                        method_args.len(),
+                        pat,
+                        self.collection.fixed_width_usize,
+                    ));
                 }
             }
         }
@@ -293,31 +378,41 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
             quote! { #reference #mut_tok }
         };
 
-        self.backend.make_harness_for_method(
+        self.backend.make_harness_for_method(MethodHarness {
             method,
             constructor,
-            getter,
-            &method_args,
-            &constructor_args,
+            getters,
+            compare_state,
+            method_args,
+            constructor_args,
             receiver_prefix,
             precondition,
-        )
+            guard,
+        })
     }
 
     /// Generate trait imports (`use` statements) for the harness file.
     fn generate_imports(&self) -> Vec<TokenStream> {
-        let mod1_import_stmts = self.mod1_imports.iter().map(|path| {
-            let ident = format_ident!("Mod1{}", path.0.last().unwrap());
+        // Symbols are relative to the source file, which becomes module `mod1` or `mod2`
+        let crate_root = Path(vec!["crate".to_string()]);
+        let import = |module: &str, alias: &str, path: &Path| {
+            let module = format_ident!("{}", module);
+            let ident = format_ident!("{}{}", alias, path.last().unwrap());
+            let path = path
+                .strip_prefix(&crate_root)
+                .unwrap_or_else(|| path.clone());
             quote! {
-                use mod1::#path as #ident;
+                use #module::#path as #ident;
             }
-        });
-        let mod2_import_stmts = self.mod2_imports.iter().map(|path| {
-            let ident = format_ident!("Mod2{}", path.0.last().unwrap());
-            quote! {
-                use mod2::#path as #ident;
-            }
-        });
+        };
+        let mod1_import_stmts = self
+            .mod1_imports
+            .iter()
+            .map(|path| import("mod1", "Mod1", path));
+        let mod2_import_stmts = self
+            .mod2_imports
+            .iter()
+            .map(|path| import("mod2", "Mod2", path));
         mod1_import_stmts.chain(mod2_import_stmts).collect()
     }
 
@@ -344,30 +439,525 @@ impl<B: HarnessBackend> HarnessGenerator<B> {
     }
 }
 
+/// Owned field type for a borrowed argument type that cannot be stored in an `Args*` struct,
+/// together with the method that borrows it back at the call site.
+///
+/// `&[T]` becomes `Vec<T>` (passed as `.as_slice()`) and `&str` becomes `String` (passed as
+/// `.as_str()`).
+fn owned_arg_type(ty: &syn::Type) -> Option<(TokenStream, &'static str)> {
+    let syn::Type::Reference(reference) = ty else {
+        return None;
+    };
+    if reference.mutability.is_some() {
+        return None;
+    }
+    match &*reference.elem {
+        syn::Type::Slice(slice) => {
+            let elem = &slice.elem;
+            Some((quote! { Vec<#elem> }, "as_slice"))
+        }
+        syn::Type::Path(path) if path.qself.is_none() && path.path.is_ident("str") => {
+            Some((quote! { String }, "as_str"))
+        }
+        _ => None,
+    }
+}
+
+/// Owned field type for an argument taken by `&mut`, together with the method that borrows it
+/// back mutably at the call site (`None` for a plain `&mut` borrow).
+///
+/// `&mut [T]` becomes `Vec<T>` (passed as `.as_mut_slice()`) and `&mut T` becomes `T`.
+fn owned_mut_arg_type(ty: &syn::Type) -> Option<(TokenStream, Option<&'static str>)> {
+    let syn::Type::Reference(reference) = ty else {
+        return None;
+    };
+    reference.mutability?;
+    match &*reference.elem {
+        syn::Type::Slice(slice) => {
+            let elem = &slice.elem;
+            Some((quote! { Vec<#elem> }, Some("as_mut_slice")))
+        }
+        elem => Some((quote! { #elem }, None)),
+    }
+}
+
+/// Fixed-width field type for a pointer-sized integer argument: `usize` becomes `u64` and
+/// `isize` becomes `i64`, cast back at the call site.
+///
+/// Inputs are then drawn from the same domain on every host, so a harness generated on a 32-bit
+/// target exercises the same values as on a 64-bit one, up to the final truncating cast.
+fn fixed_width_type(ty: &syn::Type) -> Option<TokenStream> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    if path.qself.is_some() {
+        return None;
+    }
+    match path.path.get_ident()?.to_string().as_str() {
+        "usize" => Some(quote! { u64 }),
+        "isize" => Some(quote! { i64 }),
+        _ => None,
+    }
+}
+
+/// Name of the `Args*` struct field holding the `index`-th typed argument.
+///
+/// Keywords used as raw identifiers (`r#type`) are renamed to `arg_type`, so that the field and
+/// the locals derived from it (e.g. `arg_type_mod1`) are plain identifiers in every backend.
+/// Arguments bound by other patterns (`_`, `(a, b)`) are named by position (`arg0`, `arg1`),
+/// keeping a single field of the whole argument type.
+pub fn arg_ident(index: usize, pat_type: &syn::PatType) -> syn::Ident {
+    match &*pat_type.pat {
+        syn::Pat::Ident(pat_ident) => {
+            let name = pat_ident.ident.to_string();
+            match name.strip_prefix("r#") {
+                Some(keyword) => format_ident!("arg_{}", keyword),
+                None => pat_ident.ident.clone(),
+            }
+        }
+        _ => format_ident!("arg{}", index),
+    }
+}
+
+/// Expression passing an `Args*` struct field to the checked function, appended to the struct
+/// variable by the backend (e.g. `x.clone()`, `data.as_slice()` or `len as usize`).
+fn call_arg(index: usize, pat_type: &syn::PatType, fixed_width: bool) -> TokenStream {
+    let ident = arg_ident(index, pat_type);
+    if fixed_width && fixed_width_type(&pat_type.ty).is_some() {
+        let ty = &pat_type.ty;
+        return quote! { #ident as #ty };
+    }
+    match owned_arg_type(&pat_type.ty) {
+        Some((_, borrow)) => {
+            let borrow = format_ident!("{}", borrow);
+            quote! { #ident.#borrow() }
+        }
+        None => quote! { #ident.clone() },
+    }
+}
+
+/// Expressions passing every typed argument of `func` from its `Args*` struct, see `call_arg`.
+pub fn struct_call_args(func: &CommonFunction, fixed_width: bool) -> Vec<TokenStream> {
+    typed_args(&func.metadata.signature.0)
+        .into_iter()
+        .enumerate()
+        .map(|(index, pat_type)| call_arg(index, pat_type, fixed_width))
+        .collect()
+}
+
+/// Arguments of a free-standing function call, read from the `function_arg_struct` variable
+/// holding its `Args*` struct.
+///
+/// Arguments taken by `&mut` are cloned into a separate local for each module, so `mod1` and
+/// `mod2` mutate their own copy and the final values can be compared after the calls.
+pub struct FunctionCallArgs {
+    /// Statements declaring the per-module copies of `&mut` arguments.
+    pub setup: TokenStream,
+    /// Arguments of the precondition check.
+    pub precondition: Vec<TokenStream>,
+    /// Arguments of the `mod1` call.
+    pub mod1: Vec<TokenStream>,
+    /// Arguments of the `mod2` call.
+    pub mod2: Vec<TokenStream>,
+    /// Expression that is true if a `&mut` argument differs after the calls, `None` if the
+    /// function takes no `&mut` arguments.
+    pub mut_mismatch: Option<TokenStream>,
+    /// Index of the module whose result is narrower, with the wider return type.
+    widened_result: Option<(usize, syn::Type)>,
+}
+
+impl FunctionCallArgs {
+    /// Build the call arguments of `func`, casting fixed-width fields back if `fixed_width`.
+    pub fn new(func: &CommonFunction, fixed_width: bool) -> Self {
+        let mut res = Self {
+            setup: quote! {},
+            precondition: Vec::new(),
+            mod1: Vec::new(),
+            mod2: Vec::new(),
+            mut_mismatch: None,
+            widened_result: None,
+        };
+        let mut setup = Vec::new();
+        let mut mismatches = Vec::new();
+        let args = typed_args(&func.metadata.signature.0);
+        for (index, pat_type) in args.into_iter().enumerate() {
+            match owned_mut_arg_type(&pat_type.ty) {
+                Some((_, borrow)) => {
+                    let ident = arg_ident(index, pat_type);
+                    let copy1 = format_ident!("{}_mod1", ident);
+                    let copy2 = format_ident!("{}_mod2", ident);
+                    setup.push(quote! {
+                        let mut #copy1 = function_arg_struct.#ident.clone();
+                        let mut #copy2 = function_arg_struct.#ident.clone();
+                    });
+                    match borrow.map(|borrow| format_ident!("{}", borrow)) {
+                        Some(borrow) => {
+                            res.precondition
+                                .push(quote! { function_arg_struct.#ident.clone().#borrow() });
+                            res.mod1.push(quote! { #copy1.#borrow() });
+                            res.mod2.push(quote! { #copy2.#borrow() });
+                        }
+                        None => {
+                            res.precondition
+                                .push(quote! { &mut function_arg_struct.#ident.clone() });
+                            res.mod1.push(quote! { &mut #copy1 });
+                            res.mod2.push(quote! { &mut #copy2 });
+                        }
+                    }
+                    mismatches.push(quote! { #copy1 != #copy2 });
+                }
+                None => {
+                    let arg = call_arg(index, pat_type, fixed_width);
+                    match widened_arg(func, index) {
+                        // The field has the narrower type, cast to the type of each source
+                        Some((ty1, ty2)) => {
+                            res.precondition
+                                .push(quote! { function_arg_struct.#arg as #ty2 });
+                            res.mod1.push(quote! { function_arg_struct.#arg as #ty1 });
+                            res.mod2.push(quote! { function_arg_struct.#arg as #ty2 });
+                        }
+                        None => {
+                            res.precondition.push(quote! { function_arg_struct.#arg });
+                            res.mod1.push(quote! { function_arg_struct.#arg });
+                            res.mod2.push(quote! { function_arg_struct.#arg });
+                        }
+                    }
+                }
+            }
+        }
+        res.setup = quote! { #(#setup)* };
+        if !mismatches.is_empty() {
+            res.mut_mismatch = Some(quote! { #(#mismatches)||* });
+        }
+        if let Some(sig2) = &func.signature2
+            && let (syn::ReturnType::Type(_, ty1), syn::ReturnType::Type(_, ty2)) =
+                (&func.metadata.signature.0.output, &sig2.0.output)
+            && let (Some(width1), Some(width2)) = (int_width(ty1), int_width(ty2))
+            && width1 != width2
+        {
+            res.widened_result = Some(if width1.1 < width2.1 {
+                (1, (**ty2).clone())
+            } else {
+                (2, (**ty1).clone())
+            });
+        }
+        res
+    }
+
+    /// Cast the result of `call`, the call to `mod{index}`, to the wider return type if the
+    /// return types of the sources differ in width, so that both results have the same type.
+    pub fn widen_result(&self, index: usize, call: TokenStream) -> TokenStream {
+        match &self.widened_result {
+            Some((narrower, wider)) if *narrower == index => quote! { (#call) as #wider },
+            _ => call,
+        }
+    }
+}
+
+/// Integer types of the `index`-th typed argument of `func` in the first and second source, if
+/// they differ in width (see `CommonFunction::signature2`).
+fn widened_arg(func: &CommonFunction, index: usize) -> Option<(&syn::Type, &syn::Type)> {
+    let sig2 = func.signature2.as_ref()?;
+    let args1 = typed_args(&func.metadata.signature.0);
+    let args2 = typed_args(&sig2.0);
+    let (ty1, ty2) = (&args1.get(index)?.ty, &args2.get(index)?.ty);
+    (int_width(ty1)? != int_width(ty2)?).then_some((ty1, ty2))
+}
+
+/// The narrower of two integer types.
+fn narrower<'a>(ty1: &'a syn::Type, ty2: &'a syn::Type) -> &'a syn::Type {
+    match (int_width(ty1), int_width(ty2)) {
+        (Some((_, bits1)), Some((_, bits2))) if bits2 < bits1 => ty2,
+        _ => ty1,
+    }
+}
+
+/// Guard on the receiver of a method, derived from another method of its type. Inputs failing the
+/// guard are skipped, like inputs violating a precondition.
+///
+/// Guards exclude inputs on which the implementations may legitimately differ. For now, a
+/// `dealloc`-like method taking a single key only proceeds if `test(key)` reports the key as
+/// allocated, so double frees are not compared.
+#[derive(Debug)]
+pub struct MethodGuard {
+    /// Name of the guarded method.
+    pub method: Path,
+    /// Method of the receiver queried by the guard.
+    pub query: syn::Ident,
+    /// Argument of the guarded method passed to the query, see `call_arg`.
+    pub arg: TokenStream,
+    /// Result of the query that lets the call proceed.
+    pub expected: bool,
+}
+
+impl MethodGuard {
+    /// Derive the guard of `method` from the methods of its type in `known`, if any applies.
+    pub fn derive(
+        method: &CommonFunction,
+        known: &[CommonFunction],
+        fixed_width: bool,
+    ) -> Option<Self> {
+        if !method.metadata.ident().starts_with("dealloc") {
+            return None;
+        }
+        let [arg] = typed_args(&method.metadata.signature.0)[..] else {
+            return None;
+        };
+        let query = known.iter().find(|func| {
+            let sig = &func.metadata.signature.0;
+            func.metadata.impl_type == method.metadata.impl_type
+                && sig.ident == "test"
+                && typed_args(sig).len() == 1
+                && matches!(&sig.output, syn::ReturnType::Type(_, ty)
+                    if matches!(&**ty, syn::Type::Path(p) if p.path.is_ident("bool")))
+        })?;
+        Some(Self {
+            method: method.metadata.name.clone(),
+            query: query.metadata.signature.0.ident.clone(),
+            arg: call_arg(0, arg, fixed_width),
+            expected: false,
+        })
+    }
+
+    /// Expression on receiver `s2` and argument struct `method_arg_struct` that is true if the
+    /// call should proceed.
+    pub fn condition(&self) -> TokenStream {
+        let (query, arg) = (&self.query, &self.arg);
+        if self.expected {
+            quote! { s2.#query(method_arg_struct.#arg) }
+        } else {
+            quote! { !s2.#query(method_arg_struct.#arg) }
+        }
+    }
+}
+
+/// Typed (non-receiver) arguments of a signature.
+fn typed_args(sig: &syn::Signature) -> Vec<&syn::PatType> {
+    sig.inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) => Some(pat_type),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect()
+}
+
+/// Poll the future returned by `call` to completion if the called function is `async`.
+pub fn block_on_if_async(call: TokenStream, is_async: bool) -> TokenStream {
+    if is_async {
+        quote! { futures::executor::block_on(#call) }
+    } else {
+        call
+    }
+}
+
+/// Collect the iterator returned by `call` into a `Vec` if `func` returns an iterator, so that lazy
+/// and eager implementations can be compared.
+pub fn collect_if_iterator(call: TokenStream, func: &CommonFunction) -> TokenStream {
+    if func.metadata.returns_iterator() {
+        quote! { (#call).collect::<Vec<_>>() }
+    } else {
+        call
+    }
+}
+
+/// Primitive and string types implementing `PartialEq` and `Debug`.
+const COMPARABLE_PRIMITIVES: &[&str] = &[
+    "bool", "char", "str", "String", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16",
+    "i32", "i64", "i128", "isize", "f32", "f64",
+];
+
+/// If values of `ty` are known to implement `PartialEq` and `Debug`: primitives, strings, and
+/// references, tuples, arrays, slices, `Vec`s and `Option`s of them.
+pub fn is_comparable_item(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(ty) => is_comparable_item(&ty.elem),
+        syn::Type::Tuple(ty) => ty.elems.iter().all(is_comparable_item),
+        syn::Type::Array(ty) => is_comparable_item(&ty.elem),
+        syn::Type::Slice(ty) => is_comparable_item(&ty.elem),
+        syn::Type::Path(path) if path.qself.is_none() => {
+            let Some(last) = path.path.segments.last() else {
+                return false;
+            };
+            match &last.arguments {
+                syn::PathArguments::None => {
+                    COMPARABLE_PRIMITIVES.contains(&last.ident.to_string().as_str())
+                }
+                syn::PathArguments::AngleBracketed(args)
+                    if last.ident == "Vec" || last.ident == "Option" =>
+                {
+                    args.args.iter().all(|arg| match arg {
+                        syn::GenericArgument::Type(ty) => is_comparable_item(ty),
+                        _ => true,
+                    })
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Expression that is true if the return values `r1` and `r2` of `func` differ.
+///
+/// References are compared by the values they point to. If `caught` is true, the return values
+/// are wrapped in the `Result` produced by `catch_unwind` (see `map_panic_payload`).
+pub fn return_mismatch(func: &CommonFunction, caught: bool) -> TokenStream {
+    if !func.metadata.returns_reference() {
+        return quote! { r1 != r2 };
+    }
+    if caught {
+        quote! {
+            match (&r1, &r2) {
+                (Ok(v1), Ok(v2)) => **v1 != **v2,
+                (Err(e1), Err(e2)) => e1 != e2,
+                _ => true,
+            }
+        }
+    } else {
+        quote! { *r1 != *r2 }
+    }
+}
+
+/// Expressions that are true if the states of receivers `s1` and `s2` differ after a method call,
+/// each with the name of the compared state to report.
+///
+/// States are compared through every getter, one expression per getter. Without getters, if
+/// `compare_state` is set, the whole receivers are compared by their `Debug` representations,
+/// since they are values of two different types. Empty if the state cannot be compared.
+pub fn state_mismatches(
+    getters: &[CommonFunction],
+    compare_state: bool,
+) -> Vec<(String, TokenStream)> {
+    if getters.is_empty() {
+        return compare_state
+            .then(|| {
+                let mismatch = quote! { format!("{:?}", s1) != format!("{:?}", s2) };
+                ("state".to_owned(), mismatch)
+            })
+            .into_iter()
+            .collect();
+    }
+    getters
+        .iter()
+        .map(|getter| {
+            let getter = &getter.metadata.signature.0.ident;
+            (getter.to_string(), quote! { s1.#getter() != s2.#getter() })
+        })
+        .collect()
+}
+
+/// `let` statement binding the result of `call` to `r{index}`. If `capture` is set, the stdout and
+/// stderr written during the call are bound to `out{index}` as well (see `capture_output_helper`).
+pub fn bind_call(index: usize, call: TokenStream, capture: bool) -> TokenStream {
+    let result = format_ident!("r{}", index);
+    if capture {
+        let output = format_ident!("out{}", index);
+        quote! { let (#result, #output) = capture_output(|| #call); }
+    } else {
+        quote! { let #result = #call; }
+    }
+}
+
+/// Harness function `capture_output`, which runs a closure with stdout and stderr redirected by
+/// `gag` and returns its result together with the bytes written to stdout and stderr.
+///
+/// The panic hook is silenced meanwhile, because panic messages name the module that panicked and
+/// would always differ. Redirection is process-wide, so calls must not run concurrently.
+pub fn capture_output_helper() -> TokenStream {
+    quote! {
+        fn capture_output<R>(f: impl FnOnce() -> R) -> (R, (Vec<u8>, Vec<u8>)) {
+            use std::io::{Read, Write};
+            std::io::stdout().flush().unwrap();
+            std::io::stderr().flush().unwrap();
+            let mut stdout = gag::BufferRedirect::stdout().unwrap();
+            let mut stderr = gag::BufferRedirect::stderr().unwrap();
+            let hook = std::panic::take_hook();
+            std::panic::set_hook(Box::new(|_| {}));
+            let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+            std::panic::set_hook(hook);
+            std::io::stdout().flush().unwrap();
+            std::io::stderr().flush().unwrap();
+            let mut output = (Vec::new(), Vec::new());
+            stdout.read_to_end(&mut output.0).unwrap();
+            stderr.read_to_end(&mut output.1).unwrap();
+            match res {
+                Ok(res) => (res, output),
+                Err(payload) => std::panic::resume_unwind(payload),
+            }
+        }
+    }
+}
+
+/// `map_err` call applied to the result of `catch_unwind`. If `compare_payload` is set, a panic
+/// becomes its message (see `panic_message_helper`), so that panics with different messages
+/// differ. Otherwise every panic becomes `()` and all panics are equal.
+pub fn map_panic_payload(compare_payload: bool) -> TokenStream {
+    if compare_payload {
+        quote! { .map_err(panic_message) }
+    } else {
+        quote! { .map_err(|_| ()) }
+    }
+}
+
+/// Harness function `panic_message`, which gets the message of a panic payload. Payloads other
+/// than `&str` and `String`, e.g. those of `panic_any`, have no message.
+pub fn panic_message_helper() -> TokenStream {
+    quote! {
+        fn panic_message(payload: Box<dyn std::any::Any + Send>) -> Option<String> {
+            payload
+                .downcast_ref::<&str>()
+                .map(|msg| msg.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+        }
+    }
+}
+
+/// What a backend needs to build the harness of a method.
+pub struct MethodHarness<'a> {
+    /// The method to compare.
+    pub method: &'a CommonFunction,
+    /// Constructor of the receivers `s1` and `s2`.
+    pub constructor: &'a CommonFunction,
+    /// Getters of the receiver type, whose results are compared after the call.
+    pub getters: &'a [CommonFunction],
+    /// Set if there is no getter but the receivers can be compared as a whole (see
+    /// `state_mismatches`).
+    pub compare_state: bool,
+    /// Arguments of the method call, read from `method_arg_struct`.
+    pub method_args: Vec<TokenStream>,
+    /// Arguments of the constructor call, read from `constr_arg_struct`.
+    pub constructor_args: Vec<TokenStream>,
+    /// How the receiver is passed, e.g. `&mut`.
+    pub receiver_prefix: TokenStream,
+    /// Precondition of the method, if any.
+    pub precondition: Option<&'a Precondition>,
+    /// Condition on the receiver `s2` that must hold for the call to be compared (see
+    /// `MethodGuard`).
+    pub guard: Option<TokenStream>,
+}
+
 /// The trait capturing differences between different check/test harness backends.
 pub trait HarnessBackend {
     /// Attributes / derives to put on generated `Args*` structs.
     fn arg_struct_attrs(&self) -> TokenStream;
 
+    /// Attributes to put on an `Args*` struct field of type `ty`, whose argument the precondition
+    /// bounds to `bound`.
+    fn bounded_field_attrs(&self, _ty: &TokenStream, _bound: &ArgBound) -> TokenStream {
+        quote! {}
+    }
+
     /// Build the test function TokenStream for a free-standing function.
     fn make_harness_for_function(
         &self,
         function: &CommonFunction,
-        function_args: &[TokenStream],
+        call_args: &FunctionCallArgs,
         precondition: Option<&Precondition>,
     ) -> TokenStream;
 
     /// Build the test function TokenStream for a method.
-    fn make_harness_for_method(
-        &self,
-        method: &CommonFunction,
-        constructor: &CommonFunction,
-        getter: Option<&CommonFunction>,
-        method_args: &[TokenStream],
-        constructor_args: &[TokenStream],
-        receiver_prefix: TokenStream,
-        precondition: Option<&Precondition>,
-    ) -> TokenStream;
+    fn make_harness_for_method(&self, harness: MethodHarness) -> TokenStream;
 
     /// Other additional code pieces needed can be added as associated functions here.
     fn additional_code(&self, _classifier: &FunctionCollection) -> TokenStream {
@@ -384,3 +974,130 @@ pub trait HarnessBackend {
         additional: TokenStream,
     ) -> TokenStream;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{check::test_checker, log::init_test_logger};
+
+    /// The function under checking in `src`, which is the same in both sources.
+    fn function(src: &str) -> CommonFunction {
+        let checker = test_checker(src, src, vec![]);
+        checker.under_checking_funcs[0].clone()
+    }
+
+    #[test]
+    fn reference_returns_are_compared_by_pointee() {
+        let func = function("pub fn get(x: &u32) -> &u32 { x }");
+        assert_eq!(return_mismatch(&func, false).to_string(), "* r1 != * r2");
+        let caught = return_mismatch(&func, true).to_string();
+        assert!(caught.contains("(Ok (v1) , Ok (v2)) => * * v1 != * * v2"));
+
+        let func = function("pub fn get(x: u32) -> u32 { x }");
+        assert_eq!(return_mismatch(&func, true).to_string(), "r1 != r2");
+    }
+
+    #[test]
+    fn slice_arguments_are_stored_owned() {
+        let func = function("pub fn parse(data: &[u8]) -> usize { data.len() }");
+        let arg = typed_args(&func.metadata.signature.0)[0];
+        let (field, _) = owned_arg_type(&arg.ty).unwrap();
+        assert_eq!(field.to_string(), "Vec < u8 >");
+
+        let call_args = FunctionCallArgs::new(&func, false);
+        let mod1 = call_args.mod1.iter().map(|arg| arg.to_string());
+        assert_eq!(
+            mod1.collect::<Vec<_>>(),
+            ["function_arg_struct . data . as_slice ()"]
+        );
+    }
+
+    #[test]
+    fn mut_arguments_are_compared_after_the_call() {
+        let func = function("pub fn push(v: &mut Vec<u8>, x: u8) { v.push(x) }");
+        let call_args = FunctionCallArgs::new(&func, false);
+        let mod1 = call_args.mod1.iter().map(|arg| arg.to_string());
+        let mod1 = mod1.collect::<Vec<_>>();
+        assert_eq!(mod1, ["& mut v_mod1", "function_arg_struct . x . clone ()"]);
+        // Each module mutates its own copy
+        let setup = call_args.setup.to_string();
+        assert!(setup.contains("let mut v_mod2 = function_arg_struct . v . clone () ;"));
+        let mismatch = call_args.mut_mismatch.unwrap().to_string();
+        assert_eq!(mismatch, "v_mod1 != v_mod2");
+    }
+
+    #[test]
+    fn iterator_returns_are_collected() {
+        let func = function("pub fn evens(n: u32) -> impl Iterator<Item = u32> { 0..n }");
+        let call = collect_if_iterator(quote! { mod1::evens(n) }, &func).to_string();
+        assert_eq!(call, "(mod1 :: evens (n)) . collect :: < Vec < _ >> ()");
+        let item = func.metadata.iterator_item().unwrap();
+        assert!(is_comparable_item(item));
+
+        let func = function("pub fn count(n: u32) -> u32 { n }");
+        let call = collect_if_iterator(quote! { mod1::count(n) }, &func).to_string();
+        assert_eq!(call, "mod1 :: count (n)");
+    }
+
+    /// Backend whose method harnesses only check the method guard.
+    struct GuardBackend;
+
+    impl HarnessBackend for GuardBackend {
+        fn arg_struct_attrs(&self) -> TokenStream {
+            quote! {}
+        }
+
+        fn make_harness_for_function(
+            &self,
+            _function: &CommonFunction,
+            _call_args: &FunctionCallArgs,
+            _precondition: Option<&Precondition>,
+        ) -> TokenStream {
+            quote! {}
+        }
+
+        fn make_harness_for_method(&self, harness: MethodHarness) -> TokenStream {
+            let guard = harness.guard.unwrap_or(quote! { true });
+            quote! { if !(#guard) { return; } }
+        }
+
+        fn finalize(
+            &self,
+            _imports: Vec<TokenStream>,
+            _args_structs: Vec<TokenStream>,
+            _functions: Vec<TokenStream>,
+            methods: Vec<TokenStream>,
+            _additional: TokenStream,
+        ) -> TokenStream {
+            quote! { #(#methods)* }
+        }
+    }
+
+    #[test]
+    fn dealloc_is_guarded_by_test() {
+        init_test_logger();
+        let harness = |test: &str| {
+            let src = format!(
+                "pub struct Bits(u64);
+                impl Bits {{
+                    pub fn verieasy_new() -> Self {{ Bits(0) }}
+                    pub fn dealloc(&mut self, key: u8) {{ self.0 &= !(1 << (key % 64)); }}
+                    {}
+                }}",
+                test
+            );
+            let checker = test_checker(&src, &src, vec![]);
+            HarnessGenerator::new(&checker, GuardBackend)
+                .generate_harness()
+                .to_string()
+        };
+
+        let test = "pub fn test(&self, key: u8) -> bool { self.0 & (1 << (key % 64)) != 0 }";
+        let guarded = harness(test);
+        assert!(guarded.contains("if ! (! s2 . test (method_arg_struct . key . clone ()))"));
+        // Without a `test` method, `dealloc` is compared on every input
+        let unguarded = harness("pub fn count(&self) -> u32 { self.0.count_ones() }");
+        assert!(!unguarded.contains(". test ("));
+        assert!(unguarded.contains("if ! (true)"));
+    }
+}
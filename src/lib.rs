@@ -0,0 +1,43 @@
+//! Veri-easy functional equivalence checker.
+//!
+//! The `veri-easy` binary is a thin wrapper around this library, which can also be used to
+//! check two sources programmatically:
+//!
+//! ```ignore
+//! let workflow = WorkflowConfig::parse("workflow.toml")?;
+//! let components = workflow.construct_workflow(PrecondOverride::Default)?;
+//! let options = CheckerOptions {
+//!     fail_fast_testing: workflow.fail_fast_testing,
+//!     ..Default::default()
+//! };
+//! let mut checker = Checker::new(
+//!     Source::open("original.rs")?,
+//!     Source::open("verified.rs")?,
+//!     components,
+//!     Vec::new(),
+//!     options,
+//! );
+//! checker.run_all();
+//! ```
+//!
+//! The global logger must be initialized with [`log::init_logger`] first. Custom check
+//! components can be added to workflows with [`registry::register_component`].
+
+mod cache;
+pub mod check;
+mod collect;
+pub mod components;
+pub mod config;
+pub mod defs;
+mod generate;
+pub mod log;
+pub mod registry;
+mod utils;
+
+pub use check::{CheckResult, Checker, CheckerOptions, Component, Source};
+pub use collect::{collect_preconds, translate_preconds};
+pub use config::{
+    Alive2Config, DiffFuzzConfig, ExhaustiveConfig, ExternalFormalConfig, KaniConfig, PBTConfig,
+    PrecondOverride, VerieasyCommand, VerieasyConfig, WorkflowConfig,
+};
+pub use registry::{ComponentRegistry, register_component};
@@ -2,7 +2,10 @@
 
 use clap::ValueEnum;
 use colored::Colorize;
+use std::fs::File;
+use std::io::Write;
 use std::sync::OnceLock;
+use std::time::Instant;
 
 /// Logging level.
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -26,6 +29,15 @@ impl From<&str> for LogLevel {
     }
 }
 
+/// Log output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines with colored prefixes.
+    Text,
+    /// One JSON object per line with `level`, `type`, `message` and `timestamp` fields.
+    Json,
+}
+
 /// Message type.
 #[derive(Debug, Clone, Copy)]
 pub enum MessageType {
@@ -50,12 +62,34 @@ pub enum MessageType {
 pub struct Logger {
     /// Logger level.
     level: LogLevel,
+    /// Output format.
+    format: LogFormat,
+    /// Prefix text lines with the wall-clock time and the elapsed time since `start`.
+    timestamps: bool,
+    /// Time the logger was created.
+    start: Instant,
+    /// Optional log file, receiving all messages regardless of `level`.
+    file: OnceLock<File>,
 }
 
 impl Logger {
     /// Create a new logger.
-    pub fn new(level: LogLevel) -> Self {
-        Self { level }
+    pub fn new(level: LogLevel, format: LogFormat, timestamps: bool) -> Self {
+        Self {
+            level,
+            format,
+            timestamps,
+            start: Instant::now(),
+            file: OnceLock::new(),
+        }
+    }
+
+    /// Write all later messages, up to `Verbose`, to a log file as well.
+    pub fn set_file(&self, path: &str) -> std::io::Result<()> {
+        let file = File::create(path)?;
+        self.file
+            .set(file)
+            .map_err(|_| std::io::Error::other("log file already set"))
     }
 
     /// Get the format string for a message type.
@@ -69,23 +103,104 @@ impl Logger {
             MessageType::Error => "[Error] ".red().bold().to_string(),
             MessageType::Ok => "[Ok] ".green().bold().to_string(),
         };
-        format!("{}{}", pref, msg)
+        if self.timestamps && !(matches!(msg_type, MessageType::Simple) && msg.is_empty()) {
+            let elapsed = self.start.elapsed().as_secs_f64();
+            let time = format!("{} +{:.3}s ", iso8601_now(), elapsed).dimmed();
+            format!("{}{}{}", time, pref, msg)
+        } else {
+            format!("{}{}", pref, msg)
+        }
+    }
+
+    /// Get the JSON line for a message.
+    fn format_json(&self, level: LogLevel, msg_type: MessageType, msg: &str) -> String {
+        serde_json::json!({
+            "level": format!("{:?}", level).to_lowercase(),
+            "type": format!("{:?}", msg_type).to_lowercase(),
+            "message": msg,
+            "timestamp": iso8601_now(),
+        })
+        .to_string()
     }
 
     /// Log a message if the level is sufficient.
     pub fn log(&self, level: LogLevel, msg_type: MessageType, msg: &str) {
+        let formatted = match self.format {
+            LogFormat::Text => self.format_msg(msg_type, msg),
+            LogFormat::Json => self.format_json(level, msg_type, msg),
+        };
+        if let Some(mut file) = self.file.get() {
+            // A failing log file should not abort the check
+            let _ = writeln!(file, "{}", strip_ansi(&formatted));
+        }
         if (self.level as u8) >= (level as u8) {
-            println!("{}", self.format_msg(msg_type, msg));
+            println!("{}", formatted);
         }
     }
 }
 
+/// Remove ANSI escape sequences (colors) from a string.
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip `ESC [ params final`, where the final byte is in `@..=~`
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Current UTC time as an ISO-8601 string with millisecond precision.
+fn iso8601_now() -> String {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = now.as_secs();
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        now.subsec_millis()
+    )
+}
+
 /// Global logger instance.
 static LOGGER: OnceLock<Logger> = OnceLock::new();
 
 /// Initialize the global logger.
-pub fn init_logger(level: LogLevel) {
-    LOGGER.set(Logger::new(level)).unwrap();
+pub fn init_logger(level: LogLevel, format: LogFormat, timestamps: bool) {
+    LOGGER.set(Logger::new(level, format, timestamps)).unwrap();
+}
+
+/// Initialize the global logger for unit tests, which may share it across threads.
+#[cfg(test)]
+pub(crate) fn init_test_logger() {
+    LOGGER.get_or_init(|| Logger::new(LogLevel::Brief, LogFormat::Text, false));
 }
 
 /// Get the global logger.
@@ -114,3 +229,60 @@ macro_rules! log {
             $crate::log::LogLevel::Normal, $crate::log::MessageType::Simple, &format!($fmt, $($arg)*))
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_file_receives_all_levels() {
+        let path = std::env::temp_dir().join(format!("veri-easy-log-{}", std::process::id()));
+        let logger = Logger::new(LogLevel::Brief, LogFormat::Text, false);
+        logger.set_file(&path.to_string_lossy()).unwrap();
+        logger.log(LogLevel::Brief, MessageType::Ok, "checked");
+        logger.log(LogLevel::Verbose, MessageType::Warning, "detail");
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(content, "[Ok] checked\n[Warning] detail\n");
+        // Colors are stripped in the file
+        assert_eq!(strip_ansi("\x1b[1;34m[Info] \x1b[0mdone"), "[Info] done");
+    }
+
+    #[test]
+    fn json_lines_have_the_expected_fields() {
+        let logger = Logger::new(LogLevel::Brief, LogFormat::Json, false);
+        let line = logger.format_json(LogLevel::Normal, MessageType::Warning, "a \"quoted\" name");
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["level"], "normal");
+        assert_eq!(value["type"], "warning");
+        assert_eq!(value["message"], "a \"quoted\" name");
+        assert!(value["timestamp"].is_string());
+    }
+
+    #[test]
+    fn timestamp_prefix_is_parseable() {
+        let logger = Logger::new(LogLevel::Brief, LogFormat::Text, true);
+        let line = strip_ansi(&logger.format_msg(MessageType::Simple, "msg"));
+        let mut parts = line.split(' ');
+        let time = parts.next().unwrap();
+        let elapsed = parts.next().unwrap();
+        assert_eq!(parts.next(), Some("msg"));
+
+        // `YYYY-MM-DDTHH:MM:SS.mmmZ`
+        let fields = time
+            .split(['-', 'T', ':', '.', 'Z'])
+            .filter(|field| !field.is_empty())
+            .map(|field| field.parse::<u32>().unwrap())
+            .collect::<Vec<_>>();
+        let [year, month, day, hour, minute, second, _millis] = fields[..] else {
+            panic!("unexpected timestamp `{}`", time);
+        };
+        assert!(year >= 2024 && (1..=12).contains(&month) && (1..=31).contains(&day));
+        assert!(hour < 24 && minute < 60 && second < 60);
+        // `+S.SSSs`
+        let seconds = elapsed.trim_start_matches('+').trim_end_matches('s');
+        assert!(elapsed.starts_with('+') && elapsed.ends_with('s'));
+        assert!(seconds.parse::<f64>().unwrap() >= 0.0);
+    }
+}
@@ -1,26 +1,21 @@
 use clap::Parser;
 
-use crate::{
-    check::{Checker, Source},
-    collect::collect_preconds,
-    config::{VerieasyConfig, WorkflowConfig},
+use veri_easy::{
+    Checker, CheckerOptions, Source, VerieasyCommand, VerieasyConfig, WorkflowConfig,
+    check::{EXIT_ERROR, EXIT_OK},
+    collect_preconds, log, translate_preconds,
 };
 
-mod check;
-mod collect;
-mod components;
-mod config;
-mod defs;
-mod generate;
-mod log;
-mod utils;
-
 fn main() {
     // Parse global configuration
     let config = VerieasyConfig::parse();
 
     // Initialize logger
-    log::init_logger(config.log);
+    log::init_logger(config.log, config.log_format, config.log_timestamps);
+    let log_file_res = config
+        .log_file
+        .as_deref()
+        .map(|path| log::get_logger().set_file(path));
     log!(
         Brief,
         Critical,
@@ -28,9 +23,55 @@ fn main() {
         env!("CARGO_PKG_VERSION")
     );
     log!(Brief, Info, "Log level set to {:?}", config.log);
+    if let Some(Err(e)) = log_file_res {
+        log!(Brief, Warning, "Failed to open log file: {}", e);
+    }
+
+    // Run a standalone tool instead of the workflow
+    if let Some(VerieasyCommand::TranslatePreconds { verus_file, output }) = &config.command {
+        let exit_code = match translate_preconds(verus_file, output.as_deref()) {
+            Ok(()) => EXIT_OK,
+            Err(e) => {
+                log!(
+                    Brief,
+                    Error,
+                    "Failed to translate preconditions from {}: {}",
+                    verus_file,
+                    e
+                );
+                EXIT_ERROR
+            }
+        };
+        std::process::exit(exit_code);
+    }
+
+    // Run each workflow configuration on fresh checkers
+    let mut runs = Vec::new();
+    let mut exit_code = EXIT_OK;
+    for workflow_path in &config.config {
+        match run_workflow(&config, workflow_path) {
+            Ok(Some(checker)) => runs.push((workflow_path.as_str(), checker)),
+            Ok(None) => (),
+            Err(()) => exit_code = EXIT_ERROR,
+        }
+    }
+    if config.config.len() > 1 {
+        Checker::print_combined_summary(&runs);
+    }
 
+    // Report the worst outcome of all runs to the shell
+    let exit_code = runs
+        .iter()
+        .map(|(_, checker)| checker.exit_code())
+        .fold(exit_code, i32::max);
+    std::process::exit(exit_code);
+}
+
+/// Run the workflow configured in `workflow_path`, returning the checker after all checks, or
+/// `None` if only listing/validation was requested. Errors are logged before returning `Err`.
+fn run_workflow(config: &VerieasyConfig, workflow_path: &str) -> Result<Option<Checker>, ()> {
     // Load workflow configuration
-    let res = WorkflowConfig::parse(&config.config);
+    let res = WorkflowConfig::parse(workflow_path);
     if let Err(e) = &res {
         log!(
             Brief,
@@ -38,44 +79,73 @@ fn main() {
             "Failed to parse workflow configuration: {}",
             e
         );
-        return;
+        return Err(());
+    }
+    let mut workflow_config = res.unwrap();
+    if let Some(input) = &config.replay {
+        workflow_config
+            .diff_fuzz
+            .get_or_insert_with(Default::default)
+            .replay_input = Some(input.clone());
+    }
+    if let Some(seed) = config.seed {
+        if let Some(diff_fuzz) = workflow_config.diff_fuzz.as_mut() {
+            diff_fuzz.seed = Some(seed);
+        }
+        if let Some(pbt) = workflow_config.pbt.as_mut() {
+            pbt.seed = Some(seed);
+        }
     }
-    let workflow_config = res.unwrap();
     log!(Brief, Simple, "");
     workflow_config.log();
+    if let Err(e) = workflow_config.validate(config.strict, config.preconditions.is_some()) {
+        log!(Brief, Error, "Invalid workflow configuration: {}", e);
+        return Err(());
+    }
+    if config.validate_config {
+        log!(Brief, Ok, "Workflow configuration is valid");
+        return Ok(None);
+    }
 
     // Construct workflow components
-    let components = workflow_config.construct_workflow();
+    let mut components = match workflow_config.construct_workflow(config.use_preconditions) {
+        Ok(components) => components,
+        Err(e) => {
+            log!(Brief, Error, "Invalid workflow configuration: {}", e);
+            return Err(());
+        }
+    };
+    config.filter_components(&mut components);
 
-    // Load source files
-    let res = Source::open(&config.file1);
+    // Load source files, which are required without a subcommand
+    let file1 = config.file1.as_deref().unwrap();
+    let file2 = config.file2.as_deref().unwrap();
+    let res = Source::open(file1);
     if let Err(e) = &res {
-        log!(
-            Brief,
-            Error,
-            "Failed to open source file {}: {}",
-            &config.file1,
-            e
-        );
-        return;
+        log!(Brief, Error, "Failed to open source file {}: {}", file1, e);
+        return Err(());
     }
     let s1 = res.unwrap();
-    let res = Source::open(&config.file2);
+    let res = Source::open(file2);
     if let Err(e) = &res {
-        log!(
-            Brief,
-            Error,
-            "Failed to open source file {}: {}",
-            &config.file2,
-            e
-        );
-        return;
+        log!(Brief, Error, "Failed to open source file {}: {}", file2, e);
+        return Err(());
     }
     let mut s2 = res.unwrap();
 
     // Collect preconditions
     let (precond_code, preconditions) = if let Some(precond_path) = &config.preconditions {
-        match collect_preconds(precond_path) {
+        // `self` collects the specs inlined in source 2
+        let precond_path = if precond_path == "self" {
+            file2
+        } else {
+            precond_path
+        };
+        match collect_preconds(
+            precond_path,
+            config.enforce_recommends,
+            config.precondition_overrides.as_deref(),
+        ) {
             Ok((code, preconditions)) => (code, preconditions),
             Err(e) => {
                 log!(
@@ -91,8 +161,8 @@ fn main() {
     } else {
         (String::new(), Vec::new())
     };
-    // Append preconditions to source 2
-    s2.append_content(&precond_code);
+    // Append preconditions to source 2, except for what it already defines
+    s2.append_preconditions(&precond_code);
 
     log!(Brief, Simple, "");
     log!(
@@ -104,11 +174,47 @@ fn main() {
     );
 
     // Create checker and run workflow
-    let mut checker = Checker::new(s1, s2, components, preconditions, config.strict);
+    let options = CheckerOptions {
+        strict: config.strict,
+        strict_formal: config.strict_formal,
+        fail_fast_testing: workflow_config.fail_fast_testing,
+        cache_path: config.cache.clone(),
+        dump_harness: config.dump_harness.clone(),
+        fixed_width_usize: workflow_config.fixed_width_usize,
+        width_tolerant: workflow_config.width_tolerant,
+    };
+    let mut checker = Checker::new(s1, s2, components, preconditions, options);
+    checker.exclude_nondeterministic(
+        &workflow_config.nondeterministic,
+        &workflow_config.deterministic,
+    );
+    if let Some(baseline_path) = &config.changed_since {
+        match Source::open(baseline_path) {
+            Ok(baseline) => checker.retain_changed(&baseline),
+            Err(e) => {
+                log!(
+                    Brief,
+                    Error,
+                    "Failed to open baseline file {}: {}",
+                    baseline_path,
+                    e
+                );
+                return Err(());
+            }
+        }
+    }
+    if let Some(max) = config.max_functions {
+        checker.limit_functions(max);
+    }
+    if config.list_functions {
+        checker.print_functions();
+        return Ok(None);
+    }
     log!(Normal, Info, "Logging initial state:");
     checker.print_state();
     log!(Normal, Simple, "");
 
     checker.run_all();
     checker.print_summary();
+    Ok(Some(checker))
 }
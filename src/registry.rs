@@ -0,0 +1,213 @@
+//! Registry of check components that can be named in a workflow.
+//!
+//! Built-in components are registered by default. Downstream crates can add their own with
+//! [`register_component`] before constructing the workflow; the factory receives the TOML
+//! section named after the component (an empty table if there is none) and fails if the section
+//! is invalid. Built-in components receive their parsed configurations, so registering a
+//! component under a built-in name replaces the built-in one.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::{check::Component, components::*};
+
+/// Factory building a component from its configuration section.
+pub type ComponentFactory =
+    Box<dyn Fn(&toml::Value) -> anyhow::Result<Box<dyn Component>> + Send + Sync>;
+
+/// Component factories by component name.
+pub struct ComponentRegistry {
+    /// Registered factories, keyed by lowercase component name.
+    factories: HashMap<String, ComponentFactory>,
+}
+
+impl ComponentRegistry {
+    /// Create a registry seeded with the built-in components.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self {
+            factories: HashMap::new(),
+        };
+        registry.register("identical", |_| Ok(Box::new(Identical)));
+        registry.register("kani", |value| Ok(Box::new(Kani::new(section(value)?))));
+        registry.register("pbt", |value| {
+            Ok(Box::new(PropertyBasedTesting::new(section(value)?)))
+        });
+        registry.register("difffuzz", |value| {
+            Ok(Box::new(DifferentialFuzzing::new(section(value)?)))
+        });
+        registry.register("alive2", |value| Ok(Box::new(Alive2::new(section(value)?))));
+        registry.register("external", |value| {
+            Ok(Box::new(ExternalFormal::new(section(value)?)))
+        });
+        registry.register("exhaustive", |value| {
+            Ok(Box::new(Exhaustive::new(section(value)?)))
+        });
+        registry
+    }
+
+    /// Register a component factory, replacing any factory of the same name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&toml::Value) -> anyhow::Result<Box<dyn Component>> + Send + Sync + 'static,
+    ) {
+        self.factories.insert(normalize(name), Box::new(factory));
+    }
+
+    /// Whether a component named `name` is registered.
+    pub fn contains(&self, name: &str) -> bool {
+        self.factories.contains_key(&normalize(name))
+    }
+
+    /// Create the component named `name` from its configuration section, `None` if no such
+    /// component is registered.
+    pub fn create(
+        &self,
+        name: &str,
+        config: &toml::Value,
+    ) -> Option<anyhow::Result<Box<dyn Component>>> {
+        self.factories
+            .get(&normalize(name))
+            .map(|factory| factory(config))
+    }
+}
+
+/// Deserialize a built-in configuration section.
+fn section<T: serde::de::DeserializeOwned>(value: &toml::Value) -> anyhow::Result<T> {
+    value
+        .clone()
+        .try_into()
+        .map_err(|e| anyhow::anyhow!("Invalid configuration section: {}", e))
+}
+
+/// Lowercase component name with alternative spellings normalized.
+fn normalize(name: &str) -> String {
+    match name.to_lowercase().as_str() {
+        "diff-fuzz" | "diff_fuzz" => "difffuzz".to_owned(),
+        other => other.to_owned(),
+    }
+}
+
+/// Global component registry.
+static REGISTRY: OnceLock<Mutex<ComponentRegistry>> = OnceLock::new();
+
+/// Get the global component registry.
+pub fn get_registry() -> std::sync::MutexGuard<'static, ComponentRegistry> {
+    REGISTRY
+        .get_or_init(|| Mutex::new(ComponentRegistry::with_builtins()))
+        .lock()
+        .unwrap()
+}
+
+/// Register a custom component in the global registry, so that workflows can name it.
+pub fn register_component(
+    name: &str,
+    factory: impl Fn(&toml::Value) -> anyhow::Result<Box<dyn Component>> + Send + Sync + 'static,
+) {
+    get_registry().register(name, factory);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CheckResult, Checker,
+        check::test_checker,
+        config::{PrecondOverride, WorkflowConfig},
+        log::init_test_logger,
+    };
+
+    /// Custom component passing every function under checking.
+    struct AcceptAll {
+        formal: bool,
+    }
+
+    impl Component for AcceptAll {
+        fn name(&self) -> &str {
+            "AcceptAll"
+        }
+
+        fn is_formal(&self) -> bool {
+            self.formal
+        }
+
+        fn run(&self, checker: &Checker) -> CheckResult {
+            CheckResult {
+                ok: checker
+                    .under_checking_funcs
+                    .iter()
+                    .map(|func| func.metadata.name.clone())
+                    .collect(),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[derive(serde::Deserialize)]
+    struct AcceptAllConfig {
+        formal: bool,
+    }
+
+    fn register_accept_all() {
+        register_component("accept_all", |value| {
+            let config: AcceptAllConfig = value.clone().try_into()?;
+            Ok(Box::new(AcceptAll {
+                formal: config.formal,
+            }))
+        });
+    }
+
+    #[test]
+    fn custom_component_runs_in_workflow() {
+        init_test_logger();
+        register_accept_all();
+        let workflow: WorkflowConfig =
+            toml::from_str("components = [\"accept_all\"]\n[accept_all]\nformal = true").unwrap();
+        let components = workflow
+            .construct_workflow(PrecondOverride::Default)
+            .unwrap();
+        assert_eq!(components.len(), 1);
+
+        let mut checker = test_checker(
+            "pub fn f(x: u8) -> u8 { x }",
+            "pub fn f(x: u8) -> u8 { x + 0 }",
+            components,
+        );
+        checker.run_all();
+        assert_eq!(checker.verified_funcs.len(), 1);
+        assert!(checker.under_checking_funcs.is_empty());
+    }
+
+    #[test]
+    fn invalid_custom_section_is_an_error() {
+        init_test_logger();
+        register_accept_all();
+        let workflow: WorkflowConfig =
+            toml::from_str("components = [\"accept_all\"]\n[accept_all]\nformal = \"yes\"")
+                .unwrap();
+        let err = workflow
+            .construct_workflow(PrecondOverride::Default)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("`accept_all`"));
+    }
+
+    #[test]
+    fn override_reaches_custom_sections() {
+        init_test_logger();
+        // The component is formal exactly if it uses preconditions
+        register_component("precond_probe", |value| {
+            let used = value["use_preconditions"].as_bool().unwrap();
+            Ok(Box::new(AcceptAll { formal: used }))
+        });
+        let section = "[precond_probe]\nuse_preconditions = true";
+        let workflow: WorkflowConfig =
+            toml::from_str(&format!("components = [\"precond_probe\"]\n{}", section)).unwrap();
+        let formal = |precond_override| {
+            let components = workflow.construct_workflow(precond_override).unwrap();
+            components[0].is_formal()
+        };
+        assert!(formal(PrecondOverride::Default));
+        assert!(!formal(PrecondOverride::Off));
+    }
+}
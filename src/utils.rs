@@ -32,28 +32,18 @@ pub fn run_command(
         None
     };
 
-    // Change working directory if specified
-    let cur_dir = std::env::current_dir()
-        .map_err(|e| anyhow::anyhow!("Failed to get current directory: {}", e))?;
+    // Spawn the command, in the working directory if specified
+    let mut cmd = Command::new(program);
     if let Some(dir) = work_dir {
-        std::env::set_current_dir(dir)
-            .map_err(|e| anyhow::anyhow!("Failed to set working directory: {}", e))?;
+        cmd.current_dir(dir);
     }
-
-    // Spawn the command
-    let mut cmd = Command::new(program)
+    let mut cmd = cmd
         .args(args)
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
         .map_err(|e| anyhow::anyhow!("Failed to spawn command: {}", e))?;
 
-    // Restore original working directory
-    if work_dir.is_some() {
-        std::env::set_current_dir(cur_dir)
-            .map_err(|e| anyhow::anyhow!("Failed to restore working directory: {}", e))?;
-    }
-
     let stderr = cmd.stderr.take().expect("Failed to capture stderr");
     let stdout = cmd.stdout.take().expect("Failed to capture stdout");
 
@@ -96,12 +86,7 @@ pub fn run_command(
         .join()
         .expect("Failed to join stdout saving thread");
 
-    // Treat Kani's exit code 1 (unsure verification) as normal.
-    let is_kani_exit_1 = program == "cargo"
-    && args.iter().any(|a| *a == "kani")
-    && output.status.code() == Some(1);
-
-    if output.status.success() || is_kani_exit_1 {
+    if command_succeeded(program, args, output.status) {
         log!(
             Verbose,
             Info,
@@ -120,6 +105,58 @@ pub fn run_command(
     Ok(output.status)
 }
 
+/// Whether a command finished normally. Kani's exit code 1 (unsure verification) is treated as
+/// normal.
+fn command_succeeded(program: &str, args: &[&str], status: ExitStatus) -> bool {
+    let is_kani_exit_1 = program == "cargo" && args.contains(&"kani") && status.code() == Some(1);
+    status.success() || is_kani_exit_1
+}
+
+/// Exit codes of runs that may succeed if started again: `EX_TEMPFAIL` of `sysexits.h`, and the
+/// codes shells report for processes killed by `SIGKILL` (e.g. by the OOM killer) or `SIGTERM`.
+const TRANSIENT_EXIT_CODES: [i32; 3] = [75, 137, 143];
+
+/// Whether a run ending with `status` failed transiently, i.e. it was killed by a signal or exited
+/// with one of [`TRANSIENT_EXIT_CODES`]. Other exit codes are deterministic.
+pub fn is_transient_failure(status: ExitStatus) -> bool {
+    match status.code() {
+        Some(code) => TRANSIENT_EXIT_CODES.contains(&code),
+        None => true,
+    }
+}
+
+/// Run a command like [`run_command`], running it again up to `retries` times if it fails
+/// transiently (see [`is_transient_failure`]).
+///
+/// Failing to spawn, e.g. because the program is missing, is not retried.
+pub fn run_command_with_retries(
+    program: &str,
+    args: &[&str],
+    output_path: Option<&str>,
+    work_dir: Option<&str>,
+    retries: u32,
+) -> anyhow::Result<ExitStatus> {
+    let mut attempt = 0;
+    loop {
+        let res = run_command(program, args, output_path, work_dir);
+        let transient = res.as_ref().is_ok_and(|status| {
+            !command_succeeded(program, args, *status) && is_transient_failure(*status)
+        });
+        if !transient || attempt >= retries {
+            return res;
+        }
+        attempt += 1;
+        log!(
+            Normal,
+            Warning,
+            "Command '{}' failed transiently, retrying ({}/{})",
+            program,
+            attempt,
+            retries
+        );
+    }
+}
+
 /// Create a typical harness project directory structure. Dir structure:
 ///
 /// harness_path
@@ -171,10 +208,161 @@ pub fn create_harness_project(
         .map_err(|_| anyhow!("Failed to write Cargo.toml"))?;
 
     // Cargo fmt
-    let cur_dir = std::env::current_dir().unwrap();
-    let _ = std::env::set_current_dir(path);
-    run_command("cargo", &["fmt"], None, None)?;
-    let _ = std::env::set_current_dir(cur_dir);
+    run_command("cargo", &["fmt"], None, Some(path))?;
+
+    Ok(())
+}
 
+/// Copy the harness source of a harness project to `<dir>/<component>/main.rs`, so that it
+/// survives the cleanup of the project.
+pub fn dump_harness(dir: &str, component: &str, harness_path: &str) -> anyhow::Result<()> {
+    let dump_dir = format!("{}/{}", dir, component);
+    std::fs::create_dir_all(&dump_dir)
+        .map_err(|e| anyhow!("Failed to create harness dump directory: {}", e))?;
+    std::fs::copy(
+        format!("{}/src/main.rs", harness_path),
+        format!("{}/main.rs", dump_dir),
+    )
+    .map_err(|e| anyhow!("Failed to dump harness: {}", e))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Checker, CheckerOptions, Source, components::Exhaustive, config::ExhaustiveConfig,
+        log::init_test_logger,
+    };
+    use std::os::unix::process::ExitStatusExt;
+
+    /// Status of a process exiting with `code`.
+    fn exited(code: i32) -> ExitStatus {
+        ExitStatus::from_raw(code << 8)
+    }
+
+    /// Status of a process killed by `signal`.
+    fn killed(signal: i32) -> ExitStatus {
+        ExitStatus::from_raw(signal)
+    }
+
+    #[test]
+    fn kani_exit_1_succeeds() {
+        assert!(command_succeeded("cargo", &["build"], exited(0)));
+        assert!(command_succeeded("cargo", &["kani", "--tests"], exited(1)));
+        assert!(!command_succeeded("cargo", &["build"], exited(1)));
+        assert!(!command_succeeded("cargo", &["kani"], exited(2)));
+        assert!(!command_succeeded("kani", &[], exited(1)));
+    }
+
+    #[test]
+    fn transient_failures() {
+        assert!(is_transient_failure(killed(9)));
+        assert!(is_transient_failure(exited(75)));
+        assert!(is_transient_failure(exited(137)));
+        assert!(!is_transient_failure(exited(1)));
+        assert!(!is_transient_failure(exited(101)));
+    }
+
+    #[test]
+    fn transient_failure_is_retried() {
+        init_test_logger();
+        let counter = std::env::temp_dir().join(format!("veri-easy-retry-{}", std::process::id()));
+        let counter = counter.to_string_lossy();
+        // Fail with EX_TEMPFAIL on the first attempt, succeed on the second
+        let script = format!(
+            "echo x >> '{0}'; [ $(wc -l < '{0}') -ge 2 ] || exit 75",
+            counter
+        );
+        let status = run_command_with_retries("sh", &["-c", &script], None, None, 2).unwrap();
+        let attempts = std::fs::read_to_string(counter.as_ref())
+            .unwrap()
+            .lines()
+            .count();
+        std::fs::remove_file(counter.as_ref()).unwrap();
+        assert!(status.success());
+        assert_eq!(attempts, 2);
+
+        // Deterministic exit codes and spawn failures are returned at once
+        let status = run_command_with_retries("sh", &["-c", "exit 3"], None, None, 2).unwrap();
+        assert_eq!(status.code(), Some(3));
+        assert!(run_command_with_retries("/nonexistent/program", &[], None, None, 2).is_err());
+    }
+
+    #[test]
+    fn command_runs_in_work_dir() {
+        init_test_logger();
+        let dir = std::env::temp_dir().join(format!("veri-easy-workdir-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let output = dir.join("pwd.txt");
+        let cur_dir = std::env::current_dir().unwrap();
+        let work_dir = dir.canonicalize().unwrap();
+        let status = run_command(
+            "pwd",
+            &["-P"],
+            Some(&output.to_string_lossy()),
+            Some(&dir.to_string_lossy()),
+        )
+        .unwrap();
+        let pwd = std::fs::read_to_string(&output).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // Only the child runs in `work_dir`, so concurrent commands do not interfere
+        assert!(status.success());
+        assert_eq!(pwd.trim(), work_dir.to_string_lossy());
+        assert_eq!(std::env::current_dir().unwrap(), cur_dir);
+    }
+
+    #[test]
+    fn harnesses_are_dumped_per_component() {
+        let dir = std::env::temp_dir().join(format!("veri-easy-dump-{}", std::process::id()));
+        let dump_dir = dir.join("dump").to_string_lossy().into_owned();
+        for component in ["kani", "pbt"] {
+            let harness_path = dir.join(format!("{}_harness", component));
+            std::fs::create_dir_all(harness_path.join("src")).unwrap();
+            let source = format!("// {} harness", component);
+            std::fs::write(harness_path.join("src/main.rs"), source).unwrap();
+            dump_harness(&dump_dir, component, &harness_path.to_string_lossy()).unwrap();
+            // The dump outlives the harness project
+            std::fs::remove_dir_all(&harness_path).unwrap();
+        }
+        let kani = std::fs::read_to_string(format!("{}/kani/main.rs", dump_dir));
+        let pbt = std::fs::read_to_string(format!("{}/pbt/main.rs", dump_dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(kani.unwrap(), "// kani harness");
+        assert_eq!(pbt.unwrap(), "// pbt harness");
+    }
+
+    #[test]
+    fn backend_dumps_its_harness() {
+        init_test_logger();
+        let src = "pub fn half(x: u8) -> u8 { x / 2 }";
+        let dir =
+            std::env::temp_dir().join(format!("veri-easy-backend-dump-{}", std::process::id()));
+        let harness_path = dir.join("harness");
+        let config = ExhaustiveConfig {
+            harness_path: harness_path.to_string_lossy().into_owned(),
+            output_path: dir.join("exhaustive.tmp").to_string_lossy().into_owned(),
+            ..Default::default()
+        };
+        let options = CheckerOptions {
+            dump_harness: Some(dir.join("dump").to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+        let mut checker = Checker::new(
+            Source::from_string("a.rs", src).unwrap(),
+            Source::from_string("b.rs", src).unwrap(),
+            vec![Box::new(Exhaustive::new(config))],
+            Vec::new(),
+            options,
+        );
+        checker.run_all();
+        let harness_removed = !harness_path.exists();
+        let dumped = std::fs::read_to_string(dir.join("dump/exhaustive/main.rs"));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        assert!(harness_removed);
+        assert!(dumped.unwrap().contains("half"));
+    }
+}
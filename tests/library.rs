@@ -0,0 +1,83 @@
+//! Use the library API to check two sources without going through the binary.
+
+use std::sync::Once;
+
+use veri_easy::{
+    Checker, CheckerOptions, ExternalFormalConfig, Source,
+    components::{ExternalFormal, Identical},
+    log::{self, LogFormat, LogLevel},
+};
+
+/// Initialize the global logger once for all tests.
+fn init_logger() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| log::init_logger(LogLevel::Brief, LogFormat::Text, false));
+}
+
+#[test]
+fn identical_end_to_end() {
+    init_logger();
+    let src1 = Source::from_string(
+        "a.rs",
+        "pub fn add(a: u8, b: u8) -> u8 { a.wrapping_add(b) }
+         pub fn sub(a: u8, b: u8) -> u8 { a.wrapping_sub(b) }",
+    )
+    .unwrap();
+    let src2 = Source::from_string(
+        "b.rs",
+        "pub fn add(a: u8, b: u8) -> u8 {
+             a.wrapping_add(b)
+         }
+         pub fn sub(a: u8, b: u8) -> u8 { b.wrapping_sub(a) }",
+    )
+    .unwrap();
+    let mut checker = Checker::new(
+        src1,
+        src2,
+        vec![Box::new(Identical)],
+        Vec::new(),
+        CheckerOptions::default(),
+    );
+    checker.run_all();
+
+    let verified: Vec<String> = checker
+        .verified_funcs
+        .iter()
+        .map(|f| f.metadata.name.to_string())
+        .collect();
+    assert_eq!(verified, ["add"]);
+    assert_eq!(checker.under_checking_funcs.len(), 1);
+    assert_eq!(checker.exit_code(), veri_easy::check::EXIT_UNCHECKED);
+}
+
+#[test]
+fn check_from_strings_writes_source_files() {
+    init_logger();
+    let output = std::env::temp_dir().join(format!("veri-easy-external-{}", std::process::id()));
+    // The verifier accepts `sub` only if both source files exist
+    let external = ExternalFormal::new(ExternalFormalConfig {
+        verifier_path: "sh".to_owned(),
+        args: vec![
+            "-c".to_owned(),
+            "test -f {file1} && test -f {file2} && echo SUCCESS: sub".to_owned(),
+        ],
+        output_path: output.to_string_lossy().into_owned(),
+        ..Default::default()
+    });
+    let checker = Checker::check_from_strings(
+        "pub fn add(a: u8, b: u8) -> u8 { a.wrapping_add(b) }
+         pub fn sub(a: u8, b: u8) -> u8 { a.wrapping_sub(b) }",
+        "pub fn add(a: u8, b: u8) -> u8 { a.wrapping_add(b) }
+         pub fn sub(a: u8, b: u8) -> u8 { a.wrapping_add(b.wrapping_neg()) }",
+        vec![Box::new(Identical), Box::new(external)],
+    )
+    .unwrap();
+
+    let verified: Vec<String> = checker
+        .verified_funcs
+        .iter()
+        .map(|f| f.metadata.name.to_string())
+        .collect();
+    assert_eq!(verified, ["add", "sub"]);
+    assert!(!std::path::Path::new(&checker.src1.path).exists());
+}